@@ -0,0 +1,19 @@
+#version 330 core
+in vec2 uv;
+
+uniform sampler2D image;
+// one texel along the direction of the blur
+uniform vec2 direction;
+
+out vec4 frag_color;
+
+const float weights[5] = float[](0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+
+void main() {
+    vec3 color = texture(image, uv).rgb * weights[0];
+    for (int i = 1; i < 5; i++) {
+        color += texture(image, uv + direction * float(i)).rgb * weights[i];
+        color += texture(image, uv - direction * float(i)).rgb * weights[i];
+    }
+    frag_color = vec4(color, 1.0);
+}
@@ -0,0 +1,11 @@
+#version 330 core
+in vec2 uv;
+
+uniform sampler2D image;
+uniform float intensity;
+
+out vec4 frag_color;
+
+void main() {
+    frag_color = vec4(texture(image, uv).rgb * intensity, 1.0);
+}
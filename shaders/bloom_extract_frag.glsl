@@ -0,0 +1,14 @@
+#version 330 core
+in vec2 uv;
+
+uniform sampler2D image;
+uniform float threshold;
+
+out vec4 frag_color;
+
+void main() {
+    vec3 color = texture(image, uv).rgb;
+    float brightness = max(color.r, max(color.g, color.b));
+    // keep only the part of the color that is above the threshold
+    frag_color = vec4(color * max(brightness - threshold, 0.0) / max(brightness, 0.0001), 1.0);
+}
@@ -0,0 +1,12 @@
+#version 330 core
+layout (location = 0) in vec3 Ipos;
+layout (location = 1) in vec4 _color;
+
+uniform mat4 camera_matrix;
+
+out vec4 color;
+
+void main() {
+    gl_Position = camera_matrix * vec4(Ipos.x, Ipos.y, Ipos.z, 1.0);
+    color = _color;
+}
@@ -0,0 +1,14 @@
+in vec2 uv;
+
+uniform sampler2D scene_depth;
+uniform float near;
+uniform float far;
+
+out vec4 frag_color;
+
+void main() {
+    // linearize_depth is prepended by DepthView
+    float distance = linearize_depth(texture(scene_depth, uv).r, near, far);
+    float gray = 1.0 - clamp((distance - near) / (far - near), 0.0, 1.0);
+    frag_color = vec4(vec3(gray), 1.0);
+}
@@ -1,10 +1,23 @@
 #version 330 core
 
 in vec2 tex_coord;
+in vec3 normal;
 
 uniform sampler2D tex_color;
 uniform mat4 proj;
+// the direction the light shines in, meshes without normals aren't lit
+uniform vec3 light_dir = vec3(0.0, -1.0, 0.0);
+// back faces use the flipped normal so thin surfaces look the same from both sides
+uniform bool two_sided;
 
 void main() {
-    gl_FragColor = texture(tex_color, tex_coord);
-}
\ No newline at end of file
+    vec4 color = texture(tex_color, tex_coord);
+    if (dot(normal, normal) > 0.0) {
+        vec3 n = normalize(normal);
+        if (two_sided && !gl_FrontFacing) {
+            n = -n;
+        }
+        color.rgb *= 0.2 + 0.8 * max(dot(n, -normalize(light_dir)), 0.0);
+    }
+    gl_FragColor = color;
+}
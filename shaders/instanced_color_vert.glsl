@@ -0,0 +1,13 @@
+#version 330 core
+layout (location = 0) in vec3 Ipos;
+layout (location = 8) in vec4 instance_color;
+layout (location = 9) in vec3 instance_offset;
+
+uniform mat4 camera_matrix;
+
+out vec4 color;
+
+void main() {
+    gl_Position = camera_matrix * vec4(Ipos + instance_offset, 1.0);
+    color = instance_color;
+}
@@ -0,0 +1,11 @@
+#version 330 core
+layout (location = 0) in vec3 Ipos;
+
+uniform mat4 camera_matrix;
+uniform vec3 outline_center;
+uniform float outline_scale;
+
+void main() {
+    vec3 pos = outline_center + (Ipos - outline_center) * outline_scale;
+    gl_Position = camera_matrix * vec4(pos, 1.0);
+}
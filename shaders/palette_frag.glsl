@@ -0,0 +1,14 @@
+#version 330 core
+in vec2 uv;
+
+// R8 texture where every texel is an index into the palette
+uniform sampler2D index_texture;
+// 256x1 RGBA texture with one color per index
+uniform sampler2D palette;
+
+out vec4 final_color;
+
+void main() {
+    float index = texture(index_texture, uv).r * 255.0;
+    final_color = texture(palette, vec2((index + 0.5) / 256.0, 0.5));
+}
@@ -0,0 +1,23 @@
+#version 330 core
+in vec3 world_pos;
+in vec3 world_normal;
+
+uniform vec3 camera_pos;
+uniform samplerCube environment;
+uniform vec4 base_color;
+uniform float reflectivity;
+// back faces use the flipped normal so thin surfaces look the same from both sides
+uniform bool two_sided;
+
+out vec4 frag_color;
+
+void main() {
+    vec3 view = normalize(world_pos - camera_pos);
+    vec3 normal = normalize(world_normal);
+    if (two_sided && !gl_FrontFacing) {
+        normal = -normal;
+    }
+    vec3 reflected = reflect(view, normal);
+    vec4 environment_color = texture(environment, reflected);
+    frag_color = vec4(mix(base_color.rgb, environment_color.rgb, reflectivity), base_color.a);
+}
@@ -0,0 +1,16 @@
+#version 330 core
+layout (location = 0) in vec3 Ipos;
+layout (location = 1) in vec3 Inormal;
+
+uniform mat4 camera_matrix;
+uniform mat4 u_model;
+
+out vec3 world_pos;
+out vec3 world_normal;
+
+void main() {
+    vec4 pos = u_model * vec4(Ipos, 1.0);
+    world_pos = pos.xyz;
+    world_normal = mat3(transpose(inverse(u_model))) * Inormal;
+    gl_Position = camera_matrix * pos;
+}
@@ -0,0 +1,27 @@
+#version 330 core
+in vec2 uv;
+
+// single channel atlas where 0.5 is the edge of a glyph
+uniform sampler2D atlas;
+uniform vec4 text_color;
+uniform vec4 outline_color;
+// half the width of the antialiased edge in distance units
+uniform float smoothing;
+// how far outside the edge the outline goes in distance units, 0 for no outline
+uniform float outline_width;
+
+out vec4 final_color;
+
+void main() {
+    float distance = texture(atlas, uv).r;
+    float alpha = smoothstep(0.5 - smoothing, 0.5 + smoothing, distance);
+
+    if (outline_width > 0.0) {
+        float outline_edge = 0.5 - outline_width;
+        float outline_alpha = smoothstep(outline_edge - smoothing, outline_edge + smoothing, distance);
+        vec4 color = mix(outline_color, text_color, alpha);
+        final_color = vec4(color.rgb, color.a * outline_alpha);
+    } else {
+        final_color = vec4(text_color.rgb, text_color.a * alpha);
+    }
+}
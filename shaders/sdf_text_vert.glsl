@@ -0,0 +1,12 @@
+#version 330 core
+layout (location = 0) in vec2 Ipos;
+layout (location = 1) in vec2 _uv;
+
+uniform mat4 projection;
+
+out vec2 uv;
+
+void main() {
+    gl_Position = projection * vec4(Ipos, 0.0, 1.0);
+    uv = _uv;
+}
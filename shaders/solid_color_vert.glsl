@@ -0,0 +1,8 @@
+#version 330 core
+layout (location = 0) in vec3 Ipos;
+
+uniform mat4 camera_matrix;
+
+void main() {
+    gl_Position = camera_matrix * vec4(Ipos, 1.0);
+}
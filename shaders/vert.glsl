@@ -1,16 +1,21 @@
 #version 330 core
 layout (location = 0) in vec3 Ipos;
 layout (location = 1) in vec2 _tex_coord;
+// meshes without normals get the default (0, 0, 0) and aren't lit by frag.glsl
+layout (location = 2) in vec3 _normal;
 
 uniform mat4 camera_matrix;
-// uniform mat4 model;
+// the model matrix of World::draw_mesh_transformed, meshes drawn without one aren't moved
+uniform mat4 u_model = mat4(1.0);
 // uniform mat4 view;
 // uniform mat4 proj;
 
 out vec2 tex_coord;
+out vec3 normal;
 out vec4 pos;
 
 void main() {
-    gl_Position = camera_matrix * vec4(Ipos.x, Ipos.y, Ipos.z, 1.0);
+    gl_Position = camera_matrix * u_model * vec4(Ipos.x, Ipos.y, Ipos.z, 1.0);
     tex_coord = _tex_coord;
+    normal = mat3(transpose(inverse(u_model))) * _normal;
 }
\ No newline at end of file
@@ -1,4 +1,30 @@
+/// For camera
+pub mod camera;
+/// For clock
+pub mod clock;
+/// For camera controllers
+pub mod controller;
+/// For entities spawned at runtime
+pub mod entity;
+/// For events
+pub mod event;
+/// For input
+pub mod input;
 /// For mesh
 pub mod mesh;
+/// For minimaps
+pub mod minimap;
+/// For mouse
+pub mod mouse;
+/// For object
+pub mod object;
+/// For scenes
+pub mod scene;
+/// For timers
+pub mod scheduler;
+/// For transform
+pub mod transform;
+/// For world
+pub mod world;
 
 pub use specs::{prelude::*, *};
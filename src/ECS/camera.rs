@@ -2,8 +2,24 @@ use super::object::Object;
 use super::world::GameObjectTrait;
 use crate::graphics::shader::ShaderProgram;
 use crate::graphics::uniform::Uniform;
+use crate::math::up_axis;
 use nalgebra_glm::*;
 
+/// Which way +z points relative to +x and +y in the world, it decides how
+/// [CameraTrait::view_matrix] and [CameraTrait::projection_matrix] are made
+///
+/// Assets from left handed tools look mirrored in a right handed world. Switching handedness
+/// also flips the winding of triangles on screen, see
+/// [front_face](crate::graphics::front_face)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Handedness {
+    /// The camera looks down -z in view space, like opengl
+    #[default]
+    Right,
+    /// The camera looks down +z in view space, like DirectX
+    Left,
+}
+
 /// Builder for [CameraSettings]
 ///
 /// # Example
@@ -15,7 +31,11 @@ use nalgebra_glm::*;
 ///     .shader_program(&shader_program)
 ///     // Here are the optional ones, they are filled with these default values
 ///     .fov(45.0)
-///     .sensitivity(1.0)
+///     .sensitivity(1.0) // sets both sensitivity_x and sensitivity_y
+///     .sensitivity_x(1.0)
+///     .sensitivity_y(1.0)
+///     .invert_y(false)
+///     .handedness(Handedness::Right)
 ///     .near_plane(0.1)
 ///     .far_plane(100.0)
 ///     .build() // And finally build
@@ -26,8 +46,14 @@ pub struct CameraSettingsBuilder {
     screen_size: Option<Vec2>,
     /// FOV of the camera(in degrees)
     fov: f32,
-    /// Sensitivity of the mouse
-    sensitivity: f32,
+    /// Horizontal sensitivity of the mouse
+    sensitivity_x: f32,
+    /// Vertical sensitivity of the mouse
+    sensitivity_y: f32,
+    /// Whether moving the mouse up looks down
+    invert_y: bool,
+    /// The handedness of the view and projection matrices
+    handedness: Handedness,
     /// Anything below this value will be clipped
     near_plane: f32,
     /// Anything above this value will be clipped
@@ -42,7 +68,10 @@ impl CameraSettingsBuilder {
         CameraSettingsBuilder {
             screen_size: None,
             fov: 45.0,
-            sensitivity: 1.0,
+            sensitivity_x: 1.0,
+            sensitivity_y: 1.0,
+            invert_y: false,
+            handedness: Handedness::Right,
             near_plane: 0.1,
             far_plane: 100.0,
             shader_program: None,
@@ -61,9 +90,34 @@ impl CameraSettingsBuilder {
         self
     }
 
-    /// This function is supposed to set the sensitivity of the mouse. It is optional
+    /// This function is supposed to set the sensitivity of the mouse on both axes. It is optional
     pub fn sensitivity(&mut self, sensitivity: f32) -> &mut Self {
-        self.sensitivity = sensitivity;
+        self.sensitivity_x = sensitivity;
+        self.sensitivity_y = sensitivity;
+        self
+    }
+
+    /// This function is supposed to set the horizontal sensitivity of the mouse. It is optional
+    pub fn sensitivity_x(&mut self, sensitivity_x: f32) -> &mut Self {
+        self.sensitivity_x = sensitivity_x;
+        self
+    }
+
+    /// This function is supposed to set the vertical sensitivity of the mouse. It is optional
+    pub fn sensitivity_y(&mut self, sensitivity_y: f32) -> &mut Self {
+        self.sensitivity_y = sensitivity_y;
+        self
+    }
+
+    /// This function is supposed to set invert_y. It is optional
+    pub fn invert_y(&mut self, invert_y: bool) -> &mut Self {
+        self.invert_y = invert_y;
+        self
+    }
+
+    /// This function is supposed to set the handedness. It is optional
+    pub fn handedness(&mut self, handedness: Handedness) -> &mut Self {
+        self.handedness = handedness;
         self
     }
 
@@ -91,10 +145,13 @@ impl CameraSettingsBuilder {
     pub fn build(&self) -> CameraSettings {
         CameraSettings {
             screen_size: self.screen_size.expect("Error: argument screen width is not satisfied\nhelp: you can call .screen_width"),
-            fov: 45.0,
-            sensitivity: self.sensitivity,
-            near_plane: 0.1,
-            far_plane: 100.0,
+            fov: self.fov,
+            sensitivity_x: self.sensitivity_x,
+            sensitivity_y: self.sensitivity_y,
+            invert_y: self.invert_y,
+            handedness: self.handedness,
+            near_plane: self.near_plane,
+            far_plane: self.far_plane,
             shader_program: self.shader_program.expect("Error: argument shadeer program is not satisfied\nhelp: you can call .shader_program"),
         }
     }
@@ -106,7 +163,10 @@ impl Default for CameraSettingsBuilder {
         CameraSettingsBuilder {
             screen_size: None,
             fov: 45.0,
-            sensitivity: 1.0,
+            sensitivity_x: 1.0,
+            sensitivity_y: 1.0,
+            invert_y: false,
+            handedness: Handedness::Right,
             near_plane: 0.1,
             far_plane: 100.0,
             shader_program: None,
@@ -133,8 +193,14 @@ pub struct CameraSettings {
     pub screen_size: Vec2,
     /// FOV of the camera(in degrees)
     pub fov: f32,
-    /// Sensitivity of the mouse
-    pub sensitivity: f32,
+    /// Horizontal sensitivity of the mouse
+    pub sensitivity_x: f32,
+    /// Vertical sensitivity of the mouse
+    pub sensitivity_y: f32,
+    /// Whether moving the mouse up looks down instead of up
+    pub invert_y: bool,
+    /// The handedness of the view and projection matrices
+    pub handedness: Handedness,
     /// anything below this value will be clipped
     pub near_plane: f32,
     /// anything above this value will be clipped
@@ -143,6 +209,45 @@ pub struct CameraSettings {
     pub shader_program: ShaderProgram,
 }
 
+impl CameraSettings {
+    /// Turns how far the mouse moved in window coordinates(+y is down) into how much to yaw and
+    /// pitch the camera, the output is (yaw, pitch)
+    ///
+    /// Each axis is scaled by its own sensitivity, moving the mouse up increases the pitch unless
+    /// [CameraSettings::invert_y] is true
+    pub fn look_delta(&self, mouse_delta: Vec2) -> Vec2 {
+        let pitch = if self.invert_y {
+            mouse_delta.y
+        } else {
+            -mouse_delta.y
+        };
+        vec2(
+            mouse_delta.x * self.sensitivity_x,
+            pitch * self.sensitivity_y,
+        )
+    }
+
+    /// The perspective projection matrix of the settings, it is made with their [Handedness],
+    /// see [CameraTrait::projection_matrix]
+    pub fn projection_matrix(&self) -> Mat4 {
+        let aspect = self.screen_size.x / self.screen_size.y;
+        match self.handedness {
+            Handedness::Right => perspective(
+                aspect,
+                self.fov.to_radians(),
+                self.near_plane,
+                self.far_plane,
+            ),
+            Handedness::Left => perspective_lh(
+                aspect,
+                self.fov.to_radians(),
+                self.near_plane,
+                self.far_plane,
+            ),
+        }
+    }
+}
+
 /// Camera trait responsible for the Camera struct. TODO: move Camera into Camera, ContorllabeMouse ... and users can implement
 ///
 /// You dont have to implement matrix. You do however need to implement get_camera_settings for the
@@ -157,27 +262,65 @@ pub struct CameraSettings {
 /// }
 /// ```
 pub trait CameraTrait<GameObject: GameObjectTrait + Sized>: Object<GameObject> {
-    /// Creates a new matrix from the camera position and parameters
-    fn matrix(&self) {
-        let settings = self.get_camera_settings();
+    /// The normalized direction the camera is looking in, the xyz of its rotation is the point
+    /// it looks at relative to its position. A camera with a zero direction looks along the
+    /// forward of [crate::math::up_axis]
+    fn forward(&self) -> Vec3 {
+        self.get_rot()
+            .xyz()
+            .try_normalize(f32::EPSILON)
+            .unwrap_or_else(|| up_axis().forward())
+    }
+
+    /// The view matrix from the position, [CameraTrait::forward] and up direction of the
+    /// camera, it is made with the [Handedness] of the camera settings
+    fn view_matrix(&self) -> Mat4 {
+        let center = self.get_pos() + self.forward();
+        match self.get_camera_settings().handedness {
+            Handedness::Right => look_at(self.get_pos(), &center, &self.get_up()),
+            Handedness::Left => look_at_lh(self.get_pos(), &center, &self.get_up()),
+        }
+    }
 
-        let view = look_at(
-            self.get_pos(),
-            &(self.get_pos() + self.get_rot().xyz()),
-            &vec3(0.0, 1.0, 0.0),
-        );
-        let proj = perspective::<f32>(
-            settings.screen_size.x / settings.screen_size.y,
-            settings.fov.to_radians(),
-            settings.near_plane,
-            settings.far_plane,
-        );
+    /// The perspective projection matrix from the camera settings, it is made with their
+    /// [Handedness]
+    fn projection_matrix(&self) -> Mat4 {
+        self.get_camera_settings().projection_matrix()
+    }
+
+    /// The view projection matrix, projection_matrix * view_matrix
+    fn view_projection(&self) -> Mat4 {
+        self.projection_matrix() * self.view_matrix()
+    }
 
+    /// Creates a new matrix from the camera position and parameters and sets the camera uniform
+    /// to it
+    fn matrix(&self) {
         Uniform::new(
             &self.get_camera_settings().shader_program,
             &self.get_camera_uniform(),
         )
-        .set_uniform_matrix(false, (proj * view).into())
+        .set_uniform_matrix(false, self.view_projection().into())
+    }
+
+    /// Gets the up direction of the camera, it is the up of [crate::math::up_axis] by default.
+    /// Override it to roll the camera e.g. with [crate::ECS::controller::SixDofController::up]
+    fn get_up(&self) -> Vec3 {
+        up_axis().up()
+    }
+
+    /// Moves the camera back along the direction it is looking in until the bounding sphere
+    /// (center, radius) fits on screen, e.g. with [crate::ECS::mesh::Mesh::bounding_sphere]
+    ///
+    /// The rotation is kept, only the position changes. The sphere is fit to the smaller of the
+    /// horizontal and vertical field of view
+    fn frame(&mut self, (center, radius): (Vec3, f32)) {
+        let settings = self.get_camera_settings();
+        let half_fov = settings.fov.to_radians() / 2.0;
+        let aspect = settings.screen_size.x / settings.screen_size.y;
+        let half_fov = half_fov.min((half_fov.tan() * aspect).atan());
+
+        *self.set_pos() = center - self.forward() * (radius / half_fov.sin());
     }
 
     /// Get the camera settings
@@ -186,3 +329,68 @@ pub trait CameraTrait<GameObject: GameObjectTrait + Sized>: Object<GameObject> {
     /// Gets the camera's uniform
     fn get_camera_uniform(&self) -> String;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> CameraSettingsBuilder {
+        let mut builder = CameraSettingsBuilder::new();
+        builder
+            .screen_size(vec2(800.0, 600.0))
+            .shader_program(ShaderProgram(0))
+            .sensitivity_x(2.0)
+            .sensitivity_y(0.5);
+        builder
+    }
+
+    #[test]
+    fn invert_y_flips_the_pitch_only() {
+        // the mouse moved right and up, +y is down in window coordinates
+        let mouse_delta = vec2(4.0, -2.0);
+        let look = settings().build().look_delta(mouse_delta);
+        assert_eq!(look, vec2(8.0, 1.0));
+
+        let inverted = settings().invert_y(true).build().look_delta(mouse_delta);
+        assert_eq!(inverted, vec2(8.0, -1.0));
+    }
+
+    #[test]
+    fn the_builder_keeps_the_projection_settings() {
+        let settings = settings()
+            .fov(60.0)
+            .near_plane(0.5)
+            .far_plane(50.0)
+            .handedness(Handedness::Left)
+            .build();
+        assert_eq!(settings.fov, 60.0);
+        assert_eq!(settings.near_plane, 0.5);
+        assert_eq!(settings.far_plane, 50.0);
+        assert_eq!(settings.handedness, Handedness::Left);
+    }
+
+    #[test]
+    fn the_handedness_decides_which_way_is_in_front() {
+        // a point on the near plane in view space, in front of a left handed camera only
+        let near = vec4(0.0, 0.0, 0.5, 1.0);
+        let mut builder = settings();
+        builder.near_plane(0.5).far_plane(50.0);
+
+        let left = builder
+            .handedness(Handedness::Left)
+            .build()
+            .projection_matrix()
+            * near;
+        assert!((left.z / left.w + 1.0).abs() < 1e-5);
+        let right = builder
+            .handedness(Handedness::Right)
+            .build()
+            .projection_matrix()
+            * near;
+        assert!(right.w < 0.0);
+
+        let behind = vec4(0.0, 0.0, -0.5, 1.0);
+        let right = builder.build().projection_matrix() * behind;
+        assert!((right.z / right.w + 1.0).abs() < 1e-5);
+    }
+}
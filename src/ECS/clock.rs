@@ -0,0 +1,132 @@
+use std::time::{Duration, Instant};
+
+/// A function that returns the time since the clock started, see [FrameClock::with_source]
+pub type TimeSource = Box<dyn Fn() -> Duration>;
+
+/// Counts the frames and the time of an [Enviroment](super::world::Enviroment)
+///
+/// The time is read from a [TimeSource] which is the real time by default, tests and replays can
+/// give it their own so the time only moves when they say so
+///
+/// # Example
+/// ```
+/// let now = Rc::new(Cell::new(Duration::ZERO));
+/// let source = now.clone();
+/// let mut clock = FrameClock::with_source(Box::new(move || source.get()));
+///
+/// now.set(Duration::from_secs(2));
+/// clock.next_frame();
+/// assert_eq!(clock.time(), 2.0);
+/// assert_eq!(clock.delta_time(), 2.0);
+/// ```
+pub struct FrameClock {
+    source: TimeSource,
+    /// The time of the last frame
+    last_frame: Duration,
+    /// Seconds between the last two frames
+    delta_time: f32,
+    /// How many frames were counted
+    frame: u32,
+}
+
+impl FrameClock {
+    /// Creates a clock that reads the real time, it starts now
+    pub fn new() -> Self {
+        let start = Instant::now();
+        Self::with_source(Box::new(move || start.elapsed()))
+    }
+
+    /// Creates a clock that reads the time from source
+    pub fn with_source(source: TimeSource) -> Self {
+        let last_frame = source();
+        FrameClock {
+            source,
+            last_frame,
+            delta_time: 0.0,
+            frame: 0,
+        }
+    }
+
+    /// Reads the time from source from now on, the frame count is kept and the next frame
+    /// measures its delta time from the time of source
+    pub fn set_source(&mut self, source: TimeSource) {
+        self.source = source;
+        self.skip();
+    }
+
+    /// The time since the clock started
+    pub fn now(&self) -> Duration {
+        (self.source)()
+    }
+
+    /// Seconds since the clock started
+    pub fn time(&self) -> f32 {
+        self.now().as_secs_f32()
+    }
+
+    /// How many frames were counted
+    pub fn frame(&self) -> u32 {
+        self.frame
+    }
+
+    /// Seconds between the last two frames
+    pub fn delta_time(&self) -> f32 {
+        self.delta_time
+    }
+
+    /// Counts a frame and measures the time since the last one
+    pub fn next_frame(&mut self) {
+        let now = self.now();
+        self.delta_time = now.saturating_sub(self.last_frame).as_secs_f32();
+        self.last_frame = now;
+        self.frame = self.frame.wrapping_add(1);
+    }
+
+    /// Forgets the time since the last frame, e.g. after a pause, so the next frame dosen't
+    /// measure it
+    pub fn skip(&mut self) {
+        self.last_frame = self.now();
+    }
+}
+
+impl Default for FrameClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    fn manual_clock() -> (FrameClock, Rc<Cell<Duration>>) {
+        let now = Rc::new(Cell::new(Duration::ZERO));
+        let source = now.clone();
+        (FrameClock::with_source(Box::new(move || source.get())), now)
+    }
+
+    #[test]
+    fn time_follows_the_source() {
+        let (mut clock, now) = manual_clock();
+        for frame in 1..=4 {
+            now.set(Duration::from_millis(500 * frame));
+            clock.next_frame();
+        }
+        assert!((clock.time() - 2.0).abs() < 1e-6);
+        assert_eq!(clock.frame(), 4);
+        assert!((clock.delta_time() - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn skip_forgets_the_paused_time() {
+        let (mut clock, now) = manual_clock();
+        now.set(Duration::from_secs(10));
+        clock.skip();
+        now.set(Duration::from_secs(11));
+        clock.next_frame();
+        assert_eq!(clock.delta_time(), 1.0);
+        assert_eq!(clock.frame(), 1);
+    }
+}
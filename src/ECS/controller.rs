@@ -0,0 +1,406 @@
+use crate::graphics::culling::Aabb;
+use crate::math::{move_towards, project_on_plane, up_axis};
+use crate::ECS::camera::CameraSettings;
+use device_query::Keycode;
+use nalgebra_glm::*;
+
+/// A 6 degrees of freedom controller for space and flight games
+///
+/// Movement happens along the local forward, right and up axes of the camera and rotation is
+/// done with a quaternion so the camera can pitch, yaw and roll without gimbal lock
+///
+/// # Example
+/// ```
+/// let mut controller = SixDofController::new(0.01, 0.02);
+///
+/// // every frame
+/// controller.on_key(&world.env.device.get_keys(), world.objects.set_camera().set_pos());
+/// controller.on_mouse(mouse_delta, &world.objects.get_camera().get_camera_settings());
+/// *world.objects.set_camera().set_rot() = controller.forward().push(0.0);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SixDofController {
+    /// The rotation of the camera
+    pub orientation: Qua<f32>,
+    /// How far the camera moves every time [SixDofController::on_key] is called
+    pub speed: f32,
+    /// How far the camera rolls every time [SixDofController::on_key] is called(in radians)
+    pub roll_speed: f32,
+    /// When it is Some the position is kept inside the box by [SixDofController::on_key]
+    pub bounds: Option<Aabb>,
+    /// How far the camera turns for every pixel the mouse moves in [SixDofController::on_mouse]
+    /// before the sensitivities of the camera settings(in radians)
+    pub look_speed: f32,
+}
+
+impl SixDofController {
+    /// Creates a new controller looking forward with the up of [up_axis], +z and +y by default
+    pub fn new(speed: f32, roll_speed: f32) -> Self {
+        SixDofController {
+            orientation: quat_identity(),
+            speed,
+            roll_speed,
+            bounds: None,
+            look_speed: 0.002,
+        }
+    }
+
+    /// The direction the camera is looking in
+    pub fn forward(&self) -> Vec3 {
+        quat_rotate_vec3(&self.orientation, &up_axis().forward())
+    }
+
+    /// The up direction of the camera
+    pub fn up(&self) -> Vec3 {
+        quat_rotate_vec3(&self.orientation, &up_axis().up())
+    }
+
+    /// The right direction of the camera
+    pub fn right(&self) -> Vec3 {
+        self.forward().cross(&self.up())
+    }
+
+    /// Rotates the camera around one of its local axes, angle is in radians
+    pub fn rotate_local(&mut self, axis: &Vec3, angle: f32) {
+        self.orientation = quat_normalize(&(self.orientation * quat_angle_axis(angle, axis)));
+    }
+
+    /// Rotates the camera up and down
+    pub fn pitch(&mut self, angle: f32) {
+        self.rotate_local(&up_axis().left(), angle);
+    }
+
+    /// Rotates the camera left and right
+    pub fn yaw(&mut self, angle: f32) {
+        self.rotate_local(&up_axis().up(), angle);
+    }
+
+    /// Rolls the camera around the direction it is looking in
+    pub fn roll(&mut self, angle: f32) {
+        self.rotate_local(&up_axis().forward(), angle);
+    }
+
+    /// Moves pos along the local axes of the camera, movement is (right, up, forward)
+    pub fn translate_local(&self, pos: &mut Vec3, movement: Vec3) {
+        *pos += self.right() * movement.x + self.up() * movement.y + self.forward() * movement.z;
+    }
+
+    /// Yaws and pitches the camera from how far the mouse moved in window coordinates(+y is
+    /// down), it goes through [CameraSettings::look_delta] so the sensitivities and invert_y
+    /// of the settings are used
+    pub fn on_mouse(&mut self, mouse_delta: Vec2, settings: &CameraSettings) {
+        let look = settings.look_delta(mouse_delta) * self.look_speed;
+        // a positive yaw turns left and a positive pitch looks down
+        self.yaw(-look.x);
+        self.pitch(-look.y);
+    }
+
+    /// Moves and rolls the camera from the pressed keys then keeps pos in
+    /// [SixDofController::bounds]
+    ///
+    /// W/S move forward and back, A/D move left and right, Space/Shift move up and down and
+    /// Q/E roll
+    pub fn on_key(&mut self, keys: &[Keycode], pos: &mut Vec3) {
+        let mut movement = Vec3::zeros();
+        for key in keys {
+            match key {
+                Keycode::W => movement.z += self.speed,
+                Keycode::S => movement.z -= self.speed,
+                Keycode::A => movement.x -= self.speed,
+                Keycode::D => movement.x += self.speed,
+                Keycode::Space => movement.y += self.speed,
+                Keycode::LShift | Keycode::RShift => movement.y -= self.speed,
+                Keycode::Q => self.roll(-self.roll_speed),
+                Keycode::E => self.roll(self.roll_speed),
+                _ => (),
+            }
+        }
+        self.translate_local(pos, movement);
+        if let Some(bounds) = self.bounds {
+            *pos = bounds.clamp_point(pos);
+        }
+    }
+}
+
+impl Default for SixDofController {
+    fn default() -> Self {
+        Self::new(0.01, 0.02)
+    }
+}
+
+/// Smooth movement with a velocity that accelerates toward the input direction and slows down
+/// with damping when there is no input, instead of starting and stopping instantly
+///
+/// # Example
+/// ```
+/// let mut movement = SmoothMovement::new(2.0, 8.0, 6.0);
+///
+/// // every frame
+/// let direction = SmoothMovement::key_direction(&world.env.device.get_keys());
+/// movement.update(direction, world.objects.set_camera().set_pos(), world.env.delta_time());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SmoothMovement {
+    /// The current velocity in units per second
+    pub velocity: Vec3,
+    /// The fastest the object can move along each axis in units per second, in the axes of
+    /// [SmoothMovement::key_input], x is left and right, y is up and down and z is forward and
+    /// back. It follows [up_axis] so y is always the speed along the up axis
+    ///
+    /// # Example
+    /// ```
+    /// // climbing is half as fast as walking
+    /// movement.move_speed = vec3(2.0, 1.0, 2.0);
+    /// ```
+    pub move_speed: Vec3,
+    /// How fast the velocity changes toward the input direction in units per second squared
+    pub acceleration: f32,
+    /// How fast the velocity decays when there is no input, the velocity is multiplied by
+    /// e^(-damping * delta_time) every update
+    pub damping: f32,
+    /// When it is Some the position is kept inside the box by [SmoothMovement::update] and the
+    /// velocity into a side of the box is stopped
+    pub bounds: Option<Aabb>,
+}
+
+impl SmoothMovement {
+    /// Creates a new movement that is standing still, max_speed is the
+    /// [SmoothMovement::move_speed] of every axis
+    pub fn new(max_speed: f32, acceleration: f32, damping: f32) -> Self {
+        SmoothMovement {
+            velocity: Vec3::zeros(),
+            move_speed: Vec3::repeat(max_speed),
+            acceleration,
+            damping,
+            bounds: None,
+        }
+    }
+
+    /// The world space direction of the pressed keys, it isn't normalized
+    ///
+    /// W/S are forward and back, A/D are left and right and Space/Shift are up and down of
+    /// [up_axis], see [SmoothMovement::key_input]
+    pub fn key_direction(keys: &[Keycode]) -> Vec3 {
+        up_axis().from_input(Self::key_input(keys))
+    }
+
+    /// The direction of the pressed keys in the axes of the input, it isn't normalized
+    ///
+    /// W/S are +z and -z, A/D are +x and -x and Space/Shift are +y and -y
+    pub fn key_input(keys: &[Keycode]) -> Vec3 {
+        keys.iter().fold(Vec3::zeros(), |direction, key| {
+            direction
+                + match key {
+                    Keycode::W => vec3(0.0, 0.0, 1.0),
+                    Keycode::S => vec3(0.0, 0.0, -1.0),
+                    Keycode::A => vec3(1.0, 0.0, 0.0),
+                    Keycode::D => vec3(-1.0, 0.0, 0.0),
+                    Keycode::Space => vec3(0.0, 1.0, 0.0),
+                    Keycode::LShift | Keycode::RShift => vec3(0.0, -1.0, 0.0),
+                    _ => Vec3::zeros(),
+                }
+        })
+    }
+
+    /// Changes the velocity from the input direction then moves pos by it and keeps it in
+    /// [SmoothMovement::bounds], delta_time is in seconds
+    ///
+    /// A zero direction means there is no input so the velocity is damped toward zero, otherwise
+    /// the velocity moves toward the normalized direction scaled by the
+    /// [SmoothMovement::move_speed] of each axis
+    pub fn update(&mut self, direction: Vec3, pos: &mut Vec3, delta_time: f32) {
+        if direction == Vec3::zeros() {
+            self.velocity *= (-self.damping * delta_time).exp();
+            // stop completely instead of creeping forever
+            if self.velocity.norm() < 1e-4 {
+                self.velocity = Vec3::zeros();
+            }
+        } else {
+            // the speeds are in input axes, the sign of an axis dosen't matter
+            let speed = up_axis().from_input(self.move_speed).abs();
+            self.velocity = move_towards(
+                &self.velocity,
+                &direction.normalize().component_mul(&speed),
+                self.acceleration * delta_time,
+            );
+        }
+
+        *pos += self.velocity * delta_time;
+
+        if let Some(bounds) = self.bounds {
+            let clamped = bounds.clamp_point(pos);
+            for i in 0..3 {
+                if clamped[i] != pos[i] {
+                    self.velocity[i] = 0.0;
+                }
+            }
+            *pos = clamped;
+        }
+    }
+
+    /// Checks if the velocity is zero
+    pub fn is_stopped(&self) -> bool {
+        self.velocity == Vec3::zeros()
+    }
+}
+
+impl Default for SmoothMovement {
+    fn default() -> Self {
+        Self::new(2.0, 8.0, 6.0)
+    }
+}
+
+/// Turns input relative to the camera into a world space direction on the ground plane, so
+/// forward always moves away from the camera however it is yawed
+///
+/// input is in the same axes as [SmoothMovement::key_input], +z is forward, +x is left and
+/// +y is up. The pitch of camera_forward is ignored and up stays the up of [up_axis]. When the
+/// camera faces forward the input is returned as [crate::math::UpAxis::from_input] would
+///
+/// # Example
+/// ```
+/// let input = SmoothMovement::key_input(&world.env.device.get_keys());
+/// let direction = camera_relative(input, &world.objects.get_camera().forward());
+/// ```
+pub fn camera_relative(input: Vec3, camera_forward: &Vec3) -> Vec3 {
+    let axis = up_axis();
+    let up = axis.up();
+    let forward = project_on_plane(camera_forward, &up);
+    // looking straight up or down has no yaw so keep the world axes
+    let forward = if forward.norm() < 1e-6 {
+        axis.forward()
+    } else {
+        forward.normalize()
+    };
+    let left = up.cross(&forward);
+    forward * input.z + left * input.x + up * input.y
+}
+
+/// A controller for top down and twin stick games, the object moves on the ground plane
+/// relative to the yaw of the camera instead of along the world axes
+///
+/// # Example
+/// ```
+/// let mut controller = TopDownController::new(0.05);
+///
+/// // every frame
+/// let camera_forward = world.objects.get_camera().forward();
+/// controller.on_key(&world.env.device.get_keys(), &camera_forward, &mut player.pos);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TopDownController {
+    /// How far the object moves every time [TopDownController::on_key] is called
+    pub speed: f32,
+    /// When it is Some the position is kept inside the box by [TopDownController::on_key]
+    pub bounds: Option<Aabb>,
+}
+
+impl TopDownController {
+    /// Creates a new controller
+    pub fn new(speed: f32) -> Self {
+        TopDownController {
+            speed,
+            bounds: None,
+        }
+    }
+
+    /// The world space direction of the pressed keys, it is normalized unless it is zero
+    ///
+    /// W/S move away from and toward the camera and A/D move left and right of it, see
+    /// [camera_relative]
+    pub fn direction(keys: &[Keycode], camera_forward: &Vec3) -> Vec3 {
+        let mut input = SmoothMovement::key_input(keys);
+        input.y = 0.0;
+        let direction = camera_relative(input, camera_forward);
+        if direction == Vec3::zeros() {
+            direction
+        } else {
+            direction.normalize()
+        }
+    }
+
+    /// Moves pos from the pressed keys relative to the camera then keeps it in
+    /// [TopDownController::bounds]
+    pub fn on_key(&mut self, keys: &[Keycode], camera_forward: &Vec3, pos: &mut Vec3) {
+        *pos += Self::direction(keys, camera_forward) * self.speed;
+        if let Some(bounds) = self.bounds {
+            *pos = bounds.clamp_point(pos);
+        }
+    }
+}
+
+impl Default for TopDownController {
+    fn default() -> Self {
+        Self::new(0.01)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_2;
+
+    fn assert_near(a: Vec3, b: Vec3) {
+        assert!((a - b).norm() < 1e-5, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn rolling_turns_the_up_of_the_view_matrix() {
+        let mut controller = SixDofController::default();
+        let right = controller.right();
+        controller.roll(FRAC_PI_2);
+        assert_near(controller.up(), right);
+
+        // what a camera that overrides get_up with the controller passes to the view matrix
+        let view = look_at(&Vec3::zeros(), &controller.forward(), &controller.up());
+        assert_near(view.row(1).transpose().xyz(), right);
+
+        let mut pos = Vec3::zeros();
+        controller.translate_local(&mut pos, vec3(0.0, 1.0, 0.0));
+        assert_near(pos, right);
+    }
+
+    #[test]
+    fn the_velocity_decays_with_damping() {
+        let mut movement = SmoothMovement::new(2.0, 8.0, 6.0);
+        movement.velocity = vec3(1.0, 0.0, 0.0);
+        let mut pos = Vec3::zeros();
+
+        movement.update(Vec3::zeros(), &mut pos, 0.1);
+        assert!((movement.velocity.x - (-0.6_f32).exp()).abs() < 1e-6);
+        assert!(pos.x > 0.0);
+
+        for _ in 0..100 {
+            movement.update(Vec3::zeros(), &mut pos, 0.1);
+        }
+        assert!(movement.is_stopped());
+    }
+
+    #[test]
+    fn each_axis_has_its_own_speed() {
+        let mut movement = SmoothMovement::new(0.0, 1000.0, 6.0);
+        movement.move_speed = vec3(2.0, 1.0, 2.0);
+        let mut pos = Vec3::zeros();
+
+        movement.update(vec3(0.0, 0.0, 1.0), &mut pos, 0.1);
+        assert_near(movement.velocity, vec3(0.0, 0.0, 2.0));
+        movement.update(vec3(0.0, 1.0, 0.0), &mut pos, 0.1);
+        assert_near(movement.velocity, vec3(0.0, 1.0, 0.0));
+        // the direction is normalized before it is scaled so a diagonal is slower up
+        movement.update(vec3(1.0, 1.0, 0.0), &mut pos, 0.1);
+        assert_near(movement.velocity, vec3(2.0, 1.0, 0.0) / 2.0_f32.sqrt());
+    }
+
+    #[test]
+    fn the_position_is_clamped_to_the_bounds() {
+        let mut movement = SmoothMovement::new(2.0, 100.0, 6.0);
+        movement.bounds = Some(Aabb::new(Vec3::repeat(-1.0), Vec3::repeat(1.0)));
+        let mut pos = Vec3::zeros();
+        for _ in 0..10 {
+            movement.update(vec3(1.0, 0.0, 0.0), &mut pos, 0.5);
+        }
+        assert_eq!(pos, vec3(1.0, 0.0, 0.0));
+        // the velocity into the side is stopped so it dosen't build up
+        assert_eq!(movement.velocity.x, 0.0);
+    }
+}
@@ -0,0 +1,170 @@
+use std::any::Any;
+use std::collections::HashMap;
+
+/// The id of an object in an [EntityStore], ids are never reused
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct EntityId(pub u32);
+
+/// Objects that are spawned at runtime, they can be found by a unique name or by their tags
+///
+/// Objects of any type can be stored, they are read back with [EntityStore::get] and the type
+/// they were spawned with
+///
+/// # Example
+/// ```
+/// let player = world.spawn_named("player", Player::new())?;
+/// world.entities.add_tag(player, "friendly");
+///
+/// let id = world.find("player").unwrap();
+/// world.entities.get_mut::<Player>(id).unwrap().health -= 10;
+/// ```
+#[derive(Default)]
+pub struct EntityStore {
+    next_id: u32,
+    objects: HashMap<EntityId, Box<dyn Any>>,
+    names: HashMap<String, EntityId>,
+    tags: HashMap<String, Vec<EntityId>>,
+}
+
+impl EntityStore {
+    /// Creates an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an object without a name
+    pub fn spawn<T: 'static>(&mut self, object: T) -> EntityId {
+        let id = EntityId(self.next_id);
+        self.next_id += 1;
+        self.objects.insert(id, Box::new(object));
+        id
+    }
+
+    /// Adds an object that can be found by name
+    ///
+    /// It is an error if an object already has the name, the object isn't added and the first
+    /// one keeps the name
+    pub fn spawn_named<T: 'static>(&mut self, name: &str, object: T) -> Result<EntityId, String> {
+        if let Some(existing) = self.find(name) {
+            return Err(format!(
+                "Cannot spawn a second object named {:?}, it is taken by {:?}",
+                name, existing
+            ));
+        }
+        let id = self.spawn(object);
+        self.names.insert(name.to_string(), id);
+        Ok(id)
+    }
+
+    /// Removes an object with its name and tags, returns false if it didn't exist
+    pub fn despawn(&mut self, id: EntityId) -> bool {
+        self.names.retain(|_, named| *named != id);
+        for ids in self.tags.values_mut() {
+            ids.retain(|tagged| *tagged != id);
+        }
+        self.objects.remove(&id).is_some()
+    }
+
+    /// Gets the object with the name
+    pub fn find(&self, name: &str) -> Option<EntityId> {
+        self.names.get(name).copied()
+    }
+
+    /// Gets the name of an object
+    pub fn name_of(&self, id: EntityId) -> Option<&str> {
+        self.names
+            .iter()
+            .find(|(_, named)| **named == id)
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Tags an object, an object can have any number of tags
+    pub fn add_tag(&mut self, id: EntityId, tag: &str) {
+        let ids = self.tags.entry(tag.to_string()).or_default();
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+
+    /// Removes a tag from an object
+    pub fn remove_tag(&mut self, id: EntityId, tag: &str) {
+        if let Some(ids) = self.tags.get_mut(tag) {
+            ids.retain(|tagged| *tagged != id);
+        }
+    }
+
+    /// Checks if an object has a tag
+    pub fn has_tag(&self, id: EntityId, tag: &str) -> bool {
+        self.tags.get(tag).is_some_and(|ids| ids.contains(&id))
+    }
+
+    /// Gets every object with the tag in the order they were tagged
+    pub fn entities_with_tag(&self, tag: &str) -> Vec<EntityId> {
+        self.tags.get(tag).cloned().unwrap_or_default()
+    }
+
+    /// Gets an object, None if it dosen't exist or isn't a T
+    pub fn get<T: 'static>(&self, id: EntityId) -> Option<&T> {
+        self.objects.get(&id)?.downcast_ref()
+    }
+
+    /// Gets an object mutably, None if it dosen't exist or isn't a T
+    pub fn get_mut<T: 'static>(&mut self, id: EntityId) -> Option<&mut T> {
+        self.objects.get_mut(&id)?.downcast_mut()
+    }
+
+    /// Checks if an object exists
+    pub fn contains(&self, id: EntityId) -> bool {
+        self.objects.contains_key(&id)
+    }
+
+    /// How many objects there are
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    /// Checks if there are no objects
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn named_objects_can_be_found_and_despawned() {
+        let mut store = EntityStore::new();
+        let player = store.spawn_named("player", 100_u32).unwrap();
+        let rock = store.spawn(String::from("rock"));
+        store.add_tag(player, "friendly");
+
+        assert_eq!(store.find("player"), Some(player));
+        assert_eq!(store.name_of(player), Some("player"));
+        assert_eq!(store.get::<u32>(player), Some(&100));
+        assert_eq!(store.get::<String>(player), None);
+        assert_eq!(store.entities_with_tag("friendly"), vec![player]);
+        assert_eq!(store.len(), 2);
+
+        assert!(store.despawn(player));
+        assert!(!store.despawn(player));
+        assert_eq!(store.find("player"), None);
+        assert!(store.entities_with_tag("friendly").is_empty());
+        assert!(store.contains(rock));
+        assert_eq!(store.len(), 1);
+    }
+
+    #[test]
+    fn a_duplicate_name_is_an_error() {
+        let mut store = EntityStore::new();
+        let first = store.spawn_named("player", 1_u32).unwrap();
+        assert!(store.spawn_named("player", 2_u32).is_err());
+        assert_eq!(store.find("player"), Some(first));
+        assert_eq!(store.len(), 1);
+
+        // the name is free again once the first one is gone
+        store.despawn(first);
+        assert!(store.spawn_named("player", 3_u32).is_ok());
+    }
+}
@@ -0,0 +1,920 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::mem::size_of;
+use std::path::Path;
+
+use beryllium::{
+    fermium::{self, SDL_Event},
+    Event, WindowEvent, WindowEventEnum,
+};
+
+/// The events of the engine, it adds the events beryllium dosen't handle like text input
+///
+/// # Example
+/// ```
+/// while let Some(event) = sdl.poll_events().and_then(EngineEvent::from_sdl) {
+///     match event {
+///         EngineEvent::Sdl(Event::Quit(_)) => break 'main_loop,
+///         EngineEvent::TextInput(text) => chat_box.push_str(&text),
+///         _ => (),
+///     }
+/// }
+/// ```
+pub enum EngineEvent {
+    /// An event beryllium understands
+    Sdl(Event),
+    /// Text was typed while text input is on, see [crate::ECS::world::Enviroment::start_text_input].
+    /// It is already translated by the keyboard layout and IME so it can be appended as is
+    TextInput(String),
+    /// The IME is composing text that isn't finished yet
+    TextEditing {
+        /// The text being composed
+        text: String,
+        /// Where the cursor is in the composed text
+        start: i32,
+        /// How much of the composed text is selected
+        length: i32,
+    },
+}
+
+impl EngineEvent {
+    /// Converts the result of SDL::poll_events, events that neither beryllium nor the engine
+    /// understand are None
+    pub fn from_sdl(event: Result<Event, SDL_Event>) -> Option<Self> {
+        match event {
+            Ok(event) => Some(EngineEvent::Sdl(event)),
+            Err(event) => unsafe {
+                match event.type_ {
+                    fermium::SDL_TEXTINPUT => {
+                        Some(EngineEvent::TextInput(text_to_string(&event.text.text)))
+                    }
+                    fermium::SDL_TEXTEDITING => Some(EngineEvent::TextEditing {
+                        text: text_to_string(&event.edit.text),
+                        start: event.edit.start,
+                        length: event.edit.length,
+                    }),
+                    _ => None,
+                }
+            },
+        }
+    }
+}
+
+/// Whether the window has keyboard focus and whether it is minimized, it is kept up to date from
+/// the window events by [WindowState::update]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WindowState {
+    /// The window has keyboard focus
+    pub focused: bool,
+    /// The window is minimized or hidden
+    pub minimized: bool,
+}
+
+impl WindowState {
+    /// Updates the state from an event, events that aren't about focus or minimizing are ignored
+    pub fn update(&mut self, event: &EngineEvent) {
+        if let EngineEvent::Sdl(Event::Window(WindowEvent { event, .. })) = event {
+            match event {
+                WindowEventEnum::KeyboardFocusGained => self.focused = true,
+                WindowEventEnum::KeyboardFocusLost => self.focused = false,
+                WindowEventEnum::Minimized | WindowEventEnum::Hidden => self.minimized = true,
+                WindowEventEnum::Restored | WindowEventEnum::Maximized | WindowEventEnum::Shown => {
+                    self.minimized = false
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Checks if the window is focused and not minimized
+    pub fn is_active(&self) -> bool {
+        self.focused && !self.minimized
+    }
+}
+
+impl Default for WindowState {
+    /// A focused window that isn't minimized
+    fn default() -> Self {
+        WindowState {
+            focused: true,
+            minimized: false,
+        }
+    }
+}
+
+/// Checks if the event is the window changing size, [World::run](crate::ECS::world::World::run)
+/// updates the viewport to the new drawable size when it is
+pub fn is_resize(event: &EngineEvent) -> bool {
+    matches!(
+        event,
+        EngineEvent::Sdl(Event::Window(WindowEvent {
+            event: WindowEventEnum::SizeChanged,
+            ..
+        }))
+    )
+}
+
+/// Takes every pending event from SDL, events that neither beryllium nor the engine understand
+/// are skipped
+///
+/// It is the same as calling SDL::poll_events until it is empty but it dosen't need the SDL
+/// handle, it is used by [World::run](crate::ECS::world::World::run)
+pub fn poll_events() -> Vec<EngineEvent> {
+    poll_raw_events().into_iter().filter_map(from_raw).collect()
+}
+
+/// Takes every pending event from SDL without converting them, use [from_raw] to convert them
+pub fn poll_raw_events() -> Vec<SDL_Event> {
+    let mut events = Vec::new();
+    let mut event = SDL_Event::default();
+    while unsafe { fermium::SDL_PollEvent(&mut event) } > 0 {
+        events.push(event);
+    }
+    events
+}
+
+/// Converts a raw SDL event, events that neither beryllium nor the engine understand are None
+pub fn from_raw(event: SDL_Event) -> Option<EngineEvent> {
+    EngineEvent::from_sdl(Event::try_from(event))
+}
+
+/// The first bytes of an input recording
+const RECORDING_MAGIC: &[u8; 8] = b"LHINPUT2";
+
+/// An SDL event as it is stored in an input recording, unlike the bytes of an SDL_Event it only
+/// has plain values so it can be written to a file. The timestamps aren't kept
+///
+/// Only the events that [EngineEvent] understands are recorded, events that point to memory
+/// owned by SDL like dropped files can't be
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(missing_docs)]
+pub enum RecordedEvent {
+    /// SDL_QUIT
+    Quit,
+    /// SDL_WINDOWEVENT, event is the SDL_WindowEventID
+    Window {
+        window_id: u32,
+        event: u8,
+        data1: i32,
+        data2: i32,
+    },
+    /// SDL_KEYDOWN or SDL_KEYUP
+    Key {
+        pressed: bool,
+        window_id: u32,
+        repeat: u8,
+        scancode: u32,
+        keycode: i32,
+        modifiers: u16,
+    },
+    /// SDL_MOUSEMOTION
+    MouseMotion {
+        window_id: u32,
+        mouse_id: u32,
+        state: u32,
+        x: i32,
+        y: i32,
+        x_delta: i32,
+        y_delta: i32,
+    },
+    /// SDL_MOUSEBUTTONDOWN or SDL_MOUSEBUTTONUP
+    MouseButton {
+        pressed: bool,
+        window_id: u32,
+        mouse_id: u32,
+        button: u8,
+        clicks: u8,
+        x: i32,
+        y: i32,
+    },
+    /// SDL_MOUSEWHEEL
+    MouseWheel {
+        window_id: u32,
+        mouse_id: u32,
+        x: i32,
+        y: i32,
+        direction: u32,
+    },
+    /// SDL_CONTROLLERDEVICEADDED, SDL_CONTROLLERDEVICEREMOVED or SDL_CONTROLLERDEVICEREMAPPED,
+    /// kind is the event type
+    ControllerDevice { kind: u32, joystick_id: i32 },
+    /// SDL_CONTROLLERBUTTONDOWN or SDL_CONTROLLERBUTTONUP
+    ControllerButton {
+        pressed: bool,
+        joystick_id: i32,
+        button: u8,
+    },
+    /// SDL_CONTROLLERAXISMOTION
+    ControllerAxis {
+        joystick_id: i32,
+        axis: u8,
+        value: i16,
+    },
+    /// SDL_TEXTINPUT, text is null terminated UTF-8
+    TextInput { window_id: u32, text: [u8; 32] },
+    /// SDL_TEXTEDITING, text is null terminated UTF-8
+    TextEditing {
+        window_id: u32,
+        text: [u8; 32],
+        start: i32,
+        length: i32,
+    },
+}
+
+impl RecordedEvent {
+    /// Copies the values of an SDL event, events that can't be recorded are None
+    pub fn from_sdl(event: &SDL_Event) -> Option<Self> {
+        let pressed = |state: u8| state as u32 == fermium::SDL_PRESSED;
+        unsafe {
+            Some(match event.type_ {
+                fermium::SDL_QUIT => RecordedEvent::Quit,
+                fermium::SDL_WINDOWEVENT => RecordedEvent::Window {
+                    window_id: event.window.windowID,
+                    event: event.window.event,
+                    data1: event.window.data1,
+                    data2: event.window.data2,
+                },
+                fermium::SDL_KEYDOWN | fermium::SDL_KEYUP => RecordedEvent::Key {
+                    pressed: pressed(event.key.state),
+                    window_id: event.key.windowID,
+                    repeat: event.key.repeat,
+                    scancode: event.key.keysym.scancode,
+                    keycode: event.key.keysym.sym,
+                    modifiers: event.key.keysym.mod_,
+                },
+                fermium::SDL_MOUSEMOTION => RecordedEvent::MouseMotion {
+                    window_id: event.motion.windowID,
+                    mouse_id: event.motion.which,
+                    state: event.motion.state,
+                    x: event.motion.x,
+                    y: event.motion.y,
+                    x_delta: event.motion.xrel,
+                    y_delta: event.motion.yrel,
+                },
+                fermium::SDL_MOUSEBUTTONDOWN | fermium::SDL_MOUSEBUTTONUP => {
+                    RecordedEvent::MouseButton {
+                        pressed: pressed(event.button.state),
+                        window_id: event.button.windowID,
+                        mouse_id: event.button.which,
+                        button: event.button.button,
+                        clicks: event.button.clicks,
+                        x: event.button.x,
+                        y: event.button.y,
+                    }
+                }
+                fermium::SDL_MOUSEWHEEL => RecordedEvent::MouseWheel {
+                    window_id: event.wheel.windowID,
+                    mouse_id: event.wheel.which,
+                    x: event.wheel.x,
+                    y: event.wheel.y,
+                    direction: event.wheel.direction,
+                },
+                fermium::SDL_CONTROLLERDEVICEADDED
+                | fermium::SDL_CONTROLLERDEVICEREMOVED
+                | fermium::SDL_CONTROLLERDEVICEREMAPPED => RecordedEvent::ControllerDevice {
+                    kind: event.type_,
+                    joystick_id: event.cdevice.which,
+                },
+                fermium::SDL_CONTROLLERBUTTONDOWN | fermium::SDL_CONTROLLERBUTTONUP => {
+                    RecordedEvent::ControllerButton {
+                        pressed: pressed(event.cbutton.state),
+                        joystick_id: event.cbutton.which,
+                        button: event.cbutton.button,
+                    }
+                }
+                fermium::SDL_CONTROLLERAXISMOTION => RecordedEvent::ControllerAxis {
+                    joystick_id: event.caxis.which,
+                    axis: event.caxis.axis,
+                    value: event.caxis.value,
+                },
+                fermium::SDL_TEXTINPUT => RecordedEvent::TextInput {
+                    window_id: event.text.windowID,
+                    text: event.text.text.map(|c| c as u8),
+                },
+                fermium::SDL_TEXTEDITING => RecordedEvent::TextEditing {
+                    window_id: event.edit.windowID,
+                    text: event.edit.text.map(|c| c as u8),
+                    start: event.edit.start,
+                    length: event.edit.length,
+                },
+                _ => return None,
+            })
+        }
+    }
+
+    /// Makes the SDL event again, its timestamp is 0
+    pub fn to_sdl(&self) -> SDL_Event {
+        let state = |pressed: bool| {
+            if pressed {
+                fermium::SDL_PRESSED as u8
+            } else {
+                fermium::SDL_RELEASED as u8
+            }
+        };
+        let mut event = SDL_Event::default();
+        match *self {
+            RecordedEvent::Quit => event.type_ = fermium::SDL_QUIT,
+            RecordedEvent::Window {
+                window_id,
+                event: id,
+                data1,
+                data2,
+            } => {
+                event.type_ = fermium::SDL_WINDOWEVENT;
+                event.window.windowID = window_id;
+                event.window.event = id;
+                event.window.data1 = data1;
+                event.window.data2 = data2;
+            }
+            RecordedEvent::Key {
+                pressed,
+                window_id,
+                repeat,
+                scancode,
+                keycode,
+                modifiers,
+            } => {
+                event.type_ = if pressed {
+                    fermium::SDL_KEYDOWN
+                } else {
+                    fermium::SDL_KEYUP
+                };
+                event.key.windowID = window_id;
+                event.key.state = state(pressed);
+                event.key.repeat = repeat;
+                event.key.keysym.scancode = scancode;
+                event.key.keysym.sym = keycode;
+                event.key.keysym.mod_ = modifiers;
+            }
+            RecordedEvent::MouseMotion {
+                window_id,
+                mouse_id,
+                state,
+                x,
+                y,
+                x_delta,
+                y_delta,
+            } => {
+                event.type_ = fermium::SDL_MOUSEMOTION;
+                event.motion.windowID = window_id;
+                event.motion.which = mouse_id;
+                event.motion.state = state;
+                event.motion.x = x;
+                event.motion.y = y;
+                event.motion.xrel = x_delta;
+                event.motion.yrel = y_delta;
+            }
+            RecordedEvent::MouseButton {
+                pressed,
+                window_id,
+                mouse_id,
+                button,
+                clicks,
+                x,
+                y,
+            } => {
+                event.type_ = if pressed {
+                    fermium::SDL_MOUSEBUTTONDOWN
+                } else {
+                    fermium::SDL_MOUSEBUTTONUP
+                };
+                event.button.windowID = window_id;
+                event.button.which = mouse_id;
+                event.button.button = button;
+                event.button.state = state(pressed);
+                event.button.clicks = clicks;
+                event.button.x = x;
+                event.button.y = y;
+            }
+            RecordedEvent::MouseWheel {
+                window_id,
+                mouse_id,
+                x,
+                y,
+                direction,
+            } => {
+                event.type_ = fermium::SDL_MOUSEWHEEL;
+                event.wheel.windowID = window_id;
+                event.wheel.which = mouse_id;
+                event.wheel.x = x;
+                event.wheel.y = y;
+                event.wheel.direction = direction;
+            }
+            RecordedEvent::ControllerDevice { kind, joystick_id } => {
+                event.type_ = kind;
+                event.cdevice.which = joystick_id;
+            }
+            RecordedEvent::ControllerButton {
+                pressed,
+                joystick_id,
+                button,
+            } => {
+                event.type_ = if pressed {
+                    fermium::SDL_CONTROLLERBUTTONDOWN
+                } else {
+                    fermium::SDL_CONTROLLERBUTTONUP
+                };
+                event.cbutton.which = joystick_id;
+                event.cbutton.button = button;
+                event.cbutton.state = state(pressed);
+            }
+            RecordedEvent::ControllerAxis {
+                joystick_id,
+                axis,
+                value,
+            } => {
+                event.type_ = fermium::SDL_CONTROLLERAXISMOTION;
+                event.caxis.which = joystick_id;
+                event.caxis.axis = axis;
+                event.caxis.value = value;
+            }
+            RecordedEvent::TextInput { window_id, text } => {
+                event.type_ = fermium::SDL_TEXTINPUT;
+                event.text.windowID = window_id;
+                event.text.text = text.map(|c| c as fermium::c_char);
+            }
+            RecordedEvent::TextEditing {
+                window_id,
+                text,
+                start,
+                length,
+            } => {
+                event.type_ = fermium::SDL_TEXTEDITING;
+                event.edit.windowID = window_id;
+                event.edit.text = text.map(|c| c as fermium::c_char);
+                event.edit.start = start;
+                event.edit.length = length;
+            }
+        }
+        event
+    }
+
+    /// The tag of the event in a recording, its fields and its text if it has any
+    fn fields(&self) -> (u8, Vec<i32>, Option<[u8; 32]>) {
+        match *self {
+            RecordedEvent::Quit => (0, vec![], None),
+            RecordedEvent::Window {
+                window_id,
+                event,
+                data1,
+                data2,
+            } => (1, vec![window_id as i32, event as i32, data1, data2], None),
+            RecordedEvent::Key {
+                pressed,
+                window_id,
+                repeat,
+                scancode,
+                keycode,
+                modifiers,
+            } => (
+                2,
+                vec![
+                    pressed as i32,
+                    window_id as i32,
+                    repeat as i32,
+                    scancode as i32,
+                    keycode,
+                    modifiers as i32,
+                ],
+                None,
+            ),
+            RecordedEvent::MouseMotion {
+                window_id,
+                mouse_id,
+                state,
+                x,
+                y,
+                x_delta,
+                y_delta,
+            } => (
+                3,
+                vec![
+                    window_id as i32,
+                    mouse_id as i32,
+                    state as i32,
+                    x,
+                    y,
+                    x_delta,
+                    y_delta,
+                ],
+                None,
+            ),
+            RecordedEvent::MouseButton {
+                pressed,
+                window_id,
+                mouse_id,
+                button,
+                clicks,
+                x,
+                y,
+            } => (
+                4,
+                vec![
+                    pressed as i32,
+                    window_id as i32,
+                    mouse_id as i32,
+                    button as i32,
+                    clicks as i32,
+                    x,
+                    y,
+                ],
+                None,
+            ),
+            RecordedEvent::MouseWheel {
+                window_id,
+                mouse_id,
+                x,
+                y,
+                direction,
+            } => (
+                5,
+                vec![window_id as i32, mouse_id as i32, x, y, direction as i32],
+                None,
+            ),
+            RecordedEvent::ControllerDevice { kind, joystick_id } => {
+                (6, vec![kind as i32, joystick_id], None)
+            }
+            RecordedEvent::ControllerButton {
+                pressed,
+                joystick_id,
+                button,
+            } => (7, vec![pressed as i32, joystick_id, button as i32], None),
+            RecordedEvent::ControllerAxis {
+                joystick_id,
+                axis,
+                value,
+            } => (8, vec![joystick_id, axis as i32, value as i32], None),
+            RecordedEvent::TextInput { window_id, text } => (9, vec![window_id as i32], Some(text)),
+            RecordedEvent::TextEditing {
+                window_id,
+                text,
+                start,
+                length,
+            } => (10, vec![window_id as i32, start, length], Some(text)),
+        }
+    }
+
+    /// Makes the event from a tag, fields and text read from a recording, None if the tag isn't
+    /// known. The fields and text must be as long as [RecordedEvent::shape] says
+    fn from_fields(tag: u8, f: &[i32], text: [u8; 32]) -> Option<Self> {
+        Some(match tag {
+            0 => RecordedEvent::Quit,
+            1 => RecordedEvent::Window {
+                window_id: f[0] as u32,
+                event: f[1] as u8,
+                data1: f[2],
+                data2: f[3],
+            },
+            2 => RecordedEvent::Key {
+                pressed: f[0] != 0,
+                window_id: f[1] as u32,
+                repeat: f[2] as u8,
+                scancode: f[3] as u32,
+                keycode: f[4],
+                modifiers: f[5] as u16,
+            },
+            3 => RecordedEvent::MouseMotion {
+                window_id: f[0] as u32,
+                mouse_id: f[1] as u32,
+                state: f[2] as u32,
+                x: f[3],
+                y: f[4],
+                x_delta: f[5],
+                y_delta: f[6],
+            },
+            4 => RecordedEvent::MouseButton {
+                pressed: f[0] != 0,
+                window_id: f[1] as u32,
+                mouse_id: f[2] as u32,
+                button: f[3] as u8,
+                clicks: f[4] as u8,
+                x: f[5],
+                y: f[6],
+            },
+            5 => RecordedEvent::MouseWheel {
+                window_id: f[0] as u32,
+                mouse_id: f[1] as u32,
+                x: f[2],
+                y: f[3],
+                direction: f[4] as u32,
+            },
+            6 => RecordedEvent::ControllerDevice {
+                kind: f[0] as u32,
+                joystick_id: f[1],
+            },
+            7 => RecordedEvent::ControllerButton {
+                pressed: f[0] != 0,
+                joystick_id: f[1],
+                button: f[2] as u8,
+            },
+            8 => RecordedEvent::ControllerAxis {
+                joystick_id: f[0],
+                axis: f[1] as u8,
+                value: f[2] as i16,
+            },
+            9 => RecordedEvent::TextInput {
+                window_id: f[0] as u32,
+                text,
+            },
+            10 => RecordedEvent::TextEditing {
+                window_id: f[0] as u32,
+                text,
+                start: f[1],
+                length: f[2],
+            },
+            _ => return None,
+        })
+    }
+
+    /// How many fields an event with the tag has and if it has text, None if the tag isn't known
+    fn shape(tag: u8) -> Option<(usize, bool)> {
+        Some(match tag {
+            0 => (0, false),
+            1 => (4, false),
+            2 => (6, false),
+            3 | 4 => (7, false),
+            5 => (5, false),
+            6 => (2, false),
+            7 | 8 => (3, false),
+            9 => (1, true),
+            10 => (3, true),
+            _ => return None,
+        })
+    }
+
+    /// Appends the event to out as its tag followed by its fields in little endian and its text
+    fn write_to(&self, out: &mut Vec<u8>) {
+        let (tag, fields, text) = self.fields();
+        out.push(tag);
+        for field in fields {
+            out.extend_from_slice(&field.to_le_bytes());
+        }
+        if let Some(text) = text {
+            out.extend_from_slice(&text);
+        }
+    }
+
+    /// Reads an event written by [RecordedEvent::write_to] from the start of bytes and moves
+    /// bytes past it
+    fn read_from(bytes: &mut &[u8]) -> Result<Self, String> {
+        let truncated = || "The input recording is truncated".to_string();
+        let (&tag, rest) = bytes.split_first().ok_or_else(truncated)?;
+        let (count, has_text) = Self::shape(tag)
+            .ok_or_else(|| format!("The input recording has an unknown event {}", tag))?;
+        let size = count * size_of::<i32>() + if has_text { 32 } else { 0 };
+        let body = rest.get(..size).ok_or_else(truncated)?;
+
+        let (fields, text) = body.split_at(count * size_of::<i32>());
+        let fields: Vec<i32> = fields
+            .chunks_exact(size_of::<i32>())
+            .map(|field| i32::from_le_bytes(field.try_into().unwrap()))
+            .collect();
+        let mut text_bytes = [0; 32];
+        text_bytes[..text.len()].copy_from_slice(text);
+
+        *bytes = &rest[size..];
+        Ok(Self::from_fields(tag, &fields, text_bytes).unwrap())
+    }
+}
+
+/// Writes the events of every frame to a file so they can be replayed with [InputReplay], see
+/// [Enviroment::record_input](crate::ECS::world::Enviroment::record_input)
+///
+/// Every event is stored as the frame it happened in (counted from the start of the recording)
+/// followed by the [RecordedEvent]
+pub struct InputRecorder {
+    writer: BufWriter<File>,
+    frame: u32,
+}
+
+impl InputRecorder {
+    /// Creates the file, if it exists it is overwritten
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+        let file = File::create(path)
+            .map_err(|err| format!("Couldn't create {}: {}", path.display(), err))?;
+        let mut writer = BufWriter::new(file);
+        writer
+            .write_all(RECORDING_MAGIC)
+            .map_err(|err| err.to_string())?;
+        Ok(InputRecorder { writer, frame: 0 })
+    }
+
+    /// Writes the events of the current frame then moves on to the next frame, events that
+    /// can't be recorded are skipped, see [RecordedEvent]
+    pub fn record(&mut self, events: &[SDL_Event]) -> Result<(), String> {
+        let mut bytes = Vec::new();
+        for event in events.iter().filter_map(RecordedEvent::from_sdl) {
+            bytes.extend_from_slice(&self.frame.to_le_bytes());
+            event.write_to(&mut bytes);
+        }
+        self.writer
+            .write_all(&bytes)
+            .map_err(|err| err.to_string())?;
+        self.frame += 1;
+        Ok(())
+    }
+
+    /// How many frames were recorded
+    pub fn frames(&self) -> u32 {
+        self.frame
+    }
+
+    /// Writes everything that is still buffered to the file
+    pub fn finish(mut self) -> Result<(), String> {
+        self.writer.flush().map_err(|err| err.to_string())
+    }
+}
+
+/// Plays back a file written by [InputRecorder] one frame at a time, see
+/// [Enviroment::replay_input](crate::ECS::world::Enviroment::replay_input)
+pub struct InputReplay {
+    events: VecDeque<(u32, RecordedEvent)>,
+    frame: u32,
+}
+
+impl InputReplay {
+    /// Reads a recording
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+        let file =
+            File::open(path).map_err(|err| format!("Couldn't open {}: {}", path.display(), err))?;
+        let mut bytes = Vec::new();
+        BufReader::new(file)
+            .read_to_end(&mut bytes)
+            .map_err(|err| format!("Couldn't read {}: {}", path.display(), err))?;
+        Self::from_bytes(&bytes).map_err(|err| format!("{}: {}", path.display(), err))
+    }
+
+    /// Reads a recording that is already in memory
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let mut body = bytes
+            .strip_prefix(RECORDING_MAGIC)
+            .ok_or_else(|| "Not an input recording".to_string())?;
+
+        let mut events = VecDeque::new();
+        while !body.is_empty() {
+            let frame = body
+                .get(..size_of::<u32>())
+                .ok_or_else(|| "The input recording is truncated".to_string())?;
+            let frame = u32::from_le_bytes(frame.try_into().unwrap());
+            body = &body[size_of::<u32>()..];
+            events.push_back((frame, RecordedEvent::read_from(&mut body)?));
+        }
+
+        Ok(InputReplay { events, frame: 0 })
+    }
+
+    /// Takes the events of the current frame then moves on to the next frame
+    pub fn next_frame(&mut self) -> Vec<SDL_Event> {
+        let mut events = Vec::new();
+        while let Some((_, event)) = self
+            .events
+            .front()
+            .filter(|(frame, _)| *frame <= self.frame)
+        {
+            events.push(event.to_sdl());
+            self.events.pop_front();
+        }
+        self.frame += 1;
+        events
+    }
+
+    /// Checks if every recorded event was played back
+    pub fn is_finished(&self) -> bool {
+        self.events.is_empty()
+    }
+}
+
+/// Converts the null terminated UTF-8 text of an SDL text event
+fn text_to_string(text: &[fermium::c_char; 32]) -> String {
+    let bytes: Vec<u8> = text
+        .iter()
+        .take_while(|c| **c != 0)
+        .map(|c| *c as u8)
+        .collect();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// Starts sending [EngineEvent::TextInput] events and shows the IME if there is one
+pub fn start_text_input() {
+    unsafe { fermium::SDL_StartTextInput() }
+}
+
+/// Stops sending [EngineEvent::TextInput] events
+pub fn stop_text_input() {
+    unsafe { fermium::SDL_StopTextInput() }
+}
+
+/// Checks if text input is on
+pub fn is_text_input_active() -> bool {
+    unsafe { fermium::SDL_IsTextInputActive() == fermium::SDL_TRUE }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_recording_is_replayed_in_the_same_frames() {
+        let mut text = [0; 32];
+        text[..2].copy_from_slice(b"hi");
+        let first = [
+            RecordedEvent::Key {
+                pressed: true,
+                window_id: 1,
+                repeat: 0,
+                scancode: 4,
+                keycode: 97,
+                modifiers: 1,
+            },
+            RecordedEvent::TextInput { window_id: 1, text },
+        ];
+        let third = [RecordedEvent::MouseMotion {
+            window_id: 1,
+            mouse_id: 0,
+            state: 0,
+            x: 10,
+            y: -20,
+            x_delta: 3,
+            y_delta: -4,
+        }];
+
+        let path = std::env::temp_dir().join(format!("lighthouse-{}.input", std::process::id()));
+        let mut recorder = InputRecorder::create(&path).unwrap();
+        recorder.record(&first.map(|event| event.to_sdl())).unwrap();
+        recorder.record(&[]).unwrap();
+        recorder.record(&third.map(|event| event.to_sdl())).unwrap();
+        recorder.finish().unwrap();
+
+        let mut replay = InputReplay::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let mut next_frame = || -> Vec<RecordedEvent> {
+            replay
+                .next_frame()
+                .iter()
+                .filter_map(RecordedEvent::from_sdl)
+                .collect()
+        };
+        assert_eq!(next_frame(), first);
+        assert_eq!(next_frame(), []);
+        assert_eq!(next_frame(), third);
+        assert!(replay.is_finished());
+    }
+
+    #[test]
+    fn a_truncated_recording_is_rejected() {
+        let mut bytes = RECORDING_MAGIC.to_vec();
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        RecordedEvent::Quit.write_to(&mut bytes);
+        assert!(InputReplay::from_bytes(&bytes).is_ok());
+
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.push(1);
+        assert!(InputReplay::from_bytes(&bytes).is_err());
+    }
+
+    #[test]
+    fn multibyte_text_arrives_intact() {
+        let mut text = [0; 32];
+        text[.."é".len()].copy_from_slice("é".as_bytes());
+        let event = RecordedEvent::TextInput { window_id: 1, text }.to_sdl();
+        match EngineEvent::from_sdl(Err(event)) {
+            Some(EngineEvent::TextInput(text)) => assert_eq!(text, "é"),
+            _ => panic!("SDL_TEXTINPUT wasn't turned into EngineEvent::TextInput"),
+        }
+
+        let mut text = [0; 32];
+        text[.."日本".len()].copy_from_slice("日本".as_bytes());
+        let event = RecordedEvent::TextEditing {
+            window_id: 1,
+            text,
+            start: 1,
+            length: 0,
+        }
+        .to_sdl();
+        match EngineEvent::from_sdl(Err(event)) {
+            Some(EngineEvent::TextEditing {
+                text,
+                start,
+                length,
+            }) => assert_eq!((text.as_str(), start, length), ("日本", 1, 0)),
+            _ => panic!("SDL_TEXTEDITING wasn't turned into EngineEvent::TextEditing"),
+        }
+    }
+
+    #[test]
+    fn only_size_changes_are_resizes() {
+        let window = |event| {
+            EngineEvent::Sdl(Event::Window(WindowEvent {
+                timestamp: 0,
+                window_id: 1,
+                event,
+            }))
+        };
+        assert!(is_resize(&window(WindowEventEnum::SizeChanged)));
+        assert!(!is_resize(&window(WindowEventEnum::Moved { x: 10, y: 20 })));
+        assert!(!is_resize(&window(WindowEventEnum::KeyboardFocusLost)));
+    }
+}
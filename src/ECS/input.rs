@@ -0,0 +1,119 @@
+use device_query::{Keycode, MouseState};
+
+use super::mouse::MousePressed;
+
+/// A snapshot of the keys and mouse buttons held this frame and the frame before, so any game
+/// logic can ask what is held without a controller, see
+/// [Enviroment::input](super::world::Enviroment::input)
+///
+/// # Example
+/// ```
+/// let input = world.env.input();
+/// if input.is_key_down(Keycode::W) {
+///     player.pos.z += 0.1;
+/// }
+/// if input.was_key_pressed(Keycode::Space) {
+///     player.jump();
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InputState {
+    keys: Vec<Keycode>,
+    previous_keys: Vec<Keycode>,
+    buttons: Vec<bool>,
+    previous_buttons: Vec<bool>,
+}
+
+impl InputState {
+    /// Creates a snapshot where nothing is held
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Moves the current state to the previous frame then stores the new one
+    pub fn update(&mut self, keys: Vec<Keycode>, mouse: &MouseState) {
+        self.previous_keys = std::mem::replace(&mut self.keys, keys);
+        self.previous_buttons = std::mem::replace(&mut self.buttons, mouse.button_pressed.clone());
+    }
+
+    /// Checks if the key is held this frame
+    pub fn is_key_down(&self, key: Keycode) -> bool {
+        self.keys.contains(&key)
+    }
+
+    /// Checks if the key started being held this frame
+    pub fn was_key_pressed(&self, key: Keycode) -> bool {
+        self.is_key_down(key) && !self.previous_keys.contains(&key)
+    }
+
+    /// Checks if the key stopped being held this frame
+    pub fn was_key_released(&self, key: Keycode) -> bool {
+        !self.is_key_down(key) && self.previous_keys.contains(&key)
+    }
+
+    /// Every key held this frame
+    pub fn keys(&self) -> &[Keycode] {
+        &self.keys
+    }
+
+    /// Checks if the mouse button is held this frame
+    pub fn is_mouse_down(&self, button: MousePressed) -> bool {
+        is_held(&self.buttons, button)
+    }
+
+    /// Checks if the mouse button started being held this frame
+    pub fn was_mouse_pressed(&self, button: MousePressed) -> bool {
+        let index = button_index(&button);
+        self.buttons.get(index) == Some(&true) && self.previous_buttons.get(index) != Some(&true)
+    }
+
+    /// Checks if the mouse button stopped being held this frame
+    pub fn was_mouse_released(&self, button: MousePressed) -> bool {
+        let index = button_index(&button);
+        self.buttons.get(index) != Some(&true) && self.previous_buttons.get(index) == Some(&true)
+    }
+}
+
+/// The index of a button in [MouseState::button_pressed], 0 is unused by device_query
+fn button_index(button: &MousePressed) -> usize {
+    match button {
+        MousePressed::LeftMouse => 1,
+        MousePressed::RightMouse => 2,
+        MousePressed::MiddleMouse => 3,
+    }
+}
+
+/// Checks if a button is held in a list of buttons from [MouseState::button_pressed]
+fn is_held(buttons: &[bool], button: MousePressed) -> bool {
+    buttons.get(button_index(&button)) == Some(&true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn no_mouse() -> MouseState {
+        MouseState {
+            coords: (0, 0),
+            button_pressed: vec![false; 4],
+        }
+    }
+
+    #[test]
+    fn a_key_is_down_from_its_press_until_its_release() {
+        let mut input = InputState::new();
+        assert!(!input.is_key_down(Keycode::W));
+
+        input.update(vec![Keycode::W], &no_mouse());
+        assert!(input.is_key_down(Keycode::W));
+        assert!(input.was_key_pressed(Keycode::W));
+
+        input.update(vec![Keycode::W], &no_mouse());
+        assert!(input.is_key_down(Keycode::W));
+        assert!(!input.was_key_pressed(Keycode::W));
+
+        input.update(vec![], &no_mouse());
+        assert!(!input.is_key_down(Keycode::W));
+        assert!(input.was_key_released(Keycode::W));
+    }
+}
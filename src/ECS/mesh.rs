@@ -1,9 +1,20 @@
+use std::collections::HashMap;
 use std::mem::size_of;
 
-use crate::graphics::{buffer::*, vertex::VertexArray, *};
+use crate::graphics::{
+    buffer::*,
+    culling::Aabb,
+    material::Material,
+    memory::{forget_mesh, track_mesh},
+    shader::ShaderProgram,
+    vertex::{Attribute, VertexArray},
+    PolygonMode, *,
+};
 use ogl33::*;
 
 use super::*;
+use crate::math::{any_perpendicular, project_on_plane};
+use log::error;
 use nalgebra_glm::*;
 
 /// Sets and gets the position and rotaion of the object
@@ -141,16 +152,444 @@ pub trait VertexTrait: Copy {
     /// Returns the vertex after it has been transformed
     /// rotated and translated
     fn get_vertex(&self, pos: Vec3, rot: Vec4) -> Self;
+
+    /// Returns the position of the vertex
+    ///
+    /// By default this is the first 3 elements of [VertexTrait::as_list]
+    fn position(&self) -> Vec3 {
+        let list = self.as_list();
+        vec3(list[0], list[1], list[2])
+    }
+
+    /// Returns the normal of the vertex if it has one
+    ///
+    /// It is used by [Mesh::fix_winding], by default the vertex has no normal
+    fn normal(&self) -> Option<Vec3> {
+        None
+    }
+
+    /// Returns the tangent of the vertex if it has one, by default the vertex has no tangent
+    fn tangent(&self) -> Option<Vec3> {
+        None
+    }
+
+    /// Returns the texture coordinate of the vertex if it has one, it is used by
+    /// [Mesh::compute_tangents]. By default the vertex has no texture coordinate
+    fn tex_coord(&self) -> Option<Vec2> {
+        None
+    }
+
+    /// Stores the tangent made by [Mesh::compute_tangents] in the vertex and returns if it could,
+    /// by default the vertex has nowhere to store it and false is returned
+    ///
+    /// sign is 1.0 or -1.0 and is the handedness of the uvs, the bitangent in the shader is
+    /// cross(normal, tangent) * sign. It is usually stored as the w of a Vec4 attribute
+    fn set_tangent(&mut self, _tangent: Vec3, _sign: f32) -> bool {
+        false
+    }
+
+    /// Stores the normal made by [Mesh::recompute_normals_with_angle] in the vertex and returns if
+    /// it could, by default the vertex has nowhere to store it and false is returned
+    fn set_normal(&mut self, _normal: Vec3) -> bool {
+        false
+    }
+
+    /// The number of floats of every vertex attribute in order, it is used by [MeshBuilder]
+    ///
+    /// By default the whole vertex is a single attribute of [VertexTrait::SIZE] floats, it is
+    /// made from the fields by [impl_vertex]
+    fn layout() -> Vec<u32> {
+        vec![Self::SIZE]
+    }
+}
+
+/// A type that can be a field of a vertex made with [impl_vertex]
+pub trait VertexAttribute: Copy {
+    /// How many floats the attribute has, it is the size passed to glVertexAttribPointer
+    const SIZE: u32;
+
+    /// Appends the floats of the attribute to out
+    fn push_to(&self, out: &mut Vec<f32>);
+}
+
+impl VertexAttribute for f32 {
+    const SIZE: u32 = 1;
+
+    fn push_to(&self, out: &mut Vec<f32>) {
+        out.push(*self);
+    }
+}
+
+impl VertexAttribute for Vec2 {
+    const SIZE: u32 = 2;
+
+    fn push_to(&self, out: &mut Vec<f32>) {
+        out.extend_from_slice(self.as_slice());
+    }
+}
+
+impl VertexAttribute for Vec3 {
+    const SIZE: u32 = 3;
+
+    fn push_to(&self, out: &mut Vec<f32>) {
+        out.extend_from_slice(self.as_slice());
+    }
+}
+
+impl VertexAttribute for Vec4 {
+    const SIZE: u32 = 4;
+
+    fn push_to(&self, out: &mut Vec<f32>) {
+        out.extend_from_slice(self.as_slice());
+    }
+}
+
+/// A buffer with one value of a vertex attribute for every instance, e.g. a color, it is drawn
+/// with [Mesh::draw_instanced]
+///
+/// # Example
+/// ```
+/// let colors = InstanceBuffer::new(INSTANCE_COLOR_LOCATION, &[
+///     vec4(1.0, 0.0, 0.0, 1.0),
+///     vec4(0.0, 1.0, 0.0, 1.0),
+///     vec4(0.0, 0.0, 1.0, 1.0),
+/// ])?;
+/// let offsets = InstanceBuffer::new(INSTANCE_OFFSET_LOCATION, &[
+///     vec3(-2.0, 0.0, 0.0),
+///     vec3(0.0, 0.0, 0.0),
+///     vec3(2.0, 0.0, 0.0),
+/// ])?;
+/// crate_mesh.shader_program = Some(ShaderProgram::instanced_color()?);
+/// crate_mesh.attach_instances(&[&colors, &offsets]);
+///
+/// // in GameObjectTrait::draw
+/// world.draw_mesh_instanced(&crate_mesh, &[&colors, &offsets]);
+/// ```
+pub struct InstanceBuffer {
+    /// The location of the attribute in the vertex shader
+    pub location: u32,
+    /// How many floats the attribute has
+    size: u32,
+    /// How many instances have a value
+    len: usize,
+    buffer: Buffer,
+}
+
+impl InstanceBuffer {
+    /// Creates a buffer for the attribute at location and uploads a value for every instance
+    pub fn new<T: VertexAttribute>(location: u32, values: &[T]) -> Result<Self, String> {
+        let mut out = InstanceBuffer {
+            location,
+            size: T::SIZE,
+            len: 0,
+            buffer: Buffer::new().ok_or_else(|| "Couldn't make an instance buffer".to_string())?,
+        };
+        out.upload(values)?;
+        Ok(out)
+    }
+
+    /// Replaces the values, e.g. every frame for moving debris. They must have the same number
+    /// of floats as the values the buffer was created with
+    pub fn upload<T: VertexAttribute>(&mut self, values: &[T]) -> Result<(), String> {
+        if T::SIZE != self.size {
+            return Err(format!(
+                "The instance buffer has {} floats per instance but the values have {}",
+                self.size,
+                T::SIZE
+            ));
+        }
+        let mut data = Vec::with_capacity(values.len() * T::SIZE as usize);
+        for value in values {
+            value.push_to(&mut data);
+        }
+        self.buffer.bind(BufferType::Array);
+        buffer_data(
+            BufferType::Array,
+            bytemuck::cast_slice(&data),
+            BufferUsage::Dynamic as u32,
+        );
+        self.len = values.len();
+        Ok(())
+    }
+
+    /// How many instances have a value
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Checks if no instance has a value
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Deletes the buffer
+    pub fn delete(self) {
+        self.buffer.delete();
+    }
+}
+
+/// Rotates a position by rot (the axis is xyz and the angle is w) then moves it by offset, it is
+/// used by the [VertexTrait::get_vertex] made by [impl_vertex]
+pub fn transform_position(position: &Vec3, offset: Vec3, rot: Vec4) -> Vec3 {
+    rotate_vec3(position, rot.w, &rot.xyz()) + offset
+}
+
+#[macro_export]
+/// Automaticly implement [VertexTrait] for you so [VertexTrait::SIZE] and
+/// [VertexTrait::as_list] can't get out of sync
+///
+/// The fields are listed with their types in the order of the attributes and every type must
+/// implement [VertexAttribute]. The first field is the position and must be a Vec3, it is the
+/// only field that is transformed by [VertexTrait::get_vertex]. It also makes a `layout()`
+/// function with the vertex attributes that [VertexTrait::layout] returns
+///
+/// # Example
+/// ```
+/// #[derive(Copy, Clone)]
+/// struct Vertex {
+///     pos: Vec3,
+///     tex_coord: Vec2,
+///     color: Vec4,
+/// }
+///
+/// impl_vertex!(Vertex { pos: Vec3, tex_coord: Vec2, color: Vec4 });
+///
+/// assert_eq!(Vertex::SIZE, 9);
+/// assert_eq!(Vertex::layout(), vec![3, 2, 4]);
+/// ```
+macro_rules! impl_vertex {
+    ($vertex: ident { $pos: ident: $pos_ty: ty $(, $field: ident: $ty: ty)* $(,)? }) => {
+        impl $crate::ECS::mesh::VertexTrait for $vertex {
+            const SIZE: u32 = <$pos_ty as $crate::ECS::mesh::VertexAttribute>::SIZE
+                $(+ <$ty as $crate::ECS::mesh::VertexAttribute>::SIZE)*;
+
+            fn as_list(&self) -> Vec<f32> {
+                let mut out = Vec::with_capacity(Self::SIZE as usize);
+                $crate::ECS::mesh::VertexAttribute::push_to(&self.$pos, &mut out);
+                $($crate::ECS::mesh::VertexAttribute::push_to(&self.$field, &mut out);)*
+                out
+            }
+
+            fn get_vertex(&self, pos: $pos_ty, rot: ::nalgebra_glm::Vec4) -> Self {
+                let mut out = *self;
+                out.$pos = $crate::ECS::mesh::transform_position(&self.$pos, pos, rot);
+                out
+            }
+
+            fn position(&self) -> $pos_ty {
+                self.$pos
+            }
+
+            fn layout() -> Vec<u32> {
+                $vertex::layout()
+            }
+        }
+
+        impl $vertex {
+            /// The vertex attributes of the vertex in order, see VertexTrait::layout
+            pub fn layout() -> Vec<u32> {
+                vec![
+                    <$pos_ty as $crate::ECS::mesh::VertexAttribute>::SIZE
+                    $(, <$ty as $crate::ECS::mesh::VertexAttribute>::SIZE)*
+                ]
+            }
+        }
+    };
+}
+
+/// A vertex with a position and a color and nothing else, it is drawn without textures or
+/// lighting by [ShaderProgram::vertex_color](crate::graphics::shader::ShaderProgram::vertex_color)
+///
+/// # Example
+/// ```
+/// let triangle = MeshBuilder::new()
+///     .vertices(vec![
+///         ColoredVertex::new(vec3(-0.5, -0.5, 0.0), vec4(1.0, 0.0, 0.0, 1.0)),
+///         ColoredVertex::new(vec3(0.5, -0.5, 0.0), vec4(0.0, 1.0, 0.0, 1.0)),
+///         ColoredVertex::new(vec3(0.0, 0.5, 0.0), vec4(0.0, 0.0, 1.0, 1.0)),
+///     ])
+///     .triangles(vec![[0, 1, 2]])
+///     .build()?;
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColoredVertex {
+    /// The position of the vertex
+    pub pos: Vec3,
+    /// The color of the vertex in rgba
+    pub color: Vec4,
+}
+
+impl ColoredVertex {
+    /// The vertex attributes of a colored vertex, the position then the color
+    pub const ATTRIBUTES: [u32; 2] = [3, 4];
+
+    /// Creates a new colored vertex
+    pub fn new(pos: Vec3, color: Vec4) -> Self {
+        ColoredVertex { pos, color }
+    }
+}
+
+impl_vertex!(ColoredVertex {
+    pos: Vec3,
+    color: Vec4
+});
+
+/// A vertex with a position, texture coordinate, normal and tangent, the attributes are in the
+/// order the default shader reads them so it is lit with the normal
+///
+/// [Mesh::compute_tangents] stores the tangent in it, the w of the tangent is the handedness of
+/// the uvs, and [Mesh::recompute_normals_with_angle] stores the normal
+///
+/// # Example
+/// ```
+/// let mut wall: Mesh<LitVertex> = MeshBuilder::new()
+///     .vertices(vec![
+///         LitVertex::new(vec3(0.0, 0.0, 0.0), vec2(0.0, 0.0), vec3(0.0, 0.0, 1.0)),
+///         LitVertex::new(vec3(1.0, 0.0, 0.0), vec2(1.0, 0.0), vec3(0.0, 0.0, 1.0)),
+///         LitVertex::new(vec3(0.0, 1.0, 0.0), vec2(0.0, 1.0), vec3(0.0, 0.0, 1.0)),
+///     ])
+///     .triangles(vec![[0, 1, 2]])
+///     .build()?;
+/// wall.compute_tangents()?;
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LitVertex {
+    /// The position of the vertex
+    pub pos: Vec3,
+    /// The texture coordinate of the vertex
+    pub tex_coord: Vec2,
+    /// The normal of the vertex
+    pub normal: Vec3,
+    /// The tangent of the vertex, w is 1.0 or -1.0 and is the sign of the bitangent
+    pub tangent: Vec4,
+}
+
+impl LitVertex {
+    /// The vertex attributes of a lit vertex, the position, texture coordinate, normal then
+    /// tangent
+    pub const ATTRIBUTES: [u32; 4] = [3, 2, 3, 4];
+
+    /// Creates a new lit vertex with no tangent, see [Mesh::compute_tangents]
+    pub fn new(pos: Vec3, tex_coord: Vec2, normal: Vec3) -> Self {
+        LitVertex {
+            pos,
+            tex_coord,
+            normal,
+            tangent: Vec4::zeros(),
+        }
+    }
+}
+
+impl VertexTrait for LitVertex {
+    const SIZE: u32 = 12;
+
+    fn as_list(&self) -> Vec<f32> {
+        let mut out = Vec::with_capacity(Self::SIZE as usize);
+        self.pos.push_to(&mut out);
+        self.tex_coord.push_to(&mut out);
+        self.normal.push_to(&mut out);
+        self.tangent.push_to(&mut out);
+        out
+    }
+
+    /// The normal and tangent are rotated with the position
+    fn get_vertex(&self, pos: Vec3, rot: Vec4) -> Self {
+        let tangent = rotate_vec3(&self.tangent.xyz(), rot.w, &rot.xyz());
+        LitVertex {
+            pos: transform_position(&self.pos, pos, rot),
+            tex_coord: self.tex_coord,
+            normal: rotate_vec3(&self.normal, rot.w, &rot.xyz()),
+            tangent: tangent.push(self.tangent.w),
+        }
+    }
+
+    fn position(&self) -> Vec3 {
+        self.pos
+    }
+
+    fn normal(&self) -> Option<Vec3> {
+        Some(self.normal)
+    }
+
+    fn tangent(&self) -> Option<Vec3> {
+        Some(self.tangent.xyz())
+    }
+
+    fn tex_coord(&self) -> Option<Vec2> {
+        Some(self.tex_coord)
+    }
+
+    fn set_tangent(&mut self, tangent: Vec3, sign: f32) -> bool {
+        self.tangent = tangent.push(sign);
+        true
+    }
+
+    fn set_normal(&mut self, normal: Vec3) -> bool {
+        self.normal = normal;
+        true
+    }
+
+    fn layout() -> Vec<u32> {
+        Self::ATTRIBUTES.to_vec()
+    }
+}
+
+/// How the indicies of a [Mesh] are put together into primitives
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Topology {
+    /// Every 3 indicies are a triangle
+    #[default]
+    Triangles = GL_TRIANGLES as isize,
+    /// Every index after the first 2 makes a triangle with the 2 before it
+    TriangleStrip = GL_TRIANGLE_STRIP as isize,
+    /// Every index after the first 2 makes a triangle with the one before it and the first one
+    TriangleFan = GL_TRIANGLE_FAN as isize,
+    /// Every 2 indicies are a line
+    Lines = GL_LINES as isize,
+    /// Every index after the first makes a line with the one before it
+    LineStrip = GL_LINE_STRIP as isize,
+    /// Every index is a point
+    Points = GL_POINTS as isize,
+}
+
+/// A part of the indicies of a [Mesh] that is drawn with its own material, so one vertex buffer
+/// can hold several parts of a model e.g. the body and glass of a car. See [Mesh::ranges]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DrawRange {
+    /// The first index that is drawn, 3 per triangle
+    pub index_offset: usize,
+    /// How many indicies are drawn
+    pub index_count: usize,
+    /// The material applied before the range is drawn, if it is None [Mesh::material] is used
+    pub material: Option<Material>,
+}
+
+impl DrawRange {
+    /// Creates a new range of indicies
+    pub fn new(index_offset: usize, index_count: usize, material: Option<Material>) -> Self {
+        DrawRange {
+            index_offset,
+            index_count,
+            material,
+        }
+    }
+
+    /// Creates a range over some of the triangles of a mesh
+    pub fn triangles(first: usize, count: usize, material: Option<Material>) -> Self {
+        Self::new(first * 3, count * 3, material)
+    }
 }
 
 /// Mesh for your object
 #[derive(Component)]
 pub struct Mesh<Vertex: VertexTrait + 'static + Sync + Send> {
-    /// The vertices of your object
-    pub vertices: Vec<Vertex>,
+    /// The vertices of your object, they are changed with [Mesh::set_vertices] so the cached
+    /// bounds stay right
+    vertices: Vec<Vertex>,
     /// This is the size of the vertex attributes
     pub vert_attr: Vec<u32>,
-    /// The indicies for vertices
+    /// The indicies for vertices as one flat list, they are put together into primitives by
+    /// [Mesh::topology], e.g. every 3 are a triangle
     /// # Example
     /// ```
     /// Mesh<Vertex> {
@@ -161,10 +600,59 @@ pub struct Mesh<Vertex: VertexTrait + 'static + Sync + Send> {
     ///         Vertex([2, 1, 3])
     ///         // -snip-
     ///     ]
-    ///     indicies: vec![[1, 2, 3], [2, 1, 2], [2, 1, 1]]
+    ///     indicies: vec![1, 2, 3, 2, 1, 2, 2, 1, 1]
     /// }
     /// ```
-    pub indicies: Vec<[u32; 3]>,
+    pub indicies: Vec<u32>,
+    /// How the indicies are drawn, it is [Topology::Triangles] by default
+    pub topology: Topology,
+    /// When it is Some [Mesh::draw] enables primitive restart with this index, so several strips
+    /// or fans can be drawn with one call by putting the index between them. It is only useful
+    /// with the strip and fan topologies and is None by default
+    ///
+    /// # Example
+    /// ```
+    /// // two disconnected strips in one draw call
+    /// mesh.topology = Topology::TriangleStrip;
+    /// mesh.restart_index = Some(u32::MAX);
+    /// mesh.update_indices(&[0, 1, 2, 3, u32::MAX, 4, 5, 6, 7])?;
+    /// ```
+    pub restart_index: Option<u32>,
+    /// How often the vertices and indicies are changed, it is passed to glBufferData
+    pub usage: BufferUsage,
+    /// How many times bigger the vertex and index buffers are made when the data outgrows them,
+    /// so meshes that keep growing aren't reallocated every update, see [buffer_data_growing].
+    /// Data that fits is written into the existing buffers. It is 1.0 (exact) for
+    /// [BufferUsage::Static] meshes and 1.5 otherwise
+    pub growth: f32,
+    /// The program the mesh is drawn with by [World::draw_mesh](super::world::World::draw_mesh),
+    /// if it is None the program of the enviroment is used. It is None by default
+    pub shader_program: Option<ShaderProgram>,
+    /// When it is Some [Mesh::draw] draws the mesh with this [PolygonMode] then restores the
+    /// previous one, e.g. to draw only the selected mesh in wireframe. It is None by default
+    pub render_mode: Option<PolygonMode>,
+    /// The material [World::draw_mesh](super::world::World::draw_mesh) applies before drawing
+    /// the mesh, see [Mesh::set_material]. It is None by default
+    pub material: Option<Material>,
+    /// The parts of the indicies [World::draw_mesh](super::world::World::draw_mesh) draws one
+    /// after another with their own materials, the vertices are shared. When it is empty the
+    /// whole mesh is drawn with [Mesh::material], it is empty by default
+    ///
+    /// # Example
+    /// ```
+    /// // an atlased crate and barrel sharing one vertex buffer
+    /// mesh.add_range(DrawRange::triangles(0, 12, Some(wood)))?;
+    /// mesh.add_range(DrawRange::triangles(12, 64, Some(metal)))?;
+    /// world.draw_mesh(&mesh);
+    /// ```
+    pub ranges: Vec<DrawRange>,
+    /// The cached bounding box and bounding sphere(center, radius) of the vertices
+    bounds: Option<(Aabb, Vec3, f32)>,
+    /// The position and rotation the vertices were last uploaded with, see [Mesh::update_mesh]
+    placement: (Vec3, Vec4),
+    /// The locations and buffer ids of the [InstanceBuffer]s whose layout is set in the vao, see
+    /// [Mesh::attach_instances]
+    instances: Vec<(u32, u32)>,
     vao: VertexArray,
     vbo: Buffer,
     ebo: Buffer,
@@ -172,34 +660,571 @@ pub struct Mesh<Vertex: VertexTrait + 'static + Sync + Send> {
 
 impl<Vertex: VertexTrait + 'static + Sync + Send> Mesh<Vertex> {
     /// Creates a new Mesh
+    ///
+    /// Both vert and index can be empty, in which case [Mesh::draw] does nothing. It is an error
+    /// to have indicies without any vertices
+    #[deprecated(
+        note = "use MeshBuilder, it gets the vertex attributes from VertexTrait::layout and checks the indicies"
+    )]
     pub fn new(
         vert: Vec<Vertex>,
         vert_attr: Vec<u32>,
         index: Vec<[u32; 3]>,
+        usage: BufferUsage,
+    ) -> Result<Mesh<Vertex>, String> {
+        Self::create(vert, vert_attr, index.concat(), usage)
+    }
+
+    /// Makes the mesh for [Mesh::new] and [MeshBuilder::build]
+    fn create(
+        vert: Vec<Vertex>,
+        vert_attr: Vec<u32>,
+        index: Vec<u32>,
+        usage: BufferUsage,
     ) -> Result<Mesh<Vertex>, String> {
-        if vert[0].as_list().len() != (&vert_attr).iter().sum::<u32>().try_into().unwrap() {
-            return Err(format!("The sum of the vertex attributes {} must be equal to the number of element in the vertex {}", (&vert_attr).iter().sum::<u32>(), vert[0].as_list().len()));
+        if vert.is_empty() && !index.is_empty() {
+            return Err(format!(
+                "The mesh has {} indicies but no vertices for them to index",
+                index.len()
+            ));
+        }
+
+        debug_assert!(
+            vert.is_empty() || vert[0].as_list().len() as u32 == Vertex::SIZE,
+            "VertexTrait::SIZE is {} but as_list has {} elements",
+            Vertex::SIZE,
+            vert[0].as_list().len()
+        );
+
+        if !vert.is_empty()
+            && vert[0].as_list().len() != vert_attr.iter().sum::<u32>().try_into().unwrap()
+        {
+            return Err(format!("The sum of the vertex attributes {} must be equal to the number of element in the vertex {}", vert_attr.iter().sum::<u32>(), vert[0].as_list().len()));
         }
 
         let out = Mesh {
             vertices: vert,
             vert_attr,
             indicies: index,
-            vao: VertexArray::new().expect("Couldn't make a VAO"),
-            vbo: Buffer::new().expect("Couldn't make a VBO"),
-            ebo: Buffer::new().expect("Couldn't make EBO"),
+            usage,
+            growth: if usage == BufferUsage::Static {
+                1.0
+            } else {
+                1.5
+            },
+            topology: Topology::Triangles,
+            restart_index: None,
+            shader_program: None,
+            render_mode: None,
+            material: None,
+            ranges: Vec::new(),
+            bounds: None,
+            placement: (Vec3::zeros(), vec4(0.0, 1.0, 0.0, 0.0)),
+            instances: Vec::new(),
+            vao: VertexArray::new().unwrap_or_else(|| {
+                error!("Couldn't make a VAO");
+                panic!("Couldn't make a VAO")
+            }),
+            vbo: Buffer::new().unwrap_or_else(|| {
+                error!("Couldn't make a VBO");
+                panic!("Couldn't make a VBO")
+            }),
+            ebo: Buffer::new().unwrap_or_else(|| {
+                error!("Couldn't make EBO");
+                panic!("Couldn't make EBO")
+            }),
         };
+        out.setup();
+        out.upload();
 
         Ok(out)
     }
 
+    /// Combines several static meshes into one so they can be drawn with a single call
+    ///
+    /// Every mesh is paired with the position and rotation that is baked into its vertices,
+    /// the indicies of each mesh are offset by the number of vertices that came before it.
+    /// All meshes must have the same vertex attributes and use [Topology::Triangles]
+    ///
+    /// # Example
+    /// ```
+    /// let level = Mesh::merge(&[
+    ///     (&wall, vec3(0.0, 0.0, 0.0), vec4(0.0, 1.0, 0.0, 0.0)),
+    ///     (&wall, vec3(2.0, 0.0, 0.0), vec4(0.0, 1.0, 0.0, 0.0)),
+    /// ])?;
+    /// ```
+    pub fn merge(meshes: &[(&Mesh<Vertex>, Vec3, Vec4)]) -> Result<Mesh<Vertex>, String> {
+        let vert_attr = match meshes.first() {
+            Some((mesh, _, _)) => mesh.vert_attr.clone(),
+            None => return Err("Cannot merge an empty list of meshes".to_string()),
+        };
+
+        for (mesh, _, _) in meshes {
+            if mesh.vert_attr != vert_attr {
+                return Err(format!(
+                    "Cannot merge meshes with vertex attributes {:?} and {:?}",
+                    vert_attr, mesh.vert_attr
+                ));
+            }
+            if mesh.topology != Topology::Triangles {
+                return Err(format!(
+                    "Only meshes with Topology::Triangles can be merged not {:?}",
+                    mesh.topology
+                ));
+            }
+        }
+
+        let (vert, index) = merge_geometry(
+            &meshes
+                .iter()
+                .map(|(mesh, pos, rot)| (&mesh.vertices[..], &mesh.indicies[..], *pos, *rot))
+                .collect::<Vec<_>>(),
+        );
+        Mesh::create(vert, vert_attr, index, BufferUsage::Static)
+    }
+
+    /// Flips the index order of every triangle that isn't wound counter clockwise relative to
+    /// its face normal so back face culling hides the right faces
+    ///
+    /// The face normal is the average of the vertex normals from [VertexTrait::normal]. If the
+    /// vertex has no normals the triangles are wound the same way as their neighbours across
+    /// shared edges, then every connected part faces away from the center of the mesh, a part
+    /// that is flat keeps the winding of its first triangle. Returns how many triangles were
+    /// flipped and uploads the indicies again if any were, meshes that don't use
+    /// [Topology::Triangles] aren't changed
+    ///
+    /// It is an error for an index to be out of the vertices
+    pub fn fix_winding(&mut self) -> Result<usize, String> {
+        if self.topology != Topology::Triangles {
+            return Ok(0);
+        }
+        check_indices(
+            &self.indicies,
+            self.topology,
+            self.vertices.len(),
+            self.restart_index,
+        )?;
+
+        let positions: Vec<Vec3> = self
+            .vertices
+            .iter()
+            .map(|vertex| vertex.position())
+            .collect();
+        let normals: Option<Vec<Vec3>> =
+            self.vertices.iter().map(|vertex| vertex.normal()).collect();
+        let flipped = fix_triangle_winding(
+            &positions,
+            normals.as_deref(),
+            &mut self.indicies,
+            self.restart_index,
+        );
+
+        if flipped > 0 {
+            self.upload();
+        }
+        Ok(flipped)
+    }
+
+    /// Computes the tangent of every vertex from the positions and uvs of the triangles and
+    /// stores it with [VertexTrait::set_tangent], for normal mapping
+    ///
+    /// The tangents of all the triangles that share a vertex are averaged then made perpendicular
+    /// to the normal of the vertex with Gram-Schmidt, the normal is [VertexTrait::normal] or the
+    /// averaged face normal if the vertex has none. Triangles with degenerate uvs don't add to
+    /// the average and a vertex with no usable tangent gets an arbitrary one perpendicular to its
+    /// normal instead of NaN. The mesh must use [Topology::Triangles], the vertices are uploaded
+    /// again afterwards
+    ///
+    /// It is an error if an index is out of the vertices or the vertices have no
+    /// [VertexTrait::tex_coord] or can't store a tangent, e.g. use [LitVertex]
+    ///
+    /// # Example
+    /// ```
+    /// let mut wall: Mesh<LitVertex> = MeshBuilder::new().vertices(vert).triangles(index).build()?;
+    /// wall.compute_tangents()?;
+    /// ```
+    pub fn compute_tangents(&mut self) -> Result<(), String> {
+        if self.topology != Topology::Triangles {
+            return Err(format!(
+                "Tangents can only be computed for Topology::Triangles not {:?}",
+                self.topology
+            ));
+        }
+        check_indices(
+            &self.indicies,
+            self.topology,
+            self.vertices.len(),
+            self.restart_index,
+        )?;
+
+        let tangents = vertex_tangents(&self.vertices, &self.indicies, self.restart_index)?;
+        for (vertex, (tangent, sign)) in self.vertices.iter_mut().zip(tangents) {
+            if !vertex.set_tangent(tangent, sign) {
+                return Err("The vertices of the mesh can't store a tangent".to_string());
+            }
+        }
+
+        self.upload();
+        Ok(())
+    }
+
+    /// Computes smooth normals that keep the edges sharper than max_angle hard and stores them
+    /// with [VertexTrait::set_normal], max_angle is in degrees
+    ///
+    /// Every corner of a triangle gets the area weighted average of the normals of the triangles
+    /// around its position whose normal is at most max_angle from the normal of its own triangle,
+    /// so nearly flat surfaces are smoothed while e.g. the edges of a cube stay hard. Triangles
+    /// around a position are found by the position and not the index, so vertices that are
+    /// already split are smoothed too. A vertex whose corners get different normals is split
+    /// into one vertex per normal and the indicies are changed to use them. The mesh must use
+    /// [Topology::Triangles], the vertices and indicies are uploaded again afterwards
+    ///
+    /// It is an error if an index is out of the vertices or the vertices can't store a normal,
+    /// e.g. use [LitVertex]
+    ///
+    /// # Example
+    /// ```
+    /// // a cube keeps a hard normal per face at its corners
+    /// let mut cube: Mesh<LitVertex> = MeshBuilder::new().vertices(vert).triangles(index).build()?;
+    /// cube.recompute_normals_with_angle(30.0)?;
+    ///
+    /// // two triangles folded by 5 degrees share a smoothed normal along their edge
+    /// let mut fold: Mesh<LitVertex> = MeshBuilder::new().vertices(vert).triangles(index).build()?;
+    /// fold.recompute_normals_with_angle(30.0)?;
+    /// ```
+    pub fn recompute_normals_with_angle(&mut self, max_angle: f32) -> Result<(), String> {
+        if self.topology != Topology::Triangles {
+            return Err(format!(
+                "Normals can only be computed for Topology::Triangles not {:?}",
+                self.topology
+            ));
+        }
+        if let Some(vertex) = self.vertices.first() {
+            let mut vertex = *vertex;
+            if !vertex.set_normal(Vec3::zeros()) {
+                return Err("The vertices of the mesh can't store a normal".to_string());
+            }
+        }
+        check_indices(
+            &self.indicies,
+            self.topology,
+            self.vertices.len(),
+            self.restart_index,
+        )?;
+
+        smooth_normals(
+            &mut self.vertices,
+            &mut self.indicies,
+            self.restart_index,
+            max_angle,
+        );
+        // the split vertices have to be on the gpu before any index can use them
+        self.bounds = None;
+        self.upload();
+
+        Ok(())
+    }
+
+    /// The vertices of the mesh
+    pub fn vertices(&self) -> &[Vertex] {
+        &self.vertices
+    }
+
+    /// Replaces the vertices of the mesh, uploads them with the last position and rotation and
+    /// invalidates the cached bounds
+    ///
+    /// It is an error for an index of the mesh to be out of the new vertices
+    ///
+    /// # Example
+    /// ```
+    /// let mut vert = wave.vertices().to_vec();
+    /// for vertex in &mut vert {
+    ///     vertex.pos.y = (vertex.pos.x + world.time()).sin();
+    /// }
+    /// wave.set_vertices(vert)?;
+    /// ```
+    pub fn set_vertices(&mut self, vert: Vec<Vertex>) -> Result<(), String> {
+        check_indices(
+            &self.indicies,
+            self.topology,
+            vert.len(),
+            self.restart_index,
+        )?;
+        self.vertices = vert;
+        self.bounds = None;
+        self.upload();
+        Ok(())
+    }
+
+    /// Computes the bounds of the vertices if they aren't cached
+    fn bounds(&mut self) -> (Aabb, Vec3, f32) {
+        let vertices = &self.vertices;
+        *self.bounds.get_or_insert_with(|| vertex_bounds(vertices))
+    }
+
+    /// Gets the axis aligned bounding box of the vertices in local space, it is cached until
+    /// the vertices change
+    ///
+    /// An empty mesh has a box with no size at the origin
+    pub fn aabb(&mut self) -> Aabb {
+        self.bounds().0
+    }
+
+    /// Gets the bounding sphere of the vertices in local space as (center, radius), it is
+    /// cached until the vertices change
+    ///
+    /// The center is the center of [Mesh::aabb] and the radius is the distance to the furthest
+    /// vertex. An empty mesh has a sphere with a radius of 0 at the origin
+    pub fn bounding_sphere(&mut self) -> (Vec3, f32) {
+        let (_, center, radius) = self.bounds();
+        (center, radius)
+    }
+
+    /// Swaps the material of the mesh, it is used from the next draw on
+    ///
+    /// # Example
+    /// ```
+    /// let mut damaged = *tank.mesh.material.as_ref().unwrap();
+    /// damaged.set_texture(TextureSlot::Diffuse, &scorched);
+    /// tank.mesh.set_material(damaged);
+    /// ```
+    pub fn set_material(&mut self, material: Material) {
+        self.material = Some(material);
+    }
+
+    /// How many indicies [Mesh::draw] draws, 3 per triangle
+    pub fn index_count(&self) -> usize {
+        self.indicies.len()
+    }
+
+    /// Adds a part of the indicies that is drawn with its own material, see [Mesh::ranges]
+    ///
+    /// It is an error for the range to go past the end of the indicies
+    pub fn add_range(&mut self, range: DrawRange) -> Result<(), String> {
+        check_range(&range, self.index_count())?;
+        self.ranges.push(range);
+        Ok(())
+    }
+
+    /// Replaces the indicies of the mesh and uploads them straight away, e.g. for procedural
+    /// geometry that adds or removes triangles
+    ///
+    /// The index buffer is only reallocated when the new indicies don't fit, then it grows by
+    /// [Mesh::growth], otherwise they are written over the start of it. It is an error for an
+    /// index to be out of the vertices or for the indicies not to make whole primitives of
+    /// [Mesh::topology]
+    ///
+    /// # Example
+    /// ```
+    /// let mut indicies = terrain.indicies.clone();
+    /// indicies.extend([0, 4, 5]);
+    /// terrain.update_indices(&indicies)?;
+    /// ```
+    pub fn update_indices(&mut self, indices: &[u32]) -> Result<(), String> {
+        check_indices(
+            indices,
+            self.topology,
+            self.vertices.len(),
+            self.restart_index,
+        )?;
+
+        // the index buffer binding is part of the VAO
+        self.vao.bind();
+        self.ebo.bind(BufferType::ElementArray);
+        buffer_data_growing(
+            BufferType::ElementArray,
+            bytemuck::cast_slice(indices),
+            self.usage as u32,
+            self.growth,
+        );
+        self.indicies = indices.to_vec();
+        debug_assert_eq!(self.validate(), Ok(()));
+        VertexArray::clear_binding();
+        track_mesh(self.vao.0, self.gpu_memory_bytes());
+        Ok(())
+    }
+
+    /// How many bytes the vertex buffer and index buffer of the mesh take on the gpu, it is 0
+    /// until the mesh is uploaded. It is the capacity of the buffers so it can be more than the
+    /// data of a mesh that grew, see [Mesh::growth]. Every mesh is also counted by
+    /// [World::total_gpu_memory](super::world::World::total_gpu_memory)
+    ///
+    /// # Example
+    /// ```
+    /// // 4 vertices of 8 floats and 2 triangles
+    /// assert_eq!(quad.gpu_memory_bytes(), 4 * 8 * 4 + 2 * 3 * 4);
+    /// ```
+    pub fn gpu_memory_bytes(&self) -> usize {
+        self.vbo.size() + self.ebo.size()
+    }
+
+    /// Checks that the vertex and index buffers hold all the vertices and indicies of the mesh
+    /// and that no index is out of the vertices, e.g. after a partial update. The VAO of the mesh
+    /// must be bound
+    ///
+    /// The buffers can be bigger than the data since they grow by [Mesh::growth], so only a
+    /// buffer that is too small fails. It is checked with debug_assert every time the
+    /// mesh is uploaded, release builds skip it
+    pub fn validate(&self) -> Result<(), String> {
+        let vertex_size = Vertex::SIZE as usize * size_of::<f32>();
+        let uploaded = self.vbo.size() / vertex_size;
+        if uploaded < self.vertices.len() {
+            return Err(format!(
+                "The mesh has {} vertices but its vertex buffer only has {}",
+                self.vertices.len(),
+                uploaded
+            ));
+        }
+
+        let mut size = 0;
+        unsafe { glGetBufferParameteriv(GL_ELEMENT_ARRAY_BUFFER, GL_BUFFER_SIZE, &mut size) };
+        let expected = self.indicies.len();
+        let uploaded = size as usize / size_of::<u32>();
+        if uploaded < expected {
+            return Err(format!(
+                "The mesh has {} indicies but its index buffer only has {}",
+                expected, uploaded
+            ));
+        }
+
+        check_indices(
+            &self.indicies,
+            self.topology,
+            self.vertices.len(),
+            self.restart_index,
+        )
+    }
+
+    /// Draws the mesh with its [Mesh::topology] and [Mesh::render_mode], it does nothing if the
+    /// mesh is empty
+    pub fn draw(&self) {
+        self.draw_range(0, self.index_count());
+    }
+
+    /// Draws index_count indicies starting at index_offset like [Mesh::draw], the range is
+    /// clamped to the indicies of the mesh and nothing is drawn if it is empty
+    pub fn draw_range(&self, index_offset: usize, index_count: usize) {
+        let index_count = index_count.min(self.index_count().saturating_sub(index_offset));
+        if index_count == 0 {
+            return;
+        }
+
+        self.vao.bind();
+        let previous_mode = self.render_mode.map(|mode| {
+            let previous = get_polygon_mode();
+            polygon_mode(mode);
+            previous
+        });
+        if let Some(restart_index) = self.restart_index {
+            enable(GL_PRIMITIVE_RESTART);
+            unsafe { glPrimitiveRestartIndex(restart_index) };
+        }
+        unsafe {
+            crate::gl_check!(glDrawElements(
+                self.topology as u32,
+                index_count.try_into().unwrap(),
+                GL_UNSIGNED_INT,
+                (index_offset * size_of::<u32>()) as *const _,
+            ));
+        }
+        if self.restart_index.is_some() {
+            disable(GL_PRIMITIVE_RESTART);
+        }
+        if let Some(previous) = previous_mode {
+            polygon_mode(previous);
+        }
+    }
+
+    /// Sets up the layout of the instance buffers in the vao of the mesh so they can be drawn
+    /// with [Mesh::draw_instanced], a buffer attached at the location of another one replaces
+    /// it. It only has to be called again for new buffers, not when the values are uploaded
+    ///
+    /// The locations of the instance buffers must not be used by the vertex attributes of the
+    /// mesh, see [ShaderProgram::instanced_color]
+    pub fn attach_instances(&mut self, instances: &[&InstanceBuffer]) {
+        for buffer in instances {
+            let attached = (buffer.location, buffer.buffer.0);
+            if self.instances.contains(&attached) {
+                continue;
+            }
+            self.vao.set_layout(
+                &buffer.buffer,
+                &[Attribute::per_instance(buffer.location, buffer.size as i32)],
+            );
+            // only draw_instanced turns it on so Mesh::draw isn't affected
+            unsafe { glDisableVertexAttribArray(buffer.location) };
+            self.instances
+                .retain(|(location, _)| *location != buffer.location);
+            self.instances.push(attached);
+        }
+    }
+
+    /// Draws one instance of the mesh for every value of the instance buffers, if they have a
+    /// different number of values the smallest one is used. The buffers must be attached with
+    /// [Mesh::attach_instances], their attributes are only turned on for this draw so
+    /// [Mesh::draw] isn't affected
+    pub fn draw_instanced(&self, instances: &[&InstanceBuffer]) {
+        let count = instances
+            .iter()
+            .map(|buffer| buffer.len())
+            .min()
+            .unwrap_or(0);
+        if self.indicies.is_empty() || count == 0 {
+            return;
+        }
+        if let Some(buffer) = instances
+            .iter()
+            .find(|buffer| !self.instances.contains(&(buffer.location, buffer.buffer.0)))
+        {
+            error!(
+                "The instance buffer at location {} isn't attached, see Mesh::attach_instances",
+                buffer.location
+            );
+            return;
+        }
+
+        self.vao.bind();
+        for buffer in instances {
+            unsafe { glEnableVertexAttribArray(buffer.location) };
+        }
+        let previous_mode = self.render_mode.map(|mode| {
+            let previous = get_polygon_mode();
+            polygon_mode(mode);
+            previous
+        });
+        if let Some(restart_index) = self.restart_index {
+            enable(GL_PRIMITIVE_RESTART);
+            unsafe { glPrimitiveRestartIndex(restart_index) };
+        }
+        unsafe {
+            crate::gl_check!(glDrawElementsInstanced(
+                self.topology as u32,
+                self.index_count().try_into().unwrap(),
+                GL_UNSIGNED_INT,
+                std::ptr::null(),
+                count.try_into().unwrap(),
+            ));
+        }
+        if self.restart_index.is_some() {
+            disable(GL_PRIMITIVE_RESTART);
+        }
+        unsafe {
+            for buffer in instances {
+                glDisableVertexAttribArray(buffer.location);
+            }
+        }
+        if let Some(previous) = previous_mode {
+            polygon_mode(previous);
+        }
+    }
+
     /// Setsup the mesh, is used for macro
     pub fn setup(&self) {
         self.vao.bind();
         self.vbo.bind(BufferType::Array);
         self.ebo.bind(BufferType::ElementArray);
 
-        for (i, attr) in (&self.vert_attr).iter().enumerate() {
+        for (i, attr) in self.vert_attr.iter().enumerate() {
             let pointer: u32 = size_of::<f32>().try_into().unwrap();
             let pointer = pointer * self.vert_attr[0..i].iter().sum::<u32>();
 
@@ -218,32 +1243,575 @@ impl<Vertex: VertexTrait + 'static + Sync + Send> Mesh<Vertex> {
         }
     }
 
-    /// Updates the mesh
-    fn update(&self, pos: Position, rot: Rotation) {
-        buffer_data(
+    /// Uploads the vertices moved to pos and rotated by rot (the axis is xyz and the angle is w)
+    /// and the indicies of the mesh, e.g. every frame for a mesh that moves. The mesh is uploaded
+    /// without moving it when it is made
+    ///
+    /// # Example
+    /// ```
+    /// pyramid.rot.w += 0.01;
+    /// pyramid.mesh.update_mesh(pyramid.pos, pyramid.rot);
+    /// ```
+    pub fn update_mesh(&mut self, pos: Vec3, rot: Vec4) {
+        self.placement = (pos, rot);
+        self.upload();
+    }
+
+    /// Uploads the vertices and indicies again with the last position and rotation, it must be
+    /// called after they are changed on the CPU
+    fn upload(&self) {
+        // the index buffer binding is part of the VAO
+        self.vao.bind();
+        self.vbo.bind(BufferType::Array);
+        self.ebo.bind(BufferType::ElementArray);
+        self.update(self.placement.0, self.placement.1);
+        debug_assert_eq!(self.validate(), Ok(()));
+        VertexArray::clear_binding();
+    }
+
+    /// Updates the mesh, the buffers are only reallocated when the data outgrows them, see
+    /// [Mesh::growth]
+    fn update(&self, pos: Vec3, rot: Vec4) {
+        buffer_data_growing(
             BufferType::Array,
             bytemuck::cast_slice(
                 &self
                     .vertices
                     .clone()
                     .iter()
-                    .flat_map(|vertex| vertex.get_vertex(pos.0, rot.0).as_list())
+                    .flat_map(|vertex| vertex.get_vertex(pos, rot).as_list())
                     .collect::<Vec<f32>>(),
             ),
-            GL_STATIC_DRAW,
+            self.usage as u32,
+            self.growth,
         );
-        buffer_data(
+        buffer_data_growing(
             BufferType::ElementArray,
             bytemuck::cast_slice(&self.indicies),
-            GL_STATIC_DRAW,
+            self.usage as u32,
+            self.growth,
         );
+        track_mesh(self.vao.0, self.gpu_memory_bytes());
+    }
+}
+/// Builder for [Mesh]
+///
+/// # Example
+/// ```
+/// // the vertices are the only required argument
+/// let pyramid = MeshBuilder::new()
+///     .vertices(vert)
+///     // Here are the optional ones, if they aren't set they are filled with these
+///     .triangles(index) // no triangles, or .indices with a flat list
+///     .layout(Vertex::layout()) // VertexTrait::layout
+///     .topology(Topology::Triangles)
+///     .restart_index(u32::MAX) // no primitive restart
+///     .usage(BufferUsage::Static)
+///     .material(material) // no material
+///     .shader_program(program) // the program of the enviroment
+///     .range(DrawRange::triangles(0, 4, Some(stone))) // no ranges, the whole mesh is drawn
+///     .build()?; // And finally build
+/// ```
+pub struct MeshBuilder<Vertex: VertexTrait + 'static + Sync + Send> {
+    /// The vertices of the mesh
+    vertices: Option<Vec<Vertex>>,
+    /// The indices of the mesh
+    indices: Vec<u32>,
+    /// The vertex attributes, it is VertexTrait::layout when it is None
+    layout: Option<Vec<u32>>,
+    /// How the indicies are drawn
+    topology: Topology,
+    /// The index that restarts a strip
+    restart_index: Option<u32>,
+    /// How often the mesh is changed
+    usage: BufferUsage,
+    /// How much the buffers grow, it is from the usage when it is None
+    growth: Option<f32>,
+    /// The material of the mesh
+    material: Option<Material>,
+    /// The program the mesh is drawn with
+    shader_program: Option<ShaderProgram>,
+    /// The parts of the indicies with their own material
+    ranges: Vec<DrawRange>,
+}
+
+impl<Vertex: VertexTrait + 'static + Sync + Send> MeshBuilder<Vertex> {
+    /// Creates a new mesh builder
+    pub fn new() -> Self {
+        MeshBuilder {
+            vertices: None,
+            indices: Vec::new(),
+            layout: None,
+            topology: Topology::Triangles,
+            restart_index: None,
+            usage: BufferUsage::Static,
+            growth: None,
+            material: None,
+            shader_program: None,
+            ranges: Vec::new(),
+        }
+    }
+
+    /// This function is supposed to set the vertices. It must be called
+    pub fn vertices(&mut self, vertices: Vec<Vertex>) -> &mut Self {
+        self.vertices = Some(vertices);
+        self
+    }
+
+    /// This function is supposed to set the indices as one flat list, see [Mesh::indicies]. It
+    /// is optional
+    pub fn indices(&mut self, indices: Vec<u32>) -> &mut Self {
+        self.indices = indices;
+        self
+    }
+
+    /// This function is supposed to set the indices of a [Topology::Triangles] mesh one triangle
+    /// at a time. It is optional
+    pub fn triangles(&mut self, triangles: Vec<[u32; 3]>) -> &mut Self {
+        self.indices = triangles.concat();
+        self
+    }
+
+    /// This function is supposed to override the vertex attributes from [VertexTrait::layout].
+    /// It is optional
+    pub fn layout(&mut self, layout: Vec<u32>) -> &mut Self {
+        self.layout = Some(layout);
+        self
+    }
+
+    /// This function is supposed to set the topology. It is optional
+    pub fn topology(&mut self, topology: Topology) -> &mut Self {
+        self.topology = topology;
+        self
+    }
+
+    /// This function is supposed to set the restart_index, see [Mesh::restart_index]. It is
+    /// optional
+    pub fn restart_index(&mut self, restart_index: u32) -> &mut Self {
+        self.restart_index = Some(restart_index);
+        self
+    }
+
+    /// This function is supposed to set the usage. It is optional
+    pub fn usage(&mut self, usage: BufferUsage) -> &mut Self {
+        self.usage = usage;
+        self
+    }
+
+    /// This function is supposed to set the growth, see [Mesh::growth]. It is optional
+    pub fn growth(&mut self, growth: f32) -> &mut Self {
+        self.growth = Some(growth);
+        self
+    }
+
+    /// This function is supposed to set the material. It is optional
+    pub fn material(&mut self, material: Material) -> &mut Self {
+        self.material = Some(material);
+        self
+    }
+
+    /// This function is supposed to set the shader_program. It is optional
+    pub fn shader_program(&mut self, shader_program: ShaderProgram) -> &mut Self {
+        self.shader_program = Some(shader_program);
+        self
+    }
+
+    /// This function is supposed to add a part of the indices with its own material, see
+    /// [Mesh::ranges]. It is optional
+    pub fn range(&mut self, range: DrawRange) -> &mut Self {
+        self.ranges.push(range);
+        self
+    }
+
+    /// Build the mesh, the vertices and indices are moved out of the builder
+    ///
+    /// It fails if the vertices weren't set, an index is out of the vertices, the indices don't
+    /// make whole primitives of the topology, a range is out of the indices or the layout dosen't
+    /// add up to [VertexTrait::SIZE]
+    pub fn build(&mut self) -> Result<Mesh<Vertex>, String> {
+        let vertices = self.vertices.take().ok_or_else(|| {
+            "Error: argument vertices is not satisfied\nhelp: you can call .vertices".to_string()
+        })?;
+        let indices = std::mem::take(&mut self.indices);
+        let layout = self.layout.clone().unwrap_or_else(Vertex::layout);
+
+        if layout.iter().sum::<u32>() != Vertex::SIZE {
+            return Err(format!(
+                "The vertex attributes {:?} add up to {} floats but a vertex has {}",
+                layout,
+                layout.iter().sum::<u32>(),
+                Vertex::SIZE
+            ));
+        }
+        check_indices(&indices, self.topology, vertices.len(), self.restart_index)?;
+        for range in &self.ranges {
+            check_range(range, indices.len())?;
+        }
+
+        let mut mesh = Mesh::create(vertices, layout, indices, self.usage)?;
+        if let Some(growth) = self.growth {
+            mesh.growth = growth;
+        }
+        mesh.topology = self.topology;
+        mesh.restart_index = self.restart_index;
+        mesh.material = self.material;
+        mesh.shader_program = self.shader_program;
+        mesh.ranges = std::mem::take(&mut self.ranges);
+        Ok(mesh)
     }
 }
+
+/// The bounding box and bounding sphere(center, radius) of the vertices, see [Mesh::aabb] and
+/// [Mesh::bounding_sphere]
+fn vertex_bounds<Vertex: VertexTrait>(vertices: &[Vertex]) -> (Aabb, Vec3, f32) {
+    let aabb = Aabb::from_points(vertices.iter().map(|vertex| vertex.position()))
+        .unwrap_or_else(|| Aabb::new(Vec3::zeros(), Vec3::zeros()));
+    let center = aabb.center();
+    let radius = vertices
+        .iter()
+        .map(|vertex| distance(&vertex.position(), &center))
+        .fold(0.0, f32::max);
+    (aabb, center, radius)
+}
+
+/// Concatenates the vertices moved to their position and rotation and offsets the indices of
+/// every part by the vertices before it, see [Mesh::merge]
+fn merge_geometry<Vertex: VertexTrait>(
+    parts: &[(&[Vertex], &[u32], Vec3, Vec4)],
+) -> (Vec<Vertex>, Vec<u32>) {
+    let mut vert = Vec::new();
+    let mut index = Vec::new();
+    for (vertices, indices, pos, rot) in parts {
+        let offset: u32 = vert.len().try_into().unwrap();
+        index.extend(indices.iter().map(|i| i + offset));
+        vert.extend(vertices.iter().map(|vertex| vertex.get_vertex(*pos, *rot)));
+    }
+    (vert, index)
+}
+
+/// The tangent and bitangent sign of every vertex for [Mesh::compute_tangents], the indices
+/// must be triangles that are in the vertices
+fn vertex_tangents<Vertex: VertexTrait>(
+    vertices: &[Vertex],
+    indices: &[u32],
+    restart_index: Option<u32>,
+) -> Result<Vec<(Vec3, f32)>, String> {
+    let mut tangents = vec![Vec3::zeros(); vertices.len()];
+    let mut bitangents = vec![Vec3::zeros(); vertices.len()];
+    let mut face_normals = vec![Vec3::zeros(); vertices.len()];
+    for triangle in indices.chunks_exact(3) {
+        if triangle.iter().any(|i| Some(*i) == restart_index) {
+            continue;
+        }
+        let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(|i| &vertices[i as usize]);
+        let (uv_a, uv_b, uv_c) = match (a.tex_coord(), b.tex_coord(), c.tex_coord()) {
+            (Some(uv_a), Some(uv_b), Some(uv_c)) => (uv_a, uv_b, uv_c),
+            _ => return Err("The vertices of the mesh have no texture coordinates".to_string()),
+        };
+
+        let edge_1 = b.position() - a.position();
+        let edge_2 = c.position() - a.position();
+        let normal = edge_1.cross(&edge_2);
+
+        let delta_1 = uv_b - uv_a;
+        let delta_2 = uv_c - uv_a;
+        let determinant = delta_1.x * delta_2.y - delta_2.x * delta_1.y;
+        // weighting by the area comes for free from not normalizing
+        let (tangent, bitangent) = if determinant.abs() > f32::EPSILON {
+            (
+                (edge_1 * delta_2.y - edge_2 * delta_1.y) / determinant,
+                (edge_2 * delta_1.x - edge_1 * delta_2.x) / determinant,
+            )
+        } else {
+            (Vec3::zeros(), Vec3::zeros())
+        };
+
+        for i in triangle {
+            tangents[*i as usize] += tangent;
+            bitangents[*i as usize] += bitangent;
+            face_normals[*i as usize] += normal;
+        }
+    }
+
+    Ok(vertices
+        .iter()
+        .enumerate()
+        .map(|(i, vertex)| {
+            let normal = vertex.normal().unwrap_or(face_normals[i]);
+            let normal = if normal.norm_squared() > 0.0 {
+                normal.normalize()
+            } else {
+                vec3(0.0, 0.0, 1.0)
+            };
+
+            let tangent = project_on_plane(&tangents[i], &normal);
+            let tangent = if tangent.norm_squared() > f32::EPSILON {
+                tangent.normalize()
+            } else {
+                any_perpendicular(&normal)
+            };
+            let sign = if normal.cross(&tangent).dot(&bitangents[i]) < 0.0 {
+                -1.0
+            } else {
+                1.0
+            };
+            (tangent, sign)
+        })
+        .collect())
+}
+
+/// Sets the normals of [Mesh::recompute_normals_with_angle] and splits the vertices whose
+/// corners get different normals, the indices must be triangles that are in the vertices and
+/// the vertices must be able to store a normal
+fn smooth_normals<Vertex: VertexTrait>(
+    vertices: &mut Vec<Vertex>,
+    indices: &mut [u32],
+    restart_index: Option<u32>,
+    max_angle: f32,
+) {
+    let is_restart = |triangle: &[u32]| triangle.iter().any(|i| Some(*i) == restart_index);
+    let positions: Vec<[u32; 3]> = vertices
+        .iter()
+        .map(|vertex| vertex.position().map(f32::to_bits).into())
+        .collect();
+
+    // weighting by the area comes for free from not normalizing
+    let face_normals: Vec<Vec3> = indices
+        .chunks_exact(3)
+        .map(|triangle| {
+            if is_restart(triangle) {
+                return Vec3::zeros();
+            }
+            let [a, b, c] =
+                [triangle[0], triangle[1], triangle[2]].map(|i| vertices[i as usize].position());
+            (b - a).cross(&(c - a))
+        })
+        .collect();
+
+    let mut around: HashMap<_, Vec<usize>> = HashMap::new();
+    for (t, triangle) in indices.chunks_exact(3).enumerate() {
+        if is_restart(triangle) {
+            continue;
+        }
+        for i in triangle {
+            let faces = around.entry(positions[*i as usize]).or_default();
+            if !faces.contains(&t) {
+                faces.push(t);
+            }
+        }
+    }
+
+    let min_cos = max_angle.to_radians().cos();
+    let mut split: HashMap<(u32, [u32; 3]), u32> = HashMap::new();
+    let mut normal_set = vec![false; vertices.len()];
+    for (t, triangle) in indices.chunks_exact_mut(3).enumerate() {
+        if is_restart(triangle) {
+            continue;
+        }
+        let face = face_normals[t].try_normalize(f32::EPSILON);
+        for i in triangle.iter_mut() {
+            let normal = match face {
+                Some(face) => around[&positions[*i as usize]]
+                    .iter()
+                    .filter(|other| {
+                        **other == t
+                            || face_normals[**other]
+                                .try_normalize(f32::EPSILON)
+                                .is_some_and(|other| face.dot(&other) >= min_cos)
+                    })
+                    .map(|other| face_normals[*other])
+                    .sum::<Vec3>()
+                    .try_normalize(f32::EPSILON)
+                    .unwrap_or(face),
+                None => vec3(0.0, 0.0, 1.0),
+            };
+
+            // the first corner keeps the vertex, corners with other normals get copies
+            let key = (*i, normal.map(f32::to_bits).into());
+            if !normal_set[*i as usize] {
+                normal_set[*i as usize] = true;
+                vertices[*i as usize].set_normal(normal);
+                split.insert(key, *i);
+            } else if let Some(copy) = split.get(&key) {
+                *i = *copy;
+            } else {
+                let mut vertex = vertices[*i as usize];
+                vertex.set_normal(normal);
+                let copy = vertices.len() as u32;
+                vertices.push(vertex);
+                split.insert(key, copy);
+                *i = copy;
+            }
+        }
+    }
+}
+
+/// Flips the triangles of [Mesh::fix_winding] and returns how many were flipped, the indices
+/// must already be in the positions
+fn fix_triangle_winding(
+    positions: &[Vec3],
+    normals: Option<&[Vec3]>,
+    indices: &mut [u32],
+    restart_index: Option<u32>,
+) -> usize {
+    let triangles: Vec<usize> = (0..indices.len() / 3)
+        .filter(|t| {
+            !indices[t * 3..t * 3 + 3]
+                .iter()
+                .any(|i| Some(*i) == restart_index)
+        })
+        .collect();
+    let corners = |indices: &[u32], t: usize| -> [usize; 3] {
+        [0, 1, 2].map(|corner| indices[t * 3 + corner] as usize)
+    };
+    let winding =
+        |[a, b, c]: [usize; 3]| (positions[b] - positions[a]).cross(&(positions[c] - positions[a]));
+
+    let mut flip = vec![false; indices.len() / 3];
+    if let Some(normals) = normals {
+        for &t in &triangles {
+            let [a, b, c] = corners(indices, t);
+            flip[t] = winding([a, b, c]).dot(&(normals[a] + normals[b] + normals[c])) < 0.0;
+        }
+    } else {
+        let center = positions.iter().sum::<Vec3>() / positions.len().max(1) as f32;
+        let mut edges: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+        for &t in &triangles {
+            let [a, b, c] = corners(indices, t);
+            for (from, to) in [(a, b), (b, c), (c, a)] {
+                edges
+                    .entry((from.min(to), from.max(to)))
+                    .or_default()
+                    .push(t);
+            }
+        }
+
+        let mut visited = vec![false; indices.len() / 3];
+        for &seed in &triangles {
+            if visited[seed] {
+                continue;
+            }
+            visited[seed] = true;
+            let mut part = vec![seed];
+            let mut next = 0;
+            while next < part.len() {
+                let t = part[next];
+                next += 1;
+                let [a, b, c] = corners(indices, t);
+                for (from, to) in [(a, b), (b, c), (c, a)] {
+                    // the edge as it is walked once t is flipped
+                    let (from, to) = if flip[t] { (to, from) } else { (from, to) };
+                    for &other in &edges[&(from.min(to), from.max(to))] {
+                        if visited[other] {
+                            continue;
+                        }
+                        visited[other] = true;
+                        let [x, y, z] = corners(indices, other);
+                        // a consistent neighbour walks the shared edge the other way
+                        flip[other] = [(x, y), (y, z), (z, x)].contains(&(from, to));
+                        part.push(other);
+                    }
+                }
+            }
+
+            // how much the part faces away from the center, it is 0 for a flat part
+            let (mut outward, mut scale) = (0.0, 0.0);
+            for &t in &part {
+                let [a, b, c] = corners(indices, t);
+                let normal = winding([a, b, c]) * if flip[t] { -1.0 } else { 1.0 };
+                let offset = (positions[a] + positions[b] + positions[c]) / 3.0 - center;
+                outward += normal.dot(&offset);
+                scale += normal.norm() * offset.norm();
+            }
+            if outward < -1e-4 * scale {
+                for &t in &part {
+                    flip[t] = !flip[t];
+                }
+            }
+        }
+    }
+
+    let mut flipped = 0;
+    for t in triangles {
+        if flip[t] {
+            indices.swap(t * 3 + 1, t * 3 + 2);
+            flipped += 1;
+        }
+    }
+    flipped
+}
+
+/// Checks that a range is in the index_count indicies of a mesh
+fn check_range(range: &DrawRange, index_count: usize) -> Result<(), String> {
+    if range.index_offset + range.index_count > index_count {
+        return Err(format!(
+            "The range of {} indicies from {} is out of the {} indicies of the mesh",
+            range.index_count, range.index_offset, index_count
+        ));
+    }
+    Ok(())
+}
+
+/// Checks that the indices make whole primitives of topology and that every index is in the
+/// vertices, restart_index is skipped since it isn't a vertex
+fn check_indices(
+    indices: &[u32],
+    topology: Topology,
+    vertex_count: usize,
+    restart_index: Option<u32>,
+) -> Result<(), String> {
+    let primitive_size = match topology {
+        Topology::Triangles => 3,
+        Topology::Lines => 2,
+        _ => 1,
+    };
+    if !indices.len().is_multiple_of(primitive_size) {
+        return Err(format!(
+            "The {} indicies can't be split into {:?}",
+            indices.len(),
+            topology
+        ));
+    }
+
+    let max = indices
+        .iter()
+        .filter(|index| Some(**index) != restart_index)
+        .max();
+    if let Some(max) = max {
+        if *max as usize >= vertex_count {
+            return Err(format!(
+                "The index {} is out of the {} vertices of the mesh",
+                max, vertex_count
+            ));
+        }
+    }
+    Ok(())
+}
+
+impl<Vertex: VertexTrait + 'static + Sync + Send> Default for MeshBuilder<Vertex> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Vertex: VertexTrait + 'static + Sync + Send> Drop for Mesh<Vertex> {
+    /// Deletes the VAO, VBO and EBO, the GL context must still exist
+    fn drop(&mut self) {
+        forget_mesh(self.vao.0);
+        self.vao.delete();
+        self.vbo.delete();
+        self.ebo.delete();
+    }
+}
+
+/// The position of an entity, it is used by the systems of [impl_update_mesh]
 #[derive(Component)]
-struct Position(Vec3);
+pub struct Position(pub Vec3);
 
+/// The rotation of an entity (the axis is xyz and the angle is w), it is used by the systems of
+/// [impl_update_mesh]
 #[derive(Component)]
-struct Rotation(Vec4);
+pub struct Rotation(pub Vec4);
 
 #[macro_export]
 /// implement setup methods systems
@@ -275,16 +1843,264 @@ macro_rules! impl_update_mesh {
 
         impl<'a> System<'a> for $struct_name {
             type SystemData = (
-                ReadStorage<'a, Position>,
-                ReadStorage<'a, Rotation>,
-                ReadStorage<'a, Mesh<$vertex>>,
+                ReadStorage<'a, $crate::ECS::mesh::Position>,
+                ReadStorage<'a, $crate::ECS::mesh::Rotation>,
+                WriteStorage<'a, Mesh<$vertex>>,
             );
 
-            fn run(&mut self, (pos_vec, rot_vec, mesh_vec): Self::SystemData) {
-                for (pos, rot, mesh) in (&pos_vec, &rot_vec, &mesh_vec).join() {
-                    mesh.update(pos, rot)
+            fn run(&mut self, (pos_vec, rot_vec, mut mesh_vec): Self::SystemData) {
+                for (pos, rot, mesh) in (&pos_vec, &rot_vec, &mut mesh_vec).join() {
+                    mesh.update_mesh(pos.0, rot.0)
                 }
             }
         }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn indices_must_make_whole_primitives() {
+        assert!(check_indices(&[0, 1, 2, 2, 1, 3], Topology::Triangles, 4, None).is_ok());
+        assert!(check_indices(&[0, 1, 2, 3], Topology::Triangles, 4, None).is_err());
+        assert!(check_indices(&[0, 1, 2, 3], Topology::TriangleStrip, 4, None).is_ok());
+        assert!(check_indices(&[0, 1, 2], Topology::Lines, 4, None).is_err());
+    }
+
+    #[test]
+    fn indices_must_be_in_the_vertices() {
+        assert!(check_indices(&[0, 1, 4], Topology::Triangles, 4, None).is_err());
+        // the restart index isn't a vertex
+        let strips = [0, 1, 2, u32::MAX, 1, 2, 3];
+        assert!(check_indices(&strips, Topology::TriangleStrip, 4, Some(u32::MAX)).is_ok());
+    }
+
+    fn quad() -> Vec<LitVertex> {
+        [
+            vec2(0.0, 0.0),
+            vec2(1.0, 0.0),
+            vec2(1.0, 1.0),
+            vec2(0.0, 1.0),
+        ]
+        .iter()
+        .map(|uv| LitVertex::new(vec3(uv.x, uv.y, 0.0), *uv, vec3(0.0, 0.0, 1.0)))
+        .collect()
+    }
+
+    #[test]
+    fn the_bounds_enclose_every_vertex() {
+        let (aabb, center, radius) = vertex_bounds(&quad());
+        assert_eq!(aabb, Aabb::new(Vec3::zeros(), vec3(1.0, 1.0, 0.0)));
+        assert_eq!(center, vec3(0.5, 0.5, 0.0));
+        assert!((radius - 0.5_f32.sqrt()).abs() < 1e-6);
+
+        let (aabb, center, radius) = vertex_bounds::<LitVertex>(&[]);
+        assert_eq!((aabb.min, aabb.max), (Vec3::zeros(), Vec3::zeros()));
+        assert_eq!((center, radius), (Vec3::zeros(), 0.0));
+    }
+
+    #[test]
+    fn the_tangents_of_a_quad_follow_u() {
+        let tangents = vertex_tangents(&quad(), &[0, 1, 2, 0, 2, 3], None).unwrap();
+        assert_eq!(tangents, vec![(vec3(1.0, 0.0, 0.0), 1.0); 4]);
+
+        // v running down the quad flips the bitangent but not the tangent
+        let mut flipped = quad();
+        for vertex in &mut flipped {
+            vertex.tex_coord.y = 1.0 - vertex.tex_coord.y;
+        }
+        let tangents = vertex_tangents(&flipped, &[0, 1, 2, 0, 2, 3], None).unwrap();
+        assert_eq!(tangents, vec![(vec3(1.0, 0.0, 0.0), -1.0); 4]);
+    }
+
+    #[test]
+    fn degenerate_uvs_give_finite_tangents() {
+        let mut vertices = quad();
+        for vertex in &mut vertices {
+            vertex.tex_coord = Vec2::zeros();
+        }
+        let tangents = vertex_tangents(&vertices, &[0, 1, 2, 0, 2, 3], None).unwrap();
+        for (tangent, sign) in tangents {
+            assert!(tangent.iter().all(|x| x.is_finite()));
+            assert!((tangent.norm() - 1.0).abs() < 1e-6);
+            assert!(tangent.dot(&vec3(0.0, 0.0, 1.0)).abs() < 1e-6);
+            assert_eq!(sign.abs(), 1.0);
+        }
+    }
+
+    /// A unit cube around the origin with 8 shared corners and outward triangles
+    fn cube() -> (Vec<LitVertex>, Vec<u32>) {
+        let mut vertices: Vec<LitVertex> = Vec::new();
+        let mut indices = Vec::new();
+        let mut corner = |pos: Vec3| match vertices.iter().position(|vertex| vertex.pos == pos) {
+            Some(i) => i as u32,
+            None => {
+                vertices.push(LitVertex::new(pos, Vec2::zeros(), Vec3::zeros()));
+                vertices.len() as u32 - 1
+            }
+        };
+        for normal in [
+            vec3(1.0, 0.0, 0.0),
+            vec3(-1.0, 0.0, 0.0),
+            vec3(0.0, 1.0, 0.0),
+            vec3(0.0, -1.0, 0.0),
+            vec3(0.0, 0.0, 1.0),
+            vec3(0.0, 0.0, -1.0),
+        ] {
+            // u x v is the normal so the quad is counter clockwise from outside
+            let u = vec3(normal.y, normal.z, normal.x) / 2.0;
+            let v = normal.cross(&u);
+            let center = normal / 2.0;
+            let quad = [-u - v, u - v, u + v, v - u].map(|offset| corner(center + offset));
+            indices.extend([quad[0], quad[1], quad[2], quad[0], quad[2], quad[3]]);
+        }
+        (vertices, indices)
+    }
+
+    #[test]
+    fn a_cube_keeps_hard_edges() {
+        let (mut vertices, mut indices) = cube();
+        assert_eq!(vertices.len(), 8);
+        smooth_normals(&mut vertices, &mut indices, None, 30.0);
+
+        // every corner is split into one vertex per face around it
+        assert_eq!(vertices.len(), 24);
+        for triangle in indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| vertices[triangle[i] as usize]);
+            let face = (b.pos - a.pos).cross(&(c.pos - a.pos)).normalize();
+            for vertex in [a, b, c] {
+                assert!((vertex.normal - face).norm() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn a_nearly_flat_pair_is_smoothed() {
+        // two triangles folded by about 8 degrees along the edge from 1 to 2
+        let mut vertices: Vec<LitVertex> = [
+            vec3(0.0, 0.0, 0.0),
+            vec3(1.0, 0.0, 0.0),
+            vec3(0.0, 1.0, 0.0),
+            vec3(1.0, 1.0, 0.1),
+        ]
+        .map(|pos| LitVertex::new(pos, Vec2::zeros(), Vec3::zeros()))
+        .to_vec();
+        let mut indices = vec![0, 1, 2, 1, 3, 2];
+        smooth_normals(&mut vertices, &mut indices, None, 30.0);
+
+        assert_eq!(vertices.len(), 4);
+        assert_eq!(indices, [0, 1, 2, 1, 3, 2]);
+        assert_eq!(vertices[0].normal, vec3(0.0, 0.0, 1.0));
+        // the shared edge gets the average of both faces
+        assert_eq!(vertices[1].normal, vertices[2].normal);
+        assert!(vertices[1].normal.z < 1.0);
+        assert!(vertices[1].normal.z > vertices[3].normal.z);
+    }
+
+    #[test]
+    fn merging_offsets_the_indices_of_later_meshes() {
+        let quad = quad();
+        let indices = [0, 1, 2, 0, 2, 3];
+        let still = vec4(0.0, 1.0, 0.0, 0.0);
+        let (vert, index) = merge_geometry(&[
+            (&quad[..], &indices[..], Vec3::zeros(), still),
+            (&quad[..], &indices[..], vec3(5.0, 0.0, 0.0), still),
+        ]);
+
+        assert_eq!(vert.len(), 8);
+        assert_eq!(index, vec![0, 1, 2, 0, 2, 3, 4, 5, 6, 4, 6, 7]);
+        assert_eq!(vert[2].pos, vec3(1.0, 1.0, 0.0));
+        assert_eq!(vert[6].pos, vec3(6.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn an_oppositely_wound_triangle_is_flipped_to_match_its_neighbour() {
+        let positions = quad().iter().map(|vertex| vertex.pos).collect::<Vec<_>>();
+        let mut indices = vec![0, 1, 2, 0, 3, 2];
+        assert_eq!(
+            fix_triangle_winding(&positions, None, &mut indices, None),
+            1
+        );
+        assert_eq!(indices, vec![0, 1, 2, 0, 2, 3]);
+
+        // already consistent so nothing changes
+        assert_eq!(
+            fix_triangle_winding(&positions, None, &mut indices, None),
+            0
+        );
+    }
+
+    #[test]
+    fn a_closed_mesh_wound_inward_is_flipped_to_face_out() {
+        let positions = vec![
+            vec3(0.0, 0.0, 0.0),
+            vec3(1.0, 0.0, 0.0),
+            vec3(0.0, 1.0, 0.0),
+            vec3(0.0, 0.0, 1.0),
+        ];
+        // every face of the tetrahedron is wound inward
+        let mut indices = vec![0, 1, 2, 0, 3, 1, 0, 2, 3, 1, 3, 2];
+        assert_eq!(
+            fix_triangle_winding(&positions, None, &mut indices, None),
+            4
+        );
+        assert_eq!(&indices[0..3], &[0, 2, 1]);
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct TexturedVertex {
+        pos: Vec3,
+        tex_coord: Vec2,
+        color: Vec4,
+    }
+
+    impl_vertex!(TexturedVertex {
+        pos: Vec3,
+        tex_coord: Vec2,
+        color: Vec4
+    });
+
+    #[test]
+    fn impl_vertex_lays_the_fields_out_in_order() {
+        assert_eq!(TexturedVertex::SIZE, 9);
+        assert_eq!(TexturedVertex::layout(), vec![3, 2, 4]);
+        assert_eq!(<TexturedVertex as VertexTrait>::layout(), vec![3, 2, 4]);
+
+        let vertex = TexturedVertex {
+            pos: vec3(1.0, 2.0, 3.0),
+            tex_coord: vec2(0.25, 0.75),
+            color: vec4(0.1, 0.2, 0.3, 1.0),
+        };
+        assert_eq!(
+            vertex.as_list(),
+            vec![1.0, 2.0, 3.0, 0.25, 0.75, 0.1, 0.2, 0.3, 1.0]
+        );
+
+        // only the position is moved
+        let moved = vertex.get_vertex(vec3(1.0, 0.0, 0.0), vec4(0.0, 1.0, 0.0, 0.0));
+        assert_eq!(moved.pos, vec3(2.0, 2.0, 3.0));
+        assert_eq!(
+            (moved.tex_coord, moved.color),
+            (vertex.tex_coord, vertex.color)
+        );
+    }
+
+    #[test]
+    fn lit_vertices_store_their_tangent() {
+        let mut vertex = LitVertex::new(vec3(1.0, 2.0, 3.0), vec2(0.5, 0.5), vec3(0.0, 0.0, 1.0));
+        assert!(vertex.set_tangent(vec3(1.0, 0.0, 0.0), -1.0));
+        assert_eq!(vertex.tangent, vec4(1.0, 0.0, 0.0, -1.0));
+
+        assert_eq!(vertex.as_list().len(), LitVertex::SIZE as usize);
+        assert_eq!(LitVertex::layout().iter().sum::<u32>(), LitVertex::SIZE);
+    }
+
+    #[test]
+    fn lit_vertices_store_their_normal() {
+        let mut vertex = LitVertex::new(Vec3::zeros(), Vec2::zeros(), vec3(0.0, 0.0, 1.0));
+        assert!(vertex.set_normal(vec3(0.0, 1.0, 0.0)));
+        assert_eq!(vertex.normal(), Some(vec3(0.0, 1.0, 0.0)));
+    }
+}
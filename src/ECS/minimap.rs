@@ -0,0 +1,119 @@
+use crate::math::up_axis;
+use nalgebra_glm::*;
+
+/// A corner of the window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Corner {
+    /// The top left corner
+    TopLeft,
+    /// The top right corner
+    TopRight,
+    /// The bottom left corner
+    BottomLeft,
+    /// The bottom right corner
+    BottomRight,
+}
+
+/// A top down view of the world drawn into a rectangle of the window, it is drawn by
+/// [World::render_minimap](super::world::World::render_minimap)
+///
+/// The world is seen from above with an orthographic camera centered on the player, the forward
+/// of [up_axis] is up on the minimap. The player is drawn as a square marker in the middle
+///
+/// # Example
+/// ```
+/// let mut minimap = Minimap::in_corner(Corner::TopRight, 200, 10, world.env.win_size);
+/// minimap.zoom = 20.0;
+///
+/// world.run(|world| {
+///     let player = *world.objects.get_camera().get_pos();
+///     world.render_minimap(&minimap, player);
+/// });
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Minimap {
+    /// The rectangle of the window it is drawn into as (x, y, width, height) from the bottom left
+    pub rect: (i32, i32, i32, i32),
+    /// How many world units there are from the center to the top and bottom edges, the sides
+    /// are scaled by the aspect ratio of the rectangle. Smaller values zoom in
+    pub zoom: f32,
+    /// How far above the player the camera is, everything higher than this isn't drawn
+    pub height: f32,
+    /// The color the rectangle is cleared to before the world is drawn
+    pub background: Vec4,
+    /// The color of the player marker, the marker isn't drawn if the alpha is 0.0
+    pub marker_color: Vec4,
+    /// The width and height of the player marker in pixels
+    pub marker_size: i32,
+}
+
+impl Minimap {
+    /// Creates a minimap drawn into rect(x, y, width, height)
+    ///
+    /// The zoom is 10.0, the height is 100.0, the background is dark gray and the marker is a
+    /// 6 pixel red square by default
+    pub fn new(rect: (i32, i32, i32, i32)) -> Self {
+        Minimap {
+            rect,
+            zoom: 10.0,
+            height: 100.0,
+            background: vec4(0.1, 0.1, 0.1, 1.0),
+            marker_color: vec4(1.0, 0.0, 0.0, 1.0),
+            marker_size: 6,
+        }
+    }
+
+    /// Creates a square minimap of size pixels in a corner of a window of win_size, margin
+    /// pixels away from the edges
+    pub fn in_corner(corner: Corner, size: i32, margin: i32, win_size: Vec2) -> Self {
+        let (width, height) = (win_size.x as i32, win_size.y as i32);
+        let x = match corner {
+            Corner::TopLeft | Corner::BottomLeft => margin,
+            Corner::TopRight | Corner::BottomRight => width - size - margin,
+        };
+        let y = match corner {
+            Corner::BottomLeft | Corner::BottomRight => margin,
+            Corner::TopLeft | Corner::TopRight => height - size - margin,
+        };
+        Self::new((x, y, size, size))
+    }
+
+    /// The view matrix of the camera looking straight down at center along the up of [up_axis]
+    pub fn view(&self, center: Vec3) -> Mat4 {
+        let axis = up_axis();
+        look_at(
+            &(center + axis.up() * self.height),
+            &center,
+            &axis.forward(),
+        )
+    }
+
+    /// The orthographic projection matrix of the camera
+    pub fn projection(&self) -> Mat4 {
+        let aspect = self.rect.2 as f32 / self.rect.3.max(1) as f32;
+        ortho(
+            -self.zoom * aspect,
+            self.zoom * aspect,
+            -self.zoom,
+            self.zoom,
+            0.0,
+            self.height * 2.0,
+        )
+    }
+
+    /// The view projection matrix of the camera looking down at center
+    pub fn view_projection(&self, center: Vec3) -> Mat4 {
+        self.projection() * self.view(center)
+    }
+
+    /// The rectangle of the player marker as (x, y, width, height)
+    pub fn marker_rect(&self) -> (i32, i32, i32, i32) {
+        let (x, y, width, height) = self.rect;
+        (
+            x + (width - self.marker_size) / 2,
+            y + (height - self.marker_size) / 2,
+            self.marker_size,
+            self.marker_size,
+        )
+    }
+}
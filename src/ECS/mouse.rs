@@ -3,6 +3,8 @@ use std::time::{Duration, Instant};
 use device_query::{DeviceQuery, DeviceState, MouseState};
 use nalgebra_glm::*;
 
+use super::world::Enviroment;
+
 /// State of the mouse
 #[derive(Clone, Copy)]
 pub enum StateOfMouse {
@@ -69,9 +71,9 @@ impl Mouse {
     }
 
     /// Returns the what buttons are pressed
-    /// 
+    ///
     /// # Example
-    /// 
+    ///
     pub fn get_pressed(&mut self) -> Vec<MousePressed> {
         let mut out = Vec::new();
         if self.mouse.button_pressed[1] {
@@ -90,6 +92,27 @@ impl Mouse {
         out
     }
 
+    /// The position of the mouse relative to the top left corner of the window in screen
+    /// coordinates
+    pub fn window_pos(&self, env: &Enviroment) -> Vec2 {
+        let (x, y) = self.mouse.coords;
+        vec2(x as f32, y as f32) - env.window_position()
+    }
+
+    /// The position of the mouse in normalized device coordinates, (-1, -1) is the bottom left
+    /// corner of the window and (1, 1) is the top right corner. It is outside of -1..1 when the
+    /// mouse is outside the window
+    ///
+    /// # Example
+    /// ```
+    /// let ndc = world.env.mouse.ndc(&world.env);
+    /// // a ray from the camera through the cursor
+    /// let far = inverse(&world.env.view_projection()) * vec4(ndc.x, ndc.y, 1.0, 1.0);
+    /// ```
+    pub fn ndc(&self, env: &Enviroment) -> Vec2 {
+        window_to_ndc(self.window_pos(env), env.window_size())
+    }
+
     /// Returns the buttons pressed with a cooldown
     pub fn get_pressed_cooldown(&mut self, cooldown: Duration) -> Option<Vec<MousePressed>> {
         if self.last_pressed.elapsed() > cooldown {
@@ -100,6 +123,15 @@ impl Mouse {
     }
 }
 
+/// Converts a position relative to the top left corner of a window of size to normalized
+/// device coordinates, see [Mouse::ndc]
+fn window_to_ndc(pos: Vec2, size: Vec2) -> Vec2 {
+    vec2(
+        pos.x / size.x.max(1.0) * 2.0 - 1.0,
+        1.0 - pos.y / size.y.max(1.0) * 2.0,
+    )
+}
+
 impl From<DeviceState> for Mouse {
     fn from(device: DeviceState) -> Self {
         Mouse::new(device.get_mouse(), StateOfMouse::Free)
@@ -111,3 +143,18 @@ impl From<MouseState> for Mouse {
         Mouse::new(device, StateOfMouse::Free)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_positions_are_converted_to_ndc_with_y_up() {
+        let size = vec2(800.0, 600.0);
+        assert_eq!(window_to_ndc(vec2(400.0, 300.0), size), vec2(0.0, 0.0));
+        assert_eq!(window_to_ndc(vec2(0.0, 0.0), size), vec2(-1.0, 1.0));
+        assert_eq!(window_to_ndc(vec2(800.0, 600.0), size), vec2(1.0, -1.0));
+        // outside the window is outside of -1..1
+        assert_eq!(window_to_ndc(vec2(-400.0, 300.0), size), vec2(-2.0, 0.0));
+    }
+}
@@ -0,0 +1,18 @@
+use super::{mesh::PosRot, world::*};
+
+/// An object in the world that is updated every frame
+///
+/// # Example
+/// ```
+/// impl Object<GameObject> for Pyramid {
+///     fn update(world: &mut World<GameObject>, i: u32) {
+///         world.objects.pyramids[i as usize].set_rot().w += 0.01;
+///     }
+/// }
+/// ```
+pub trait Object<G: GameObjectTrait>: PosRot {
+    /// Updates the object, i is the index of the object if there are several of it
+    fn update(world: &mut World<G>, i: u32)
+    where
+        Self: Sized;
+}
@@ -0,0 +1,251 @@
+/// What a [Scene] wants the [SceneStack] to do after it was updated
+pub enum SceneTransition<W> {
+    /// Keep the stack as it is
+    None,
+    /// Put a scene on top of this one, e.g. a pause menu
+    Push(Box<dyn Scene<W>>),
+    /// Remove this scene so the one below it resumes
+    Pop,
+    /// Remove this scene and put another one in its place, e.g. going from the menu to a level
+    Replace(Box<dyn Scene<W>>),
+}
+
+/// A state of the game like the main menu, a level or the pause menu, see [SceneStack]
+///
+/// W is the world the scene is given, it is a [World](super::world::World) in a game. Only
+/// update and render have to be implemented
+pub trait Scene<W> {
+    /// Called when the scene is pushed onto the stack
+    fn enter(&mut self, _world: &mut W) {}
+
+    /// Called when the scene is removed from the stack
+    fn exit(&mut self, _world: &mut W) {}
+
+    /// Called when another scene is pushed on top of this one
+    fn pause(&mut self, _world: &mut W) {}
+
+    /// Called when the scene on top of this one is removed so this one is on top again
+    fn resume(&mut self, _world: &mut W) {}
+
+    /// Called every frame while the scene is on top of the stack, delta_time is in seconds
+    fn update(&mut self, world: &mut W, delta_time: f32) -> SceneTransition<W>;
+
+    /// Called every frame while the scene is visible, after the world is rendered
+    fn render(&mut self, world: &mut W);
+
+    /// When true the scenes below this one are still rendered(but not updated) before it, e.g.
+    /// for a pause menu drawn over the paused level. It is false by default
+    fn is_overlay(&self) -> bool {
+        false
+    }
+}
+
+/// A stack of [Scene]s where only the top scene is updated, see
+/// [World::run_scenes](super::world::World::run_scenes)
+///
+/// # Example
+/// ```
+/// struct Level;
+///
+/// impl Scene<World<GameObject>> for Level {
+///     fn update(
+///         &mut self,
+///         world: &mut World<GameObject>,
+///         _: f32,
+///     ) -> SceneTransition<World<GameObject>> {
+///         if world.env.device.get_keys().contains(&Keycode::P) {
+///             SceneTransition::Push(Box::new(PauseMenu))
+///         } else {
+///             SceneTransition::None
+///         }
+///     }
+///
+///     fn render(&mut self, _: &mut World<GameObject>) {}
+/// }
+///
+/// let mut scenes = SceneStack::new();
+/// scenes.push(Box::new(Level), &mut world);
+/// world.run_scenes(&mut scenes);
+/// ```
+pub struct SceneStack<W> {
+    scenes: Vec<Box<dyn Scene<W>>>,
+}
+
+impl<W> SceneStack<W> {
+    /// Creates an empty stack
+    pub fn new() -> Self {
+        SceneStack { scenes: Vec::new() }
+    }
+
+    /// Pauses the top scene then enters scene and puts it on top
+    pub fn push(&mut self, mut scene: Box<dyn Scene<W>>, world: &mut W) {
+        if let Some(top) = self.scenes.last_mut() {
+            top.pause(world);
+        }
+        scene.enter(world);
+        self.scenes.push(scene);
+    }
+
+    /// Exits and removes the top scene then resumes the one below it
+    pub fn pop(&mut self, world: &mut W) -> Option<Box<dyn Scene<W>>> {
+        let mut scene = self.scenes.pop()?;
+        scene.exit(world);
+        if let Some(top) = self.scenes.last_mut() {
+            top.resume(world);
+        }
+        Some(scene)
+    }
+
+    /// Exits and removes the top scene then enters scene in its place, the scene below isn't
+    /// resumed or paused
+    pub fn replace(
+        &mut self,
+        mut scene: Box<dyn Scene<W>>,
+        world: &mut W,
+    ) -> Option<Box<dyn Scene<W>>> {
+        let mut old = self.scenes.pop();
+        if let Some(old) = &mut old {
+            old.exit(world);
+        }
+        scene.enter(world);
+        self.scenes.push(scene);
+        old
+    }
+
+    /// Exits and removes every scene from the top down
+    pub fn clear(&mut self, world: &mut W) {
+        while let Some(mut scene) = self.scenes.pop() {
+            scene.exit(world);
+        }
+    }
+
+    /// The scene on top of the stack
+    pub fn top(&self) -> Option<&dyn Scene<W>> {
+        self.scenes.last().map(|scene| scene.as_ref())
+    }
+
+    /// How many scenes are on the stack
+    pub fn len(&self) -> usize {
+        self.scenes.len()
+    }
+
+    /// Checks if there are no scenes
+    pub fn is_empty(&self) -> bool {
+        self.scenes.is_empty()
+    }
+
+    /// Updates the top scene then applies the [SceneTransition] it returned
+    pub fn update(&mut self, world: &mut W, delta_time: f32) {
+        let transition = match self.scenes.last_mut() {
+            Some(top) => top.update(world, delta_time),
+            None => return,
+        };
+        match transition {
+            SceneTransition::None => (),
+            SceneTransition::Push(scene) => self.push(scene, world),
+            SceneTransition::Pop => {
+                self.pop(world);
+            }
+            SceneTransition::Replace(scene) => {
+                self.replace(scene, world);
+            }
+        }
+    }
+
+    /// Renders the visible scenes from the bottom up, a scene is visible if it is on top or
+    /// every scene above it is an overlay
+    pub fn render(&mut self, world: &mut W) {
+        let first_visible = self
+            .scenes
+            .iter()
+            .rposition(|scene| !scene.is_overlay())
+            .unwrap_or(0);
+        for scene in &mut self.scenes[first_visible..] {
+            scene.render(world);
+        }
+    }
+}
+
+impl<W> Default for SceneStack<W> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A scene that writes what happened to it into the world
+    struct Logged {
+        name: &'static str,
+        overlay: bool,
+    }
+
+    impl Scene<Vec<String>> for Logged {
+        fn enter(&mut self, log: &mut Vec<String>) {
+            log.push(format!("{} enter", self.name));
+        }
+
+        fn exit(&mut self, log: &mut Vec<String>) {
+            log.push(format!("{} exit", self.name));
+        }
+
+        fn pause(&mut self, log: &mut Vec<String>) {
+            log.push(format!("{} pause", self.name));
+        }
+
+        fn resume(&mut self, log: &mut Vec<String>) {
+            log.push(format!("{} resume", self.name));
+        }
+
+        fn update(&mut self, log: &mut Vec<String>, _: f32) -> SceneTransition<Vec<String>> {
+            log.push(format!("{} update", self.name));
+            SceneTransition::None
+        }
+
+        fn render(&mut self, log: &mut Vec<String>) {
+            log.push(format!("{} render", self.name));
+        }
+
+        fn is_overlay(&self) -> bool {
+            self.overlay
+        }
+    }
+
+    #[test]
+    fn a_pause_menu_freezes_the_game_under_it_until_it_is_popped() {
+        let mut log = Vec::new();
+        let mut scenes = SceneStack::new();
+        let gameplay = Logged {
+            name: "gameplay",
+            overlay: false,
+        };
+        let pause = Logged {
+            name: "pause",
+            overlay: true,
+        };
+        scenes.push(Box::new(gameplay), &mut log);
+        scenes.push(Box::new(pause), &mut log);
+        assert_eq!(log, ["gameplay enter", "gameplay pause", "pause enter"]);
+
+        log.clear();
+        scenes.update(&mut log, 0.016);
+        scenes.render(&mut log);
+        assert_eq!(log, ["pause update", "gameplay render", "pause render"]);
+
+        log.clear();
+        scenes.pop(&mut log);
+        scenes.update(&mut log, 0.016);
+        scenes.render(&mut log);
+        assert_eq!(
+            log,
+            [
+                "pause exit",
+                "gameplay resume",
+                "gameplay update",
+                "gameplay render"
+            ]
+        );
+    }
+}
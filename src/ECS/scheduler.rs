@@ -0,0 +1,250 @@
+/// The id of a timer in a [Scheduler], it can be used to cancel the timer. Ids are never reused
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TimerId(pub u32);
+
+/// A function called by a [Scheduler] with the context it is ticked with
+pub type TimerCallback<'a, T> = Box<dyn FnMut(&mut T) + 'a>;
+
+struct Timer<'a, T> {
+    id: TimerId,
+    /// Seconds until the timer fires
+    remaining: f32,
+    /// Some for timers that repeat
+    interval: Option<f32>,
+    callback: TimerCallback<'a, T>,
+}
+
+/// Calls functions after a delay or repeatedly, advanced by the delta time of every frame
+///
+/// The delta time is multiplied by [Scheduler::time_scale] so a time scale of 0 pauses every
+/// timer without losing how long they have left. T is what the functions are called with, for
+/// [World::scheduler](super::world::World::scheduler) it is the world
+///
+/// # Example
+/// ```
+/// world.scheduler.after(2.0, |world| world.env.set_title("2 seconds later"));
+/// let blink = world.scheduler.every(0.5, |world| world.objects.light.toggle());
+///
+/// // later
+/// world.scheduler.cancel(blink);
+/// ```
+pub struct Scheduler<'a, T> {
+    /// How fast the timers run, 1.0 is real time and 0.0 pauses them. It is 1.0 by default
+    pub time_scale: f32,
+    next_id: u32,
+    timers: Vec<Timer<'a, T>>,
+    /// Timers that were cancelled while they were detached, see [Scheduler::detach]
+    cancelled: Vec<TimerId>,
+    detached: bool,
+}
+
+impl<'a, T> Scheduler<'a, T> {
+    /// Creates a scheduler without any timers
+    pub fn new() -> Self {
+        Scheduler {
+            time_scale: 1.0,
+            next_id: 0,
+            timers: Vec::new(),
+            cancelled: Vec::new(),
+            detached: false,
+        }
+    }
+
+    fn add(
+        &mut self,
+        delay: f32,
+        interval: Option<f32>,
+        callback: TimerCallback<'a, T>,
+    ) -> TimerId {
+        let id = TimerId(self.next_id);
+        self.next_id += 1;
+        self.timers.push(Timer {
+            id,
+            remaining: delay,
+            interval,
+            callback,
+        });
+        id
+    }
+
+    /// Calls f once after delay seconds
+    pub fn after<F: FnMut(&mut T) + 'a>(&mut self, delay: f32, f: F) -> TimerId {
+        self.add(delay, None, Box::new(f))
+    }
+
+    /// Calls f every interval seconds until the timer is cancelled, the first call is after
+    /// interval seconds
+    ///
+    /// If a tick is longer than the interval f is called several times to catch up, an interval
+    /// of 0 or less calls f once every tick
+    pub fn every<F: FnMut(&mut T) + 'a>(&mut self, interval: f32, f: F) -> TimerId {
+        self.add(interval, Some(interval), Box::new(f))
+    }
+
+    /// Stops a timer, returns false if it already fired or was cancelled
+    pub fn cancel(&mut self, id: TimerId) -> bool {
+        let len = self.timers.len();
+        self.timers.retain(|timer| timer.id != id);
+        if self.timers.len() == len {
+            if self.detached {
+                // the timer may be running, it is removed when it is reattached
+                self.cancelled.push(id);
+            }
+            false
+        } else {
+            true
+        }
+    }
+
+    /// Checks if a timer is still waiting to fire
+    pub fn is_scheduled(&self, id: TimerId) -> bool {
+        self.timers.iter().any(|timer| timer.id == id)
+    }
+
+    /// Seconds until a timer fires next, before the time scale is applied
+    pub fn remaining(&self, id: TimerId) -> Option<f32> {
+        self.timers
+            .iter()
+            .find(|timer| timer.id == id)
+            .map(|timer| timer.remaining)
+    }
+
+    /// How many timers are waiting to fire
+    pub fn len(&self) -> usize {
+        self.timers.len()
+    }
+
+    /// Checks if there are no timers
+    pub fn is_empty(&self) -> bool {
+        self.timers.is_empty()
+    }
+
+    /// Removes every timer
+    pub fn clear(&mut self) {
+        self.timers.clear();
+    }
+
+    /// Advances every timer by delta_time seconds times [Scheduler::time_scale] and calls the
+    /// ones that are due with context, timers that don't repeat are removed after they fire
+    ///
+    /// # Example
+    /// ```
+    /// let mut scheduler = Scheduler::new();
+    /// let mut fired = 0;
+    /// scheduler.after(1.0, |fired: &mut i32| *fired += 1);
+    ///
+    /// scheduler.tick(0.5, &mut fired);
+    /// assert_eq!(fired, 0);
+    /// scheduler.tick(0.6, &mut fired);
+    /// assert_eq!(fired, 1);
+    /// ```
+    pub fn tick(&mut self, delta_time: f32, context: &mut T) {
+        let delta_time = delta_time * self.time_scale;
+        for timer in &mut self.timers {
+            timer.remaining -= delta_time;
+            match timer.interval {
+                Some(interval) if interval > 0.0 => {
+                    while timer.remaining <= 0.0 {
+                        (timer.callback)(context);
+                        timer.remaining += interval;
+                    }
+                }
+                Some(_) => {
+                    (timer.callback)(context);
+                    timer.remaining = 0.0;
+                }
+                None => {
+                    if timer.remaining <= 0.0 {
+                        (timer.callback)(context);
+                    }
+                }
+            }
+        }
+        self.timers
+            .retain(|timer| timer.interval.is_some() || timer.remaining > 0.0);
+    }
+
+    /// Moves the timers out into a new scheduler so they can be ticked with a context that owns
+    /// this one, timers added or cancelled in the meantime are kept here with ids that don't
+    /// clash. [Scheduler::reattach] puts them back together
+    pub(crate) fn detach(&mut self) -> Self {
+        self.detached = true;
+        Scheduler {
+            time_scale: self.time_scale,
+            next_id: self.next_id,
+            timers: std::mem::take(&mut self.timers),
+            cancelled: Vec::new(),
+            detached: false,
+        }
+    }
+
+    /// Puts the timers of [Scheduler::detach] back before the ones added since, minus the ones
+    /// that were cancelled
+    pub(crate) fn reattach(&mut self, mut detached: Self) {
+        self.detached = false;
+        let cancelled = std::mem::take(&mut self.cancelled);
+        detached
+            .timers
+            .retain(|timer| !cancelled.contains(&timer.id));
+        detached.timers.append(&mut self.timers);
+        self.timers = detached.timers;
+    }
+}
+
+impl<'a, T> Default for Scheduler<'a, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_timer_fires_once_after_its_delay() {
+        let mut scheduler = Scheduler::new();
+        let mut fired = 0;
+        let id = scheduler.after(1.0, |fired: &mut i32| *fired += 1);
+
+        scheduler.tick(0.5, &mut fired);
+        assert_eq!(fired, 0);
+        assert!(scheduler.is_scheduled(id));
+        scheduler.tick(0.6, &mut fired);
+        assert_eq!(fired, 1);
+        assert!(!scheduler.is_scheduled(id));
+        scheduler.tick(1.0, &mut fired);
+        assert_eq!(fired, 1);
+    }
+
+    #[test]
+    fn a_repeating_timer_catches_up() {
+        let mut scheduler = Scheduler::new();
+        let mut fired = 0;
+        let id = scheduler.every(0.3, |fired: &mut i32| *fired += 1);
+
+        scheduler.tick(0.2, &mut fired);
+        assert_eq!(fired, 0);
+        scheduler.tick(0.2, &mut fired);
+        assert_eq!(fired, 1);
+        // 0.1 was left over so a whole second fires 3 more times
+        scheduler.tick(1.0, &mut fired);
+        assert_eq!(fired, 4);
+
+        assert!(scheduler.cancel(id));
+        scheduler.tick(1.0, &mut fired);
+        assert_eq!(fired, 4);
+    }
+
+    #[test]
+    fn a_time_scale_of_zero_pauses_the_timers() {
+        let mut scheduler = Scheduler::new();
+        let mut fired = 0;
+        let id = scheduler.after(1.0, |fired: &mut i32| *fired += 1);
+
+        scheduler.time_scale = 0.0;
+        scheduler.tick(5.0, &mut fired);
+        assert_eq!(fired, 0);
+        assert_eq!(scheduler.remaining(id), Some(1.0));
+    }
+}
@@ -0,0 +1,231 @@
+use nalgebra_glm::*;
+use std::cell::Cell;
+
+use super::mesh::PosRot;
+use crate::graphics::{shader::ShaderProgram, uniform::Uniform};
+
+/// The position, rotation and scale of an object with its model matrix cached
+///
+/// The matrix is only recomputed after [PosRot::set_pos], [PosRot::set_rot] or
+/// [Transform::set_scale] was called, so objects that don't move reuse last frame's matrix
+///
+/// # Example
+/// ```
+/// let mut transform = Transform::new(vec3(0.0, 0.0, 0.0), vec4(0.0, 1.0, 0.0, 0.0));
+///
+/// let model = transform.matrix(); // computed
+/// let model = transform.matrix(); // cached
+///
+/// transform.set_pos().x += 1.0;
+/// let model = transform.matrix(); // computed again
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transform {
+    pos: Vec3,
+    /// The rotation axis is xyz and the angle(in radians) is w
+    rot: Vec4,
+    scale: Vec3,
+    matrix: Cell<Option<Mat4>>,
+    /// The position, rotation and scale of the last frame or logic step, see
+    /// [Transform::store_previous]
+    previous: Option<(Vec3, Vec4, Vec3)>,
+}
+
+impl Transform {
+    /// Creates a new transform with a scale of 1
+    pub fn new(pos: Vec3, rot: Vec4) -> Self {
+        Transform {
+            pos,
+            rot,
+            scale: vec3(1.0, 1.0, 1.0),
+            matrix: Cell::new(None),
+            previous: None,
+        }
+    }
+
+    /// Get the scale of the object
+    pub fn get_scale(&self) -> &Vec3 {
+        &self.scale
+    }
+
+    /// Set the scale of the object, the cached matrix is invalidated
+    pub fn set_scale(&mut self) -> &mut Vec3 {
+        self.matrix.set(None);
+        &mut self.scale
+    }
+
+    /// Checks if the matrix has to be recomputed
+    pub fn is_dirty(&self) -> bool {
+        self.matrix.get().is_none()
+    }
+
+    /// Gets the model matrix (translation * rotation * scale), it is only computed if the
+    /// transform changed since the last call
+    ///
+    /// It only needs a shared reference so it can be called while drawing, see
+    /// [World::draw_mesh_transformed](super::world::World::draw_mesh_transformed)
+    pub fn matrix(&self) -> Mat4 {
+        match self.matrix.get() {
+            Some(matrix) => matrix,
+            None => {
+                let matrix = model_matrix(&self.pos, &rotation_quat(&self.rot), &self.scale);
+                self.matrix.set(Some(matrix));
+                matrix
+            }
+        }
+    }
+}
+
+impl Transform {
+    /// Remembers the current transform as the previous one, call it once per frame (or once per
+    /// logic step with a fixed timestep) before the transform is moved
+    pub fn store_previous(&mut self) {
+        self.previous = Some((self.pos, self.rot, self.scale));
+    }
+
+    /// Gets the model matrix of the previous frame, it is the current matrix if
+    /// [Transform::store_previous] was never called
+    pub fn previous_matrix(&self) -> Mat4 {
+        match self.previous {
+            Some((pos, rot, scale)) => model_matrix(&pos, &rotation_quat(&rot), &scale),
+            None => self.matrix(),
+        }
+    }
+
+    /// Gets the model matrix between the previous and the current transform, alpha 0 is the
+    /// previous and 1 the current. At 1 the cached matrix is used
+    ///
+    /// The position and scale are interpolated linearly and the rotation spherically. It is used
+    /// to render smoothly with a fixed timestep, see
+    /// [Enviroment::alpha](super::world::Enviroment::alpha)
+    pub fn interpolated_matrix(&self, alpha: f32) -> Mat4 {
+        let (prev_pos, prev_rot, prev_scale) = match self.previous {
+            Some(previous) if alpha < 1.0 => previous,
+            _ => return self.matrix(),
+        };
+
+        model_matrix(
+            &lerp(&prev_pos, &self.pos, alpha),
+            &quat_slerp(&rotation_quat(&prev_rot), &rotation_quat(&self.rot), alpha),
+            &lerp(&prev_scale, &self.scale, alpha),
+        )
+    }
+
+    /// Sets the mat4 u_model uniform of program to [Transform::interpolated_matrix], uniforms the
+    /// program dosen't have are skipped
+    ///
+    /// The program must be in use
+    pub fn upload_interpolated(&self, program: &ShaderProgram, alpha: f32) {
+        if let Some(uniform) = Uniform::find(program, "u_model") {
+            uniform.set_uniform_matrix(false, self.interpolated_matrix(alpha).into());
+        }
+    }
+
+    /// Sets the mat4 u_model uniform of program to the model matrix and, if motion_vectors is
+    /// true, u_prev_model to the previous matrix. Uniforms the program dosen't have are skipped
+    ///
+    /// The program must be in use
+    pub fn upload(&self, program: &ShaderProgram, motion_vectors: bool) {
+        if let Some(uniform) = Uniform::find(program, "u_model") {
+            uniform.set_uniform_matrix(false, self.matrix().into());
+        }
+        if motion_vectors {
+            if let Some(uniform) = Uniform::find(program, "u_prev_model") {
+                uniform.set_uniform_matrix(false, self.previous_matrix().into());
+            }
+        }
+    }
+}
+
+/// Turns a rotation where the axis is xyz and the angle(in radians) is w into a quaternion
+fn rotation_quat(rot: &Vec4) -> Qua<f32> {
+    if rot.w != 0.0 && rot.xyz() != Vec3::zeros() {
+        quat_angle_axis(rot.w, &rot.xyz())
+    } else {
+        quat_identity()
+    }
+}
+
+/// translation * rotation * scale
+fn model_matrix(pos: &Vec3, rot: &Qua<f32>, scale_by: &Vec3) -> Mat4 {
+    scale(&(translation(pos) * quat_to_mat4(rot)), scale_by)
+}
+
+impl PosRot for Transform {
+    fn get_pos(&self) -> &Vec3 {
+        &self.pos
+    }
+
+    fn get_rot(&self) -> &Vec4 {
+        &self.rot
+    }
+
+    fn set_pos(&mut self) -> &mut Vec3 {
+        self.matrix.set(None);
+        &mut self.pos
+    }
+
+    fn set_rot(&mut self) -> &mut Vec4 {
+        self.matrix.set(None);
+        &mut self.rot
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::new(Vec3::zeros(), vec4(0.0, 1.0, 0.0, 0.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matrix_is_cached_until_the_transform_changes() {
+        let mut transform = Transform::new(vec3(1.0, 2.0, 3.0), vec4(0.0, 1.0, 0.0, 0.5));
+        assert!(transform.is_dirty());
+
+        let first = transform.matrix();
+        assert!(!transform.is_dirty());
+        assert_eq!(transform.matrix(), first);
+
+        transform.set_pos().x += 1.0;
+        assert!(transform.is_dirty());
+        let moved = transform.matrix();
+        assert_ne!(moved, first);
+        assert_eq!(moved.column(3).xyz(), vec3(2.0, 2.0, 3.0));
+    }
+
+    #[test]
+    fn scale_invalidates_the_matrix() {
+        let mut transform = Transform::default();
+        transform.matrix();
+        *transform.set_scale() = vec3(2.0, 2.0, 2.0);
+        assert!(transform.is_dirty());
+        assert_eq!(transform.matrix()[(0, 0)], 2.0);
+    }
+
+    #[test]
+    fn the_previous_matrix_is_the_stored_transform() {
+        let mut transform = Transform::new(vec3(1.0, 0.0, 0.0), vec4(0.0, 1.0, 0.0, 0.0));
+        assert_eq!(transform.previous_matrix(), transform.matrix());
+
+        let before = transform.matrix();
+        transform.store_previous();
+        transform.set_pos().x = 3.0;
+        assert_eq!(transform.previous_matrix(), before);
+        assert_eq!(transform.matrix().column(3).x, 3.0);
+    }
+
+    #[test]
+    fn interpolates_between_the_logic_steps() {
+        let mut transform = Transform::default();
+        transform.store_previous();
+        transform.set_pos().x = 2.0;
+
+        assert_eq!(transform.interpolated_matrix(0.0).column(3).x, 0.0);
+        assert_eq!(transform.interpolated_matrix(0.5).column(3).x, 1.0);
+        assert_eq!(transform.interpolated_matrix(1.0), transform.matrix());
+    }
+}
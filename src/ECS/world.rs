@@ -1,10 +1,441 @@
-use beryllium::GlWindow;
-use device_query::DeviceState;
-use nalgebra_glm::Vec2;
+use std::cell::RefCell;
+use std::ffi::CStr;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
 
-use crate::graphics::shader::ShaderProgram;
+use beryllium::{fermium, Event, GlWindow};
+use device_query::{DeviceQuery, DeviceState, Keycode};
+use log::{info, warn};
+use nalgebra_glm::{vec2, vec3, Mat4, Vec2, Vec3, Vec4};
 
-use super::{camera::CameraTrait, mouse::Mouse};
+use crate::graphics::{
+    capabilities::{self, GlCapabilities},
+    debug::DepthView,
+    draw_queue::DrawQueue,
+    extension,
+    framebuffer::Framebuffer,
+    material::Material,
+    memory,
+    shader::ShaderProgram,
+    solid_color::SolidColor,
+    state::StateGuard,
+    sync::Fence,
+    texture::Texture,
+    uniform::Uniform,
+    vertex::VertexArray,
+    *,
+};
+
+use crate::math::{set_up_axis, up_axis, UpAxis};
+use crate::profiler::{self, ProfileTree};
+
+use super::{
+    camera::{CameraTrait, Handedness},
+    clock::{FrameClock, TimeSource},
+    entity::{EntityId, EntityStore},
+    event::{self, EngineEvent, InputRecorder, InputReplay, WindowState},
+    input::InputState,
+    mesh::{InstanceBuffer, Mesh, VertexTrait},
+    minimap::Minimap,
+    mouse::Mouse,
+    scene::SceneStack,
+    scheduler::Scheduler,
+    transform::Transform,
+};
+
+/// Everything the view projection matrix of a camera is made from, the position, rotation, up,
+/// screen size, (fov, near, far) and handedness
+type CameraState = (Vec3, Vec4, Vec3, Vec2, Vec3, Handedness);
+
+/// Builder for [Enviroment]
+///
+/// # Example
+/// ```
+/// // the window is the only required dependency
+/// let env = EnviromentBuilder::new()
+///     .win(win)
+///     // Here are the optional ones, if they aren't set they are filled with these
+///     .win_size(vec2(800.0, 600.0)) // the drawable size of the window
+///     .shader_program(shader_program) // ShaderProgram::vertex_color
+///     .device(device_state) // DeviceState::new
+///     .mouse(mouse) // a free mouse from the device
+///     .build(); // And finally build
+/// ```
+#[derive(Default)]
+pub struct EnviromentBuilder {
+    /// this is the window size
+    win_size: Option<Vec2>,
+    /// Window
+    win: Option<GlWindow>,
+    /// The shader program
+    shader_program: Option<ShaderProgram>,
+    /// device is the [DeviceState] for getting keyboard and mouse
+    device: Option<DeviceState>,
+    /// mouse is the [Mouse] wrapper for all things mouse
+    mouse: Option<Mouse>,
+    /// The MSAA samples requested with [request_msaa](crate::graphics::window::request_msaa)
+    msaa_samples: Option<u8>,
+}
+
+impl EnviromentBuilder {
+    /// Creates a new enviroment builder
+    pub fn new() -> Self {
+        EnviromentBuilder {
+            win_size: None,
+            win: None,
+            shader_program: None,
+            device: None,
+            mouse: None,
+            msaa_samples: None,
+        }
+    }
+
+    /// This function is supposed to set the win_size, it is the drawable size of the window in
+    /// pixels by default, see [Enviroment::drawable_size]. It is optional
+    pub fn win_size(&mut self, win_size: Vec2) -> &mut Self {
+        self.win_size = Some(win_size);
+        self
+    }
+
+    /// This function is supposed to set the win. It must be called
+    pub fn win(&mut self, win: GlWindow) -> &mut Self {
+        self.win = Some(win);
+        self
+    }
+
+    /// This function is supposed to set the shader_program. It is optional
+    pub fn shader_program(&mut self, shader_program: ShaderProgram) -> &mut Self {
+        self.shader_program = Some(shader_program);
+        self
+    }
+
+    /// This function is supposed to set the device. It is optional
+    pub fn device(&mut self, device: DeviceState) -> &mut Self {
+        self.device = Some(device);
+        self
+    }
+
+    /// This function is supposed to set the mouse. It is optional
+    pub fn mouse(&mut self, mouse: Mouse) -> &mut Self {
+        self.mouse = Some(mouse);
+        self
+    }
+
+    /// This function is supposed to set the MSAA samples that were requested for the window,
+    /// they are compared to what the driver gave when building. It is optional
+    pub fn msaa_samples(&mut self, msaa_samples: u8) -> &mut Self {
+        self.msaa_samples = Some(msaa_samples);
+        self
+    }
+
+    /// The win_size that was set or the drawable size of the window
+    fn win_size_or(&self, (width, height): (i32, i32)) -> Vec2 {
+        self.win_size.unwrap_or(vec2(width as f32, height as f32))
+    }
+
+    /// Build the enviroment, the window is moved out of the builder
+    ///
+    /// When [EnviromentBuilder::msaa_samples] was set a warning is logged if the driver
+    /// supports fewer samples or the window got a different count, see
+    /// [Enviroment::msaa_samples]
+    ///
+    /// NOTE: will panic if the window isn't specified or the default shader fails to compile
+    pub fn build(&mut self) -> Enviroment {
+        let win = self
+            .win
+            .take()
+            .expect("Error: argument win is not satisfied\nhelp: you can call .win");
+        let win_size = self.win_size_or(win.get_drawable_size());
+        let shader_program = self.shader_program.unwrap_or_else(|| {
+            ShaderProgram::vertex_color().expect("Error: couldn't compile the default shader")
+        });
+        let device = self.device.take().unwrap_or_default();
+        let mouse = self.mouse.take().unwrap_or_else(|| device.clone().into());
+
+        let env = Enviroment::new(win_size, win, shader_program, device, mouse);
+        if let Some(requested) = self.msaa_samples {
+            let supported = env.capabilities.clamp_samples(requested);
+            if supported < requested {
+                warn!(
+                    "{}x MSAA was requested but the driver supports at most {}x",
+                    requested, supported
+                );
+            }
+            if env.msaa_samples != supported {
+                warn!(
+                    "{}x MSAA was requested but the window got {}x",
+                    requested, env.msaa_samples
+                );
+            }
+        }
+        env
+    }
+}
+
+/// The most logic steps [World::run] runs in a frame with a fixed timestep
+const MAX_STEPS_PER_FRAME: u32 = 5;
+
+/// What [World::run] does while the window is unfocused or minimized
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BackgroundMode {
+    /// Keep running at full speed
+    #[default]
+    Full,
+    /// Keep running but at most this many frames per second
+    Throttle(u32),
+    /// Stop updating and rendering until the window is active again, the events are still
+    /// polled
+    Pause,
+}
+
+/// A function called when [World::run] stops, see [Enviroment::on_quit]
+pub type QuitCallback = Box<dyn FnMut(&mut Enviroment)>;
+
+/// A function called during a phase of the frame with the world and [Enviroment::delta_time],
+/// see [FrameCallbacks]
+pub type FrameCallback<'a, GameObject> = Box<dyn FnMut(&mut World<GameObject>, f32) + 'a>;
+
+/// Functions that [World::run_callbacks] calls during each phase of the frame, every one of them
+/// is optional
+///
+/// Every frame they are called in the order pre_update, update, pre_render then post_render
+///
+/// # Example
+/// ```
+/// let mut callbacks = FrameCallbacks::new();
+/// callbacks
+///     .pre_update(|world, _| world.objects.player.read_input(&world.env))
+///     .update(|world, delta_time| world.objects.player.animate(delta_time))
+///     .post_render(|world, _| debug_draw.flush(&world.env.view_projection()));
+/// world.run_callbacks(&mut callbacks);
+/// ```
+pub struct FrameCallbacks<'a, GameObject: GameObjectTrait> {
+    /// Called after the input is read and before the world is updated
+    pub pre_update: Option<FrameCallback<'a, GameObject>>,
+    /// Called after the world is updated, it is where the frame closure of [World::run] goes
+    pub update: Option<FrameCallback<'a, GameObject>>,
+    /// Called before the world is rendered
+    pub pre_render: Option<FrameCallback<'a, GameObject>>,
+    /// Called after the world is rendered and before it is presented, e.g. for overlays
+    pub post_render: Option<FrameCallback<'a, GameObject>>,
+}
+
+impl<'a, GameObject: GameObjectTrait> FrameCallbacks<'a, GameObject> {
+    /// Creates callbacks that do nothing
+    pub fn new() -> Self {
+        FrameCallbacks {
+            pre_update: None,
+            update: None,
+            pre_render: None,
+            post_render: None,
+        }
+    }
+
+    /// This function is supposed to set pre_update. It is optional
+    pub fn pre_update<F: FnMut(&mut World<GameObject>, f32) + 'a>(&mut self, f: F) -> &mut Self {
+        self.pre_update = Some(Box::new(f));
+        self
+    }
+
+    /// This function is supposed to set update. It is optional
+    pub fn update<F: FnMut(&mut World<GameObject>, f32) + 'a>(&mut self, f: F) -> &mut Self {
+        self.update = Some(Box::new(f));
+        self
+    }
+
+    /// This function is supposed to set pre_render. It is optional
+    pub fn pre_render<F: FnMut(&mut World<GameObject>, f32) + 'a>(&mut self, f: F) -> &mut Self {
+        self.pre_render = Some(Box::new(f));
+        self
+    }
+
+    /// This function is supposed to set post_render. It is optional
+    pub fn post_render<F: FnMut(&mut World<GameObject>, f32) + 'a>(&mut self, f: F) -> &mut Self {
+        self.post_render = Some(Box::new(f));
+        self
+    }
+}
+
+impl<'a, GameObject: GameObjectTrait> Default for FrameCallbacks<'a, GameObject> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Calls the callback of a phase if it is set
+fn call_phase<GameObject: GameObjectTrait>(
+    callback: &mut Option<FrameCallback<'_, GameObject>>,
+    world: &mut World<GameObject>,
+) {
+    if let Some(callback) = callback {
+        let delta_time = world.env.delta_time();
+        callback(world, delta_time);
+    }
+}
+
+/// A phase of the frame that [run_frames] calls back into, see [FrameCallbacks]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    PreUpdate,
+    Update,
+    PreRender,
+    PostRender,
+}
+
+/// The parts of a frame of [World::run_callbacks], the loop is written against this trait so it
+/// can run without a window
+trait FrameLoop {
+    /// Sets up the loop before the first frame
+    fn start(&mut self);
+    /// Checks if the loop should keep going, see [Enviroment::quit]
+    fn is_running(&self) -> bool;
+    /// Polls the events and reads the input, returns false if the loop should stop
+    fn poll(&mut self) -> bool;
+    /// Checks if the window is focused and not minimized
+    fn is_active(&self) -> bool;
+    /// What the loop does while the window isn't active
+    fn background_mode(&self) -> BackgroundMode;
+    /// Waits for something to happen while the loop is paused
+    fn wait_paused(&mut self);
+    /// Checks if every frame should be checked with a [StateGuard]
+    fn validate_state(&self) -> bool;
+    /// Updates the world once, or as many logic steps as fit with a fixed timestep
+    fn step(&mut self);
+    /// Clears the screen and draws the world
+    fn render_frame(&mut self);
+    /// Shows the rendered frame
+    fn present_frame(&mut self);
+    /// Ends the frame then sleeps for the rest of it if the frame rate is capped
+    fn end_frame(&mut self, active: bool, frame_start: Instant);
+    /// Cleans up after the last frame and calls the quit callback
+    fn finish(&mut self);
+}
+
+/// Runs frames until the host stops, phase is called with each [Phase] of the frame
+fn run_frames<Host: FrameLoop>(host: &mut Host, mut phase: impl FnMut(&mut Host, Phase)) {
+    host.start();
+    while host.is_running() {
+        let frame_start = Instant::now();
+        if !host.poll() {
+            break;
+        }
+
+        let active = host.is_active();
+        if !active && host.background_mode() == BackgroundMode::Pause {
+            host.wait_paused();
+            continue;
+        }
+
+        let state_guard = host.validate_state().then(StateGuard::new);
+        phase(host, Phase::PreUpdate);
+        host.step();
+        phase(host, Phase::Update);
+
+        phase(host, Phase::PreRender);
+        host.render_frame();
+        phase(host, Phase::PostRender);
+        host.present_frame();
+        if let Some(state_guard) = state_guard {
+            state_guard.check_and_restore();
+        }
+        host.end_frame(active, frame_start);
+    }
+    host.finish();
+}
+
+/// The matrix of the last frame, on the first frame there is none so it is the current one
+fn previous_matrix(last: Option<Mat4>, current: Mat4) -> Mat4 {
+    last.unwrap_or(current)
+}
+
+/// Checks if the quit key is held while the window is focused, see [Enviroment::quit_key]
+fn quit_key_down(quit_key: Option<Keycode>, window_state: WindowState, input: &InputState) -> bool {
+    match quit_key {
+        Some(key) => window_state.focused && input.is_key_down(key),
+        None => false,
+    }
+}
+
+/// Checks if the window was closed or the quit key was pressed this frame
+fn should_quit(events: &[EngineEvent], quit_key_pressed: bool) -> bool {
+    quit_key_pressed
+        || events
+            .iter()
+            .any(|event| matches!(event, EngineEvent::Sdl(Event::Quit(_))))
+}
+
+/// The most frames per second [World::run] renders, see [Enviroment::frame_cap]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FrameCap {
+    /// Render as fast as possible, or as fast as vsync allows
+    #[default]
+    Unlimited,
+    /// Render at most this many frames per second
+    Fps(u32),
+    /// Render at most as many frames per second as the refresh rate of the display the window
+    /// is on, see [Enviroment::display_refresh_rate]. It is unlimited if the rate is unknown
+    MatchRefresh,
+}
+
+impl FrameCap {
+    /// The frames per second the cap allows given the refresh rate of the display, None means
+    /// it is unlimited
+    pub fn fps(self, refresh_rate: Option<u32>) -> Option<u32> {
+        match self {
+            FrameCap::Unlimited => None,
+            FrameCap::Fps(fps) => Some(fps),
+            FrameCap::MatchRefresh => refresh_rate,
+        }
+    }
+}
+
+/// The refresh rate of a display mode, SDL uses 0 when it is unknown
+fn mode_refresh_rate(mode: &fermium::SDL_DisplayMode) -> Option<u32> {
+    (mode.refresh_rate > 0).then_some(mode.refresh_rate as u32)
+}
+
+/// The GL state a pass of [World::render] draws the world with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PassState {
+    color_write: bool,
+    depth_write: bool,
+    depth_func: u32,
+}
+
+impl PassState {
+    fn apply(self) {
+        let color = self.color_write;
+        color_mask(color, color, color, color);
+        depth_mask(self.depth_write);
+        depth_func(self.depth_func);
+    }
+}
+
+/// The passes [World::render] draws the world with, a depth prepass only writes the depth then
+/// the main pass only draws the fragments that are equal to it
+fn frame_passes(depth_prepass: bool) -> Vec<PassState> {
+    let main = PassState {
+        color_write: true,
+        depth_write: true,
+        depth_func: GL_LESS,
+    };
+    if depth_prepass {
+        vec![
+            PassState {
+                color_write: false,
+                ..main
+            },
+            PassState {
+                depth_write: false,
+                depth_func: GL_EQUAL,
+                ..main
+            },
+        ]
+    } else {
+        vec![main]
+    }
+}
 
 /// The world envieorment containing things like the keyboard and window
 pub struct Enviroment {
@@ -18,6 +449,93 @@ pub struct Enviroment {
     pub device: DeviceState,
     /// mouse is the [Mouse] wrapper for all things mouse
     pub mouse: Mouse,
+    /// When true [World::render] draws the scene to the depth buffer only before the shaded pass
+    /// so that each pixel is only shaded once. It is off by default
+    pub depth_prepass: bool,
+    /// When true [World::render] sets the float u_time (seconds since the enviroment was created) and
+    /// uint u_frame (how many frames were rendered) uniforms of the shader program. It is off by default
+    pub time_uniforms: bool,
+    /// When true [World::draw_mesh] also sets the mat4 u_prev_camera_matrix uniform to last
+    /// frame's view projection matrix so a shader can write screen space velocity, e.g. for motion
+    /// blur. See [Transform::upload](super::transform::Transform::upload) for the model matrices.
+    /// It is off by default
+    pub motion_vectors: bool,
+    /// When true [Enviroment::present] waits for the gpu to finish the frame before swapping, so
+    /// the driver can't queue up frames behind vsync and add input lag. It costs some gpu
+    /// throughput so it is off by default
+    pub low_latency: bool,
+    /// When true [World::render] draws the world into an offscreen framebuffer then copies the
+    /// color to the window, so the depth is kept in a texture that effects drawn afterwards can
+    /// sample, see [Enviroment::depth_texture]. The depth buffer of the window isn't written. It
+    /// is off by default
+    pub keep_depth_texture: bool,
+    /// When true [World::render] shows the linearized depth of the frame in grayscale instead of
+    /// its color, near things are white and far things are black, see [DepthView]. The world is
+    /// drawn offscreen like with [Enviroment::keep_depth_texture] while it is on, without turning
+    /// that on. It is off by default
+    pub show_depth: bool,
+    /// The length of a logic step in seconds, see [Enviroment::set_fixed_timestep]
+    fixed_timestep: Option<f32>,
+    /// When true [World::run] checks that every frame restores the GL state it changed, e.g.
+    /// blending or the bound VAO, then logs and undoes the leaked changes, see [StateGuard]. It is
+    /// on in debug builds and off in release builds
+    pub validate_state: bool,
+    /// When this key is pressed while the window is focused [World::run] stops, None means only
+    /// closing the window stops it. It is Escape by default
+    pub quit_key: Option<Keycode>,
+    /// What [World::run] does while the window is unfocused or minimized. It is
+    /// [BackgroundMode::Full] by default
+    pub background_mode: BackgroundMode,
+    /// The most frames per second [World::run] renders, the loop sleeps for the rest of the
+    /// frame. It is [FrameCap::Unlimited] by default
+    pub frame_cap: FrameCap,
+    /// The time and frame count, u_time is relative to when it started
+    clock: FrameClock,
+    /// The SDL window of win, beryllium dosen't expose it
+    sdl_window: *mut fermium::SDL_Window,
+    /// The GL context of win
+    sdl_context: fermium::SDL_GLContext,
+    /// The driver limits, queried once when the enviroment is created
+    capabilities: GlCapabilities,
+    /// The samples of the default framebuffer, queried once when the enviroment is created
+    msaa_samples: u8,
+    /// The value the depth buffer is cleared to
+    clear_depth: f32,
+    /// The near and far of glDepthRange
+    depth_range: (f32, f32),
+    /// The events of the current frame of [World::run]
+    events: Vec<EngineEvent>,
+    /// Set to false by [Enviroment::quit] to stop [World::run]
+    running: bool,
+    /// The keys and mouse buttons held this frame, updated by [World::run]
+    input: InputState,
+    /// Called once when [World::run] stops, see [Enviroment::on_quit]
+    on_quit: Option<QuitCallback>,
+    /// Writes the polled events to a file, see [Enviroment::record_input]
+    recorder: Option<InputRecorder>,
+    /// Replaces the polled events with recorded ones, see [Enviroment::replay_input]
+    replay: Option<InputReplay>,
+    /// The focus and minimize state of the window, updated by [World::run]
+    window_state: WindowState,
+    /// The time [World::run] hasn't simulated yet with a fixed timestep
+    accumulator: f32,
+    /// The time of the clock when [World::run] last added to the accumulator
+    last_step: Duration,
+    /// How far the render is between the last two logic steps
+    alpha: f32,
+    /// The view projection matrix of the camera this frame, it is set by [World::render]
+    view_projection: Option<Mat4>,
+    /// The view projection matrix of the camera last frame
+    prev_view_projection: Mat4,
+    /// The camera the view projection matrix was last computed for and the matrix, it is reused
+    /// while the camera dosen't change
+    camera_cache: Option<(CameraState, Mat4)>,
+    /// The programs that got the camera matrix this frame from [World::draw_mesh]
+    camera_programs: RefCell<Vec<u32>>,
+    /// The framebuffer the world is drawn into when [Enviroment::keep_depth_texture] is on
+    scene: Option<Framebuffer>,
+    /// Draws the depth when [Enviroment::show_depth] is on, it is made the first time it is used
+    depth_view: Option<DepthView>,
 }
 
 impl Enviroment {
@@ -29,13 +547,641 @@ impl Enviroment {
         device: DeviceState,
         mouse: Mouse,
     ) -> Self {
+        info!(
+            "Created a {}x{} window with OpenGL {} on {} and {}x MSAA",
+            win_size.x,
+            win_size.y,
+            get_string(GL_VERSION),
+            get_string(GL_RENDERER),
+            capabilities::get_integer(GL_SAMPLES)
+        );
+
+        let (sdl_window, sdl_context) = window::current();
+        viewport(0, 0, win_size.x as i32, win_size.y as i32);
+
         Enviroment {
+            sdl_window,
+            sdl_context,
             win_size,
             win,
             shader_program,
             device,
             mouse,
+            depth_prepass: false,
+            time_uniforms: false,
+            motion_vectors: false,
+            low_latency: false,
+            keep_depth_texture: false,
+            show_depth: false,
+            fixed_timestep: None,
+            validate_state: cfg!(debug_assertions),
+            quit_key: Some(Keycode::Escape),
+            background_mode: BackgroundMode::Full,
+            frame_cap: FrameCap::Unlimited,
+            clock: FrameClock::new(),
+            capabilities: GlCapabilities::query(),
+            msaa_samples: capabilities::get_integer(GL_SAMPLES).clamp(0, u8::MAX as i32) as u8,
+            clear_depth: 1.0,
+            depth_range: (0.0, 1.0),
+            events: Vec::new(),
+            running: true,
+            on_quit: None,
+            input: InputState::new(),
+            recorder: None,
+            replay: None,
+            window_state: WindowState::default(),
+            accumulator: 0.0,
+            last_step: Duration::ZERO,
+            alpha: 1.0,
+            view_projection: None,
+            prev_view_projection: Mat4::identity(),
+            camera_cache: None,
+            camera_programs: RefCell::new(Vec::new()),
+            scene: None,
+            depth_view: None,
+        }
+    }
+
+    /// Seconds since the enviroment was created, or since the time source started, see
+    /// [Enviroment::set_time_source]
+    pub fn time(&self) -> f32 {
+        self.clock.time()
+    }
+
+    /// How many frames were rendered
+    pub fn frame(&self) -> u32 {
+        self.clock.frame()
+    }
+
+    /// Seconds between the last two frames, use it to make movement independent of the frame rate
+    pub fn delta_time(&self) -> f32 {
+        self.clock.delta_time()
+    }
+
+    /// Replaces the real time with source, e.g. so a test or a replay controls u_time, the delta
+    /// time and the steps of a [Enviroment::fixed_timestep]. The frame count is kept
+    ///
+    /// # Example
+    /// ```
+    /// let now = Rc::new(Cell::new(Duration::ZERO));
+    /// let source = now.clone();
+    /// world.env.set_time_source(Box::new(move || source.get()));
+    ///
+    /// now.set(Duration::from_secs(2));
+    /// world.render(); // u_time is 2.0
+    /// ```
+    pub fn set_time_source(&mut self, source: TimeSource) {
+        self.clock.set_source(source);
+    }
+
+    /// Sets u_time and u_frame if [Enviroment::time_uniforms] is on then counts the frame
+    fn next_frame(&mut self) {
+        if self.time_uniforms {
+            self.shader_program.use_program();
+            if let Some(uniform) = Uniform::find(&self.shader_program, "u_time") {
+                uniform.set_uniform_f(&[self.time()]);
+            }
+            if let Some(uniform) = Uniform::find(&self.shader_program, "u_frame") {
+                uniform.set_uniform_ui(&[self.frame()]);
+            }
+        }
+        self.clock.next_frame();
+    }
+
+    /// Sets the value the depth buffer is cleared to, it is 1.0 by default and 0.0 for reverse-Z
+    pub fn set_clear_depth(&mut self, depth: f32) {
+        self.clear_depth = depth;
+        clear_depth(depth);
+    }
+
+    /// Gets the value the depth buffer is cleared to
+    pub fn get_clear_depth(&self) -> f32 {
+        self.clear_depth
+    }
+
+    /// Sets the depth range, it is (0.0, 1.0) by default
+    ///
+    /// # Example
+    /// ```
+    /// // draw the first person arms in a compressed range so they never clip into walls
+    /// world.env.set_depth_range(0.0, 0.1);
+    /// draw_arms();
+    /// world.env.set_depth_range(0.1, 1.0);
+    /// draw_level();
+    /// ```
+    pub fn set_depth_range(&mut self, near: f32, far: f32) {
+        self.depth_range = (near, far);
+        depth_range(near, far);
+    }
+
+    /// Gets the depth range as (near, far)
+    pub fn get_depth_range(&self) -> (f32, f32) {
+        self.depth_range
+    }
+
+    /// Sets the rectangle of the window that is drawn to, e.g. for split screen, see
+    /// [Enviroment::scoped_viewport] to restore it afterwards
+    pub fn set_viewport(&mut self, x: i32, y: i32, width: i32, height: i32) {
+        viewport(x, y, width, height);
+    }
+
+    /// Gets the viewport as (x, y, width, height)
+    pub fn viewport(&self) -> (i32, i32, i32, i32) {
+        get_viewport()
+    }
+
+    /// Sets the viewport until the returned guard is dropped
+    ///
+    /// # Example
+    /// ```
+    /// let (_, _, width, height) = world.env.viewport();
+    /// {
+    ///     let _left = world.env.scoped_viewport(0, 0, width / 2, height);
+    ///     draw_player_one();
+    /// }
+    /// ```
+    pub fn scoped_viewport(&mut self, x: i32, y: i32, width: i32, height: i32) -> ViewportGuard {
+        ViewportGuard::new(x, y, width, height)
+    }
+
+    /// Turns on sample shading with a minimum rate when it is Some and turns it off when it is
+    /// None, the rate is clamped to 0.0..=1.0
+    ///
+    /// With MSAA every fragment is normally shaded once for all its samples, sample shading
+    /// shades at least rate * samples of them separately so alpha tested foliage and high
+    /// frequency textures are antialiased inside of triangles too. 1.0 shades every sample, it
+    /// is the slowest. It needs OpenGL 4.0 or GL_ARB_sample_shading and an MSAA window, see
+    /// [Enviroment::msaa_samples]
+    ///
+    /// # Example
+    /// ```
+    /// if world.env.set_sample_shading(Some(0.5)).is_err() {
+    ///     // fall back to alpha to coverage
+    /// }
+    /// ```
+    pub fn set_sample_shading(&mut self, min_rate: Option<f32>) -> Result<(), String> {
+        if !extension::is_loaded("glMinSampleShading") {
+            return Err(
+                "Sample shading needs OpenGL 4.0 or GL_ARB_sample_shading, see load_extensions_with"
+                    .to_string(),
+            );
+        }
+        match min_rate {
+            Some(rate) => {
+                enable(extension::GL_SAMPLE_SHADING);
+                unsafe { extension::glMinSampleShading(rate.clamp(0.0, 1.0)) };
+            }
+            None => disable(extension::GL_SAMPLE_SHADING),
+        }
+        Ok(())
+    }
+
+    /// Gets the minimum sample shading rate, it is None when sample shading is off or isn't
+    /// supported
+    pub fn sample_shading(&self) -> Option<f32> {
+        (extension::is_loaded("glMinSampleShading") && is_enabled(extension::GL_SAMPLE_SHADING))
+            .then(|| capabilities::get_float(extension::GL_MIN_SAMPLE_SHADING_VALUE))
+    }
+
+    /// Turns the GL error checks of [gl_check](crate::gl_check) on or off in debug builds, they
+    /// are on by default. Release builds never check
+    pub fn set_debug_gl(&mut self, on: bool) {
+        set_debug_gl(on);
+    }
+
+    /// Checks if GL calls check for errors
+    pub fn debug_gl(&self) -> bool {
+        debug_gl()
+    }
+
+    /// Clips drawing and clearing to a rectangle of the window when it is Some, e.g. for UI panels,
+    /// and turns the clipping off when it is None
+    ///
+    /// # Example
+    /// ```
+    /// world.env.set_scissor(Some((10, 10, 200, 100)));
+    /// clear(GL_COLOR_BUFFER_BIT); // only the panel is cleared
+    /// draw_panel();
+    /// world.env.set_scissor(None);
+    /// ```
+    pub fn set_scissor(&mut self, rect: Option<Rect>) {
+        match rect {
+            Some((x, y, width, height)) => {
+                enable(GL_SCISSOR_TEST);
+                scissor(x, y, width, height);
+            }
+            None => disable(GL_SCISSOR_TEST),
+        }
+    }
+
+    /// Gets the rectangle drawing is clipped to, it is None when the scissor test is off
+    pub fn scissor(&self) -> Option<Rect> {
+        is_enabled(GL_SCISSOR_TEST).then(get_scissor)
+    }
+
+    /// Sets the title of the window
+    pub fn set_title(&mut self, title: &str) {
+        self.win.set_title(title);
+    }
+
+    /// Gets the position of the top left corner of the window on the screen in screen
+    /// coordinates, the same coordinates as the mouse from [DeviceState]
+    pub fn window_position(&self) -> Vec2 {
+        let (mut x, mut y) = (0, 0);
+        unsafe { fermium::SDL_GetWindowPosition(self.sdl_window, &mut x, &mut y) };
+        vec2(x as f32, y as f32)
+    }
+
+    /// Gets the size of the window in screen coordinates, it can be smaller than
+    /// [Enviroment::win_size] on high DPI screens
+    pub fn window_size(&self) -> Vec2 {
+        let (mut width, mut height) = (0, 0);
+        unsafe { fermium::SDL_GetWindowSize(self.sdl_window, &mut width, &mut height) };
+        vec2(width as f32, height as f32)
+    }
+
+    /// Gets the size of the default framebuffer in pixels with SDL_GL_GetDrawableSize, on high DPI
+    /// screens it is bigger than [Enviroment::window_size]. The viewport and the screen size of
+    /// the camera should use it
+    ///
+    /// # Example
+    /// ```
+    /// let (width, height) = world.env.drawable_size();
+    /// world.env.set_viewport(0, 0, width as i32, height as i32);
+    /// ```
+    pub fn drawable_size(&self) -> (u32, u32) {
+        let (mut width, mut height) = (0, 0);
+        unsafe { fermium::SDL_GL_GetDrawableSize(self.sdl_window, &mut width, &mut height) };
+        (width.max(0) as u32, height.max(0) as u32)
+    }
+
+    /// Sets [Enviroment::win_size] and the viewport to the [Enviroment::drawable_size], it is
+    /// called by [World::run] when the window changes size. A minimized window with no pixels
+    /// is ignored
+    pub fn resize_to_drawable(&mut self) {
+        let (width, height) = self.drawable_size();
+        if width == 0 || height == 0 {
+            return;
+        }
+        self.win_size = vec2(width as f32, height as f32);
+        viewport(0, 0, width as i32, height as i32);
+    }
+
+    /// How many pixels there are per screen coordinate, e.g. 2.0 on a retina screen and 1.0
+    /// otherwise
+    pub fn dpi_scale(&self) -> f32 {
+        let window_width = self.window_size().x;
+        if window_width <= 0.0 {
+            1.0
+        } else {
+            self.drawable_size().0 as f32 / window_width
+        }
+    }
+
+    /// Gets the refresh rate in Hz of the display the window is on, it is None if SDL dosen't
+    /// know it
+    pub fn display_refresh_rate(&self) -> Option<u32> {
+        unsafe {
+            let display = fermium::SDL_GetWindowDisplayIndex(self.sdl_window);
+            let mut mode: fermium::SDL_DisplayMode = std::mem::zeroed();
+            if display < 0 || fermium::SDL_GetCurrentDisplayMode(display, &mut mode) != 0 {
+                return None;
+            }
+            mode_refresh_rate(&mode)
+        }
+    }
+
+    /// When it is Some [World::run] updates the world in fixed steps of this many seconds and
+    /// renders as often as it can, [World::draw_mesh_transformed] interpolates between the steps
+    /// with [Enviroment::alpha]. It is None by default so the world is updated once per frame
+    ///
+    /// It is an error for the step not to be a finite number more than 0
+    ///
+    /// # Example
+    /// ```
+    /// world.env.set_fixed_timestep(Some(1.0 / 60.0))?;
+    /// ```
+    pub fn set_fixed_timestep(&mut self, step: Option<f32>) -> Result<(), String> {
+        match step {
+            Some(step) if !step.is_finite() || step <= 0.0 => Err(format!(
+                "The fixed timestep must be a finite number more than 0 not {}",
+                step
+            )),
+            _ => {
+                self.fixed_timestep = step;
+                Ok(())
+            }
+        }
+    }
+
+    /// The length of a logic step in seconds, None means the world is updated once per frame
+    pub fn fixed_timestep(&self) -> Option<f32> {
+        self.fixed_timestep
+    }
+
+    /// The frames per second [Enviroment::frame_cap] allows on the current display, None means
+    /// it is unlimited
+    pub fn frame_cap_fps(&self) -> Option<u32> {
+        self.frame_cap.fps(self.display_refresh_rate())
+    }
+
+    /// Gets the title of the window, it is empty if SDL has no title for it
+    pub fn get_title(&self) -> String {
+        let title = unsafe { fermium::SDL_GetWindowTitle(self.sdl_window) };
+        if title.is_null() {
+            return String::new();
+        }
+        unsafe { CStr::from_ptr(title) }
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    /// Sets the icon shown in the title bar and taskbar
+    pub fn set_icon(&mut self, icon: &DynamicImage) -> Result<(), Error> {
+        let mut icon = icon.to_rgba8();
+        let (r, g, b, a) = if cfg!(target_endian = "little") {
+            (0x0000_00FF, 0x0000_FF00, 0x00FF_0000, 0xFF00_0000)
+        } else {
+            (0xFF00_0000, 0x00FF_0000, 0x0000_FF00, 0x0000_00FF)
+        };
+
+        unsafe {
+            let surface = fermium::SDL_CreateRGBSurfaceFrom(
+                icon.as_mut_ptr().cast(),
+                icon.width() as i32,
+                icon.height() as i32,
+                32,
+                icon.width() as i32 * 4,
+                r,
+                g,
+                b,
+                a,
+            );
+            if surface.is_null() {
+                return Err(Error::Misc(window::sdl_error()));
+            }
+            // SDL copies the pixels so the surface can be freed straight away
+            fermium::SDL_SetWindowIcon(self.sdl_window, surface);
+            fermium::SDL_FreeSurface(surface);
+        }
+
+        Ok(())
+    }
+
+    /// Shows the frame by swapping the window, in [Enviroment::low_latency] mode a fence is
+    /// inserted and waited on first so at most one frame is ever queued
+    pub fn present(&mut self) {
+        crate::profile!("present");
+        if self.low_latency && !Fence::new().wait(Duration::from_secs(1)) {
+            warn!("The gpu didn't finish the frame before presenting it");
+        }
+        self.win.swap_window();
+    }
+
+    /// Makes the context of the main window current again, e.g. after drawing to a
+    /// [SharedWindow](crate::graphics::window::SharedWindow)
+    pub fn make_current(&self) {
+        unsafe { fermium::SDL_GL_MakeCurrent(self.sdl_window, self.sdl_context) };
+    }
+
+    /// Starts sending [EngineEvent::TextInput](super::event::EngineEvent::TextInput) events,
+    /// call it when a text field gets focus
+    pub fn start_text_input(&self) {
+        event::start_text_input();
+    }
+
+    /// Stops sending text input events, call it when a text field loses focus
+    pub fn stop_text_input(&self) {
+        event::stop_text_input();
+    }
+
+    /// Checks if text input is on
+    pub fn is_text_input_active(&self) -> bool {
+        event::is_text_input_active()
+    }
+
+    /// How far the current frame is between the previous and the current logic step from 0 to 1,
+    /// pass it to [Transform::interpolated_matrix](super::transform::Transform::interpolated_matrix)
+    ///
+    /// It is always 1 without a [Enviroment::fixed_timestep]
+    pub fn alpha(&self) -> f32 {
+        self.alpha
+    }
+
+    /// The view projection matrix of the camera in the current frame, it is updated by
+    /// [World::render] and is the identity before the first frame
+    pub fn view_projection(&self) -> Mat4 {
+        self.view_projection.unwrap_or_else(Mat4::identity)
+    }
+
+    /// The view projection matrix of the camera in the previous frame, in the first frame it is
+    /// the same as [Enviroment::view_projection]
+    pub fn prev_view_projection(&self) -> Mat4 {
+        self.prev_view_projection
+    }
+
+    /// The framebuffer the last frame was drawn into when [Enviroment::keep_depth_texture] is
+    /// on, its depth texture can be sampled by effects such as soft particles and fog
+    ///
+    /// # Example
+    /// ```
+    /// world.env.keep_depth_texture = true;
+    /// world.render();
+    /// if let Some(scene) = world.env.depth_texture() {
+    ///     scene.bind_depth(GL_TEXTURE1);
+    ///     Uniform::new(&fog, "scene_depth").set_uniform_i(&[1]);
+    ///     fullscreen_pass(&fog);
+    /// }
+    /// ```
+    pub fn depth_texture(&self) -> Option<&Framebuffer> {
+        self.scene.as_ref()
+    }
+
+    /// Binds the scene framebuffer, making it first if it dosen't exist or the window was
+    /// resized
+    fn bind_scene(&mut self) {
+        let (width, height) = (self.win_size.x as u32, self.win_size.y as u32);
+        if let Some(scene) = &self.scene {
+            if (scene.width, scene.height) != (width, height) {
+                scene.delete();
+                self.scene = None;
+            }
+        }
+        if self.scene.is_none() {
+            match Framebuffer::new(width, height) {
+                Ok(scene) => self.scene = Some(scene),
+                Err(err) => {
+                    warn!(
+                        "Couldn't make the framebuffer for the depth texture: {}",
+                        err
+                    );
+                    self.keep_depth_texture = false;
+                    self.show_depth = false;
+                    return;
+                }
+            }
+        }
+        if let Some(scene) = &self.scene {
+            scene.bind();
+        }
+    }
+
+    /// The events that were polled at the start of the current frame of [World::run]
+    pub fn events(&self) -> &[EngineEvent] {
+        &self.events
+    }
+
+    /// Starts writing the events of every frame of [World::run] to a file, any previous recording
+    /// is finished first
+    ///
+    /// Replaying it with [Enviroment::replay_input] delivers the same events in the same frames
+    /// of [World::run], see [RecordedEvent](event::RecordedEvent) for the events that are kept.
+    /// It only reproduces the events, the keyboard and mouse state of [Enviroment::input] isn't
+    /// recorded, and with a [Enviroment::fixed_timestep] the number of logic steps in a frame
+    /// still depends on the real time, so a session that depends on them can play out differently
+    ///
+    /// # Example
+    /// ```
+    /// world.env.set_fixed_timestep(Some(1.0 / 60.0))?;
+    /// if std::env::args().any(|arg| arg == "--replay") {
+    ///     world.env.replay_input("data/bug.input")?;
+    /// } else {
+    ///     world.env.record_input("data/bug.input")?;
+    /// }
+    /// world.run(|_| ());
+    /// ```
+    pub fn record_input<P: AsRef<Path>>(&mut self, path: P) -> Result<(), String> {
+        self.stop_recording()?;
+        self.recorder = Some(InputRecorder::create(path)?);
+        Ok(())
+    }
+
+    /// Finishes the recording started by [Enviroment::record_input], it is also finished when
+    /// the enviroment is dropped
+    pub fn stop_recording(&mut self) -> Result<(), String> {
+        match self.recorder.take() {
+            Some(recorder) => recorder.finish(),
+            None => Ok(()),
+        }
+    }
+
+    /// Plays back a recording from [Enviroment::record_input], while it plays the live events
+    /// are ignored except for closing the window. When every event was played back the live
+    /// events are used again
+    pub fn replay_input<P: AsRef<Path>>(&mut self, path: P) -> Result<(), String> {
+        self.replay = Some(InputReplay::open(path)?);
+        Ok(())
+    }
+
+    /// Checks if a recording is being played back
+    pub fn is_replaying(&self) -> bool {
+        self.replay.is_some()
+    }
+
+    /// Takes the events of this frame, from the replay if there is one, and records them if
+    /// recording
+    fn poll_events(&mut self) -> Vec<EngineEvent> {
+        let live = event::poll_raw_events();
+        let raw = match &mut self.replay {
+            Some(replay) => {
+                let mut events = replay.next_frame();
+                if replay.is_finished() {
+                    info!("Finished replaying the input");
+                    self.replay = None;
+                }
+                // the window can still be closed during a replay
+                events.extend(
+                    live.into_iter()
+                        .filter(|event| unsafe { event.type_ == fermium::SDL_QUIT }),
+                );
+                events
+            }
+            None => live,
+        };
+
+        if let Some(recorder) = &mut self.recorder {
+            if let Err(err) = recorder.record(&raw) {
+                warn!("Stopped recording the input: {}", err);
+                self.recorder = None;
+            }
         }
+
+        raw.into_iter().filter_map(event::from_raw).collect()
+    }
+
+    /// Whether the window is focused and minimized, it is updated from the events by [World::run]
+    pub fn window_state(&self) -> WindowState {
+        self.window_state
+    }
+
+    /// Stops [World::run] after the current frame
+    pub fn quit(&mut self) {
+        self.running = false;
+    }
+
+    /// Sets a function that is called once right before [World::run] returns, however it was
+    /// stopped, e.g. to save the game. It replaces the previous one
+    ///
+    /// # Example
+    /// ```
+    /// world.env.quit_key = Some(Keycode::Q);
+    /// world.env.on_quit(|env| info!("Quit after {} frames", env.frame()));
+    /// ```
+    pub fn on_quit<F: FnMut(&mut Enviroment) + 'static>(&mut self, on_quit: F) {
+        self.on_quit = Some(Box::new(on_quit));
+    }
+
+    /// The keys and mouse buttons held this frame, see [InputState]
+    pub fn input(&self) -> &InputState {
+        &self.input
+    }
+
+    /// Reads the keyboard and mouse into [Enviroment::input] and [Enviroment::mouse], it is
+    /// called every frame by [World::run]
+    pub fn update_input(&mut self) {
+        self.mouse.mouse = self.device.get_mouse();
+        self.input.update(self.device.get_keys(), &self.mouse.mouse);
+    }
+
+    /// Checks if the quit key is pressed while the window is focused
+    fn quit_key_pressed(&self) -> bool {
+        quit_key_down(self.quit_key, self.window_state, &self.input)
+    }
+
+    /// Checks if [World::run] will keep going after the current frame
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    /// Gets the limits of the driver and the supported extensions
+    pub fn capabilities(&self) -> &GlCapabilities {
+        &self.capabilities
+    }
+
+    /// How many samples the default framebuffer has, 0 means it isn't multisampled
+    ///
+    /// It is what the driver actually gave, which can be less than what was requested with
+    /// [request_msaa](crate::graphics::window::request_msaa)
+    pub fn msaa_samples(&self) -> u8 {
+        self.msaa_samples
+    }
+}
+
+impl Drop for Enviroment {
+    /// Deletes the shader program, the GL context and window are dropped after this
+    fn drop(&mut self) {
+        self.make_current();
+        self.shader_program.delete();
+        if let Some(scene) = self.scene.take() {
+            scene.delete();
+        }
+        if let Some(depth_view) = self.depth_view.take() {
+            depth_view.delete();
+        }
+        if get_error() != GL_NO_ERROR {
+            warn!("There was a GL error while shutting down the enviroment");
+        }
+        info!("Shut down the enviroment after {} frames", self.frame());
     }
 }
 
@@ -67,6 +1213,17 @@ pub trait GameObjectTrait {
     where
         Self: Sized;
 
+    /// Draws the objects in game object, it is called once per pass by [World::render]
+    /// so it should only issue draw calls
+    fn draw(&self) -> fn(world: &World<Self>)
+    where
+        Self: Sized;
+
+    /// Calls [Transform::store_previous] on the transforms of the objects, it is called by
+    /// [World::run] before every logic step so [World::draw_mesh_transformed] can interpolate
+    /// between the steps. It does nothing by default
+    fn store_previous(&mut self) {}
+
     /// Gets the camera, must implemt CameraTrait
     fn get_camera(&self) -> &dyn CameraTrait<Self>;
 
@@ -74,22 +1231,934 @@ pub trait GameObjectTrait {
     fn set_camera(&mut self) -> &mut dyn CameraTrait<Self>;
 }
 
+/// Lets an object replace the standard mesh path with its own GL calls, e.g. a particle renderer,
+/// see [World::draw_object]
+///
+/// # Example
+/// ```
+/// impl CustomDraw<GameObject> for Sparks {
+///     fn custom_draw(&self, world: &World<GameObject>, camera: &dyn CameraTrait<GameObject>) -> bool {
+///         self.particles.draw(&world.env.view_projection(), camera.get_pos());
+///         true
+///     }
+/// }
+///
+/// // objects without custom drawing keep the default, which draws their mesh
+/// impl CustomDraw<GameObject> for Crate {}
+/// ```
+pub trait CustomDraw<GameObject: GameObjectTrait> {
+    /// Draws the object, it returns true if it drew the object or false to draw its mesh with
+    /// [World::draw_mesh] instead. By default it draws nothing and returns false
+    fn custom_draw(
+        &self,
+        _world: &World<GameObject>,
+        _camera: &dyn CameraTrait<GameObject>,
+    ) -> bool {
+        false
+    }
+}
+
 /// World struct taht stores everything thats relevant to the world
+///
+/// NOTE: objects and entities are declared before env so they are dropped first, while the GL
+/// context still exists
 pub struct World<GameObject: GameObjectTrait> {
-    /// The computer enviroment
-    pub env: Enviroment,
     /// All the objects in the world
     pub objects: GameObject,
+    /// The objects spawned at runtime, see [World::spawn_named]
+    pub entities: EntityStore,
+    /// Functions to call after a delay or repeatedly, the timers are advanced in [World::update]
+    pub scheduler: Scheduler<'static, World<GameObject>>,
+    /// The computer enviroment
+    pub env: Enviroment,
 }
 
 impl<GameObject: GameObjectTrait> World<GameObject> {
     /// Creates a new world struct
     pub fn new(env: Enviroment, objects: GameObject) -> Self {
-        World { env, objects }
+        World {
+            objects,
+            entities: EntityStore::new(),
+            scheduler: Scheduler::new(),
+            env,
+        }
+    }
+
+    /// Releases everything the world owns in the right order, the objects and entities (and
+    /// their GL objects) then the shader program and finally the GL context and window
+    ///
+    /// It is the same as dropping the world but makes the order explicit
+    pub fn shutdown(self) {
+        let World {
+            objects,
+            entities,
+            scheduler,
+            env,
+        } = self;
+        drop(scheduler);
+        drop(objects);
+        drop(entities);
+        drop(env);
+    }
+
+    /// Runs the main loop until the window is closed, [Enviroment::quit_key] is pressed or
+    /// [Enviroment::quit] is called, then calls the function set by [Enviroment::on_quit]
+    ///
+    /// Every frame the events are polled (see [Enviroment::events]), the input is read, the
+    /// world is updated, frame is called for custom logic, then the world is cleared, rendered
+    /// and presented. [Enviroment::delta_time] is the time between frames, with a
+    /// [Enviroment::frame_cap] the loop sleeps so frames aren't shorter than the cap allows
+    ///
+    /// With a [Enviroment::fixed_timestep] the world is updated zero or more times per frame so
+    /// the logic runs at a fixed rate, at most 5 steps are run in a frame
+    ///
+    /// While the window is unfocused or minimized [Enviroment::background_mode] decides if the
+    /// loop keeps going, is throttled or is paused. A paused loop dosen't call frame and the
+    /// time it was paused isn't counted in [Enviroment::delta_time]
+    ///
+    /// With [Enviroment::validate_state] the GL state is snapshotted at the start of every frame
+    /// and any state the frame didn't restore is logged and restored after it is presented
+    ///
+    /// # Example
+    /// ```
+    /// world.env.background_mode = BackgroundMode::Throttle(10);
+    /// world.run(|world| {
+    ///     for event in world.env.events() {
+    ///         // -snip-
+    ///     }
+    ///     if world.env.frame() == 1000 {
+    ///         world.env.quit();
+    ///     }
+    /// });
+    /// ```
+    pub fn run<F: FnMut(&mut World<GameObject>)>(&mut self, mut frame: F) {
+        let mut callbacks = FrameCallbacks::new();
+        callbacks.update(|world, _| frame(world));
+        self.run_callbacks(&mut callbacks);
+    }
+
+    /// Runs the main loop like [World::run] but the top scene of scenes is updated every frame
+    /// and the visible scenes are rendered after the world, see [SceneStack]
+    ///
+    /// The loop also stops when the last scene is popped
+    pub fn run_scenes(&mut self, scenes: &mut SceneStack<World<GameObject>>) {
+        // both closures need the stack, only one of them runs at a time
+        let scenes = RefCell::new(scenes);
+        let mut callbacks = FrameCallbacks::new();
+        callbacks
+            .update(|world, delta_time| {
+                scenes.borrow_mut().update(world, delta_time);
+                if scenes.borrow().is_empty() {
+                    world.env.quit();
+                }
+            })
+            .post_render(|world, _| scenes.borrow_mut().render(world));
+        self.run_callbacks(&mut callbacks);
+    }
+
+    /// Runs the main loop like [World::run] but calls a function in each phase of the frame, see
+    /// [FrameCallbacks]
+    ///
+    /// Every frame pre_update is called after the input is read, then the world is updated and
+    /// update is called, then pre_render, the world is rendered and post_render is called before
+    /// the frame is presented. They are only called once a frame even with a
+    /// [Enviroment::fixed_timestep]
+    pub fn run_callbacks(&mut self, callbacks: &mut FrameCallbacks<GameObject>) {
+        run_frames(self, |world, phase| {
+            let callback = match phase {
+                Phase::PreUpdate => &mut callbacks.pre_update,
+                Phase::Update => &mut callbacks.update,
+                Phase::PreRender => &mut callbacks.pre_render,
+                Phase::PostRender => &mut callbacks.post_render,
+            };
+            call_phase(callback, world);
+        });
+    }
+
+    /// Spawns an object that can be found with [World::find], see [EntityStore::spawn_named]
+    ///
+    /// It is an error if an object already has the name
+    pub fn spawn_named<T: 'static>(&mut self, name: &str, object: T) -> Result<EntityId, String> {
+        self.entities.spawn_named(name, object)
+    }
+
+    /// Finds a spawned object by its name
+    pub fn find(&self, name: &str) -> Option<EntityId> {
+        self.entities.find(name)
+    }
+
+    /// Gets every spawned object with the tag, see [EntityStore::add_tag]
+    pub fn entities_with_tag(&self, tag: &str) -> Vec<EntityId> {
+        self.entities.entities_with_tag(tag)
+    }
+
+    /// How many bytes all the live meshes and textures take on the gpu, see
+    /// [Mesh::gpu_memory_bytes] and [Texture::gpu_memory_bytes]
+    ///
+    /// Meshes are counted once they are uploaded and textures are estimated from the size,
+    /// format and mipmap levels they were uploaded with. Deleted meshes and textures aren't
+    /// counted
+    pub fn total_gpu_memory(&self) -> usize {
+        memory::mesh_memory() + memory::texture_memory()
     }
 
-    /// Update the world
+    /// The CPU time spent in every [profile](crate::profile) scope of the last frame of the main
+    /// loop, [World::update], [World::render] and [Enviroment::present] are always profiled
+    ///
+    /// # Example
+    /// ```
+    /// let profile = world.cpu_profile();
+    /// let render = profile.find(&["render"]).unwrap();
+    /// for child in &render.children {
+    ///     println!("{}: {:?}", child.name, child.time);
+    /// }
+    /// ```
+    pub fn cpu_profile(&self) -> ProfileTree {
+        profiler::last_frame()
+    }
+
+    /// Sets which axis points up in the world, the default camera up vector, the controllers and
+    /// their ground plane follow it. It is [UpAxis::Y] by default
+    ///
+    /// NOTE: the up axis is global so it is shared by every world, see [crate::math::set_up_axis]
+    ///
+    /// # Example
+    /// ```
+    /// // assets exported from Blender
+    /// world.set_up_axis(UpAxis::Z);
+    /// ```
+    pub fn set_up_axis(&mut self, axis: UpAxis) {
+        set_up_axis(axis);
+    }
+
+    /// Gets which axis points up in the world
+    pub fn up_axis(&self) -> UpAxis {
+        up_axis()
+    }
+
+    /// Update the world then advance the timers of [World::scheduler] by the delta time, or by
+    /// the step with a [Enviroment::fixed_timestep], and call the ones that are due
     pub fn update(&mut self) {
+        crate::profile!("update");
         self.objects.update()(self);
+        let delta_time = self.env.fixed_timestep.unwrap_or(self.env.delta_time());
+        self.tick_scheduler(delta_time);
+    }
+
+    /// Advances the timers of [World::scheduler] by delta_time seconds and calls the ones that
+    /// are due, it is called by [World::update]
+    ///
+    /// The timers can add and cancel timers while they are called
+    pub fn tick_scheduler(&mut self, delta_time: f32) {
+        let mut timers = self.scheduler.detach();
+        timers.tick(delta_time, self);
+        self.scheduler.reattach(timers);
+    }
+
+    /// Draws a mesh with its own shader program, or the program of the enviroment if it dosen't
+    /// have one, it should be called from [GameObjectTrait::draw]
+    ///
+    /// glUseProgram is only called when the program changes and the first time a program is used
+    /// in a frame the view projection matrix of the camera is uploaded to its camera uniform, as
+    /// well as u_prev_camera_matrix if [Enviroment::motion_vectors] is on. The
+    /// [Mesh::material] is applied every draw so swapped textures take effect straight away, a
+    /// two sided material is drawn with culling off. A mesh with [Mesh::ranges] is drawn range by
+    /// range, each with its own material
+    ///
+    /// # Example
+    /// ```
+    /// fn draw(&self) -> fn(world: &World<GameObject>) {
+    ///     |world| {
+    ///         world.draw_mesh(&world.objects.lit_mesh);
+    ///         world.draw_mesh(&world.objects.ui_mesh);
+    ///     }
+    /// }
+    /// ```
+    pub fn draw_mesh<Vertex: VertexTrait + 'static + Sync + Send>(&self, mesh: &Mesh<Vertex>) {
+        let program = self.bind_mesh_program(mesh);
+        if mesh.ranges.is_empty() {
+            self.draw_with_material(&program, mesh.material.as_ref(), || mesh.draw());
+        }
+        for range in &mesh.ranges {
+            let material = range.material.as_ref().or(mesh.material.as_ref());
+            self.draw_with_material(&program, material, || {
+                mesh.draw_range(range.index_offset, range.index_count)
+            });
+        }
+    }
+
+    /// Draws a mesh like [World::draw_mesh] with the model matrix of transform in the mat4
+    /// u_model uniform of its program, then sets u_model back to the identity
+    ///
+    /// The matrix is cached by the transform so objects that didn't move reuse last frame's,
+    /// unlike [Mesh::update_mesh] the vertices aren't uploaded again when the object moves.
+    /// With a [Enviroment::fixed_timestep] the matrix is interpolated between the last two logic
+    /// steps by [Enviroment::alpha], see [GameObjectTrait::store_previous]. Programs without
+    /// u_model draw the mesh untransformed
+    ///
+    /// # Example
+    /// ```
+    /// fn draw(&self) -> fn(world: &World<GameObject>) {
+    ///     |world| world.draw_mesh_transformed(&world.objects.crate_mesh, &world.objects.crate_pos)
+    /// }
+    /// ```
+    pub fn draw_mesh_transformed<Vertex: VertexTrait + 'static + Sync + Send>(
+        &self,
+        mesh: &Mesh<Vertex>,
+        transform: &Transform,
+    ) {
+        let program = mesh.shader_program.unwrap_or(self.env.shader_program);
+        program.bind();
+        transform.upload_interpolated(&program, self.env.alpha);
+        self.draw_mesh(mesh);
+        if let Some(uniform) = Uniform::find(&program, "u_model") {
+            uniform.set_uniform_matrix(false, Mat4::identity().into());
+        }
+    }
+
+    /// Draws one instance of a mesh for every value of the instance buffers like
+    /// [World::draw_mesh], see [Mesh::draw_instanced]. [Mesh::ranges] are ignored
+    ///
+    /// # Example
+    /// ```
+    /// fn draw(&self) -> fn(world: &World<GameObject>) {
+    ///     |world| {
+    ///         let debris = &world.objects.debris;
+    ///         world.draw_mesh_instanced(&debris.mesh, &[&debris.colors, &debris.offsets]);
+    ///     }
+    /// }
+    /// ```
+    pub fn draw_mesh_instanced<Vertex: VertexTrait + 'static + Sync + Send>(
+        &self,
+        mesh: &Mesh<Vertex>,
+        instances: &[&InstanceBuffer],
+    ) {
+        let program = self.bind_mesh_program(mesh);
+        self.draw_with_material(&program, mesh.material.as_ref(), || {
+            mesh.draw_instanced(instances)
+        });
+    }
+
+    /// Binds the program of the mesh, or the program of the enviroment if it dosen't have one,
+    /// and uploads the camera uniforms the first time it is used in a frame
+    fn bind_mesh_program<Vertex: VertexTrait + 'static + Sync + Send>(
+        &self,
+        mesh: &Mesh<Vertex>,
+    ) -> ShaderProgram {
+        let program = mesh.shader_program.unwrap_or(self.env.shader_program);
+        program.bind();
+
+        let mut camera_programs = self.env.camera_programs.borrow_mut();
+        if !camera_programs.contains(&program.0) {
+            let camera = self.objects.get_camera();
+            if let Some(uniform) = Uniform::find(&program, &camera.get_camera_uniform()) {
+                uniform.set_uniform_matrix(false, self.env.view_projection().into());
+            }
+            if self.env.motion_vectors {
+                if let Some(uniform) = Uniform::find(&program, "u_prev_camera_matrix") {
+                    uniform.set_uniform_matrix(false, self.env.prev_view_projection.into());
+                }
+            }
+            camera_programs.push(program.0);
+        }
+        program
+    }
+
+    /// Draws a mesh in the flat color of solid with the view projection matrix of the camera,
+    /// its material, textures and [Mesh::ranges] are ignored
+    ///
+    /// # Example
+    /// ```
+    /// fn draw(&self) -> fn(world: &World<GameObject>) {
+    ///     |world| {
+    ///         world.draw_mesh(&world.objects.pyramid.mesh);
+    ///         if world.objects.pyramid.selected {
+    ///             world.draw_mesh_solid(&world.objects.pyramid.mesh, &world.objects.highlight);
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn draw_mesh_solid<Vertex: VertexTrait + 'static + Sync + Send>(
+        &self,
+        mesh: &Mesh<Vertex>,
+        solid: &SolidColor,
+    ) {
+        solid.draw(&self.env.view_projection(), || mesh.draw());
+    }
+
+    /// Applies the material if there is one then draws, a two sided material is drawn with culling
+    /// off
+    fn draw_with_material<F: FnOnce()>(
+        &self,
+        program: &ShaderProgram,
+        material: Option<&Material>,
+        draw: F,
+    ) {
+        let mut restore_culling = false;
+        if let Some(material) = material {
+            material.apply(program, self.objects.get_camera().get_pos());
+            if material.two_sided && is_enabled(GL_CULL_FACE) {
+                disable(GL_CULL_FACE);
+                restore_culling = true;
+            }
+        }
+        draw();
+        if restore_culling {
+            enable(GL_CULL_FACE);
+        }
+    }
+
+    /// Draws an object with [CustomDraw::custom_draw], or its mesh with [World::draw_mesh] if it
+    /// dosen't draw itself, it should be called from [GameObjectTrait::draw]
+    ///
+    /// # Example
+    /// ```
+    /// fn draw(&self) -> fn(world: &World<GameObject>) {
+    ///     |world| {
+    ///         world.draw_object(&world.objects.sparks, &world.objects.sparks.mesh);
+    ///         world.draw_object(&world.objects.pyramid, &world.objects.pyramid.mesh);
+    ///     }
+    /// }
+    /// ```
+    pub fn draw_object<Vertex: VertexTrait + 'static + Sync + Send>(
+        &self,
+        object: &dyn CustomDraw<GameObject>,
+        mesh: &Mesh<Vertex>,
+    ) {
+        if !object.custom_draw(self, self.objects.get_camera()) {
+            self.draw_mesh(mesh);
+        }
+    }
+
+    /// Adds a mesh to a [DrawQueue] so it is drawn with [World::draw_mesh] sorted by its program
+    /// and texture when the queue is flushed, texture is bound to GL_TEXTURE_2D of the active
+    /// texture unit
+    pub fn queue_mesh<'a, Vertex: VertexTrait + 'static + Sync + Send>(
+        &'a self,
+        queue: &mut DrawQueue<'a>,
+        mesh: &'a Mesh<Vertex>,
+        texture: Option<&Texture>,
+    ) {
+        let program = mesh.shader_program.unwrap_or(self.env.shader_program);
+        let texture = texture.map_or(0, |texture| texture.id);
+        queue.push(program, texture, move || self.draw_mesh(mesh));
+    }
+
+    /// Draws the world again from the top down camera of the minimap into its rectangle, it
+    /// should be called after [World::render], e.g. in the closure of [World::run]
+    ///
+    /// The minimap only shows meshes drawn with [World::draw_mesh] since they get the camera
+    /// matrix from the enviroment. center is where the camera looks, usually the player
+    pub fn render_minimap(&mut self, minimap: &Minimap, center: Vec3) {
+        let (x, y, width, height) = minimap.rect;
+        let _viewport = ViewportGuard::new(x, y, width, height);
+        let _scissor = ScissorGuard::new(x, y, width, height);
+        let mut previous_clear_color = [0.0; 4];
+        unsafe { glGetFloatv(GL_COLOR_CLEAR_VALUE, previous_clear_color.as_mut_ptr()) };
+
+        let background = minimap.background;
+        clear_color(background.x, background.y, background.z, background.w);
+        clear(GL_COLOR_BUFFER_BIT | GL_DEPTH_BUFFER_BIT);
+
+        let view_projection = self.env.view_projection;
+        self.env.view_projection = Some(minimap.view_projection(center));
+        self.env.camera_programs.borrow_mut().clear();
+        self.objects.draw()(self);
+        // the programs have the minimap camera now so they need the real one again
+        self.env.camera_programs.borrow_mut().clear();
+        self.env.view_projection = view_projection;
+
+        if minimap.marker_color.w > 0.0 {
+            let (x, y, width, height) = minimap.marker_rect();
+            let marker = minimap.marker_color;
+            scissor(x, y, width, height);
+            clear_color(marker.x, marker.y, marker.z, marker.w);
+            clear(GL_COLOR_BUFFER_BIT);
+        }
+
+        let [r, g, b, a] = previous_clear_color;
+        clear_color(r, g, b, a);
+        VertexArray::clear_binding();
+    }
+
+    /// Clears and draws the world like [World::render] but only into a rectangle of the window,
+    /// e.g. a viewport panel of an editor. Everything outside of the rectangle is left as it was
+    ///
+    /// The viewport is set to the rectangle and drawing is clipped to it with the scissor test,
+    /// both are restored afterwards. rect is (x, y, width, height) from the bottom left, the
+    /// camera should have the aspect ratio of the rectangle
+    ///
+    /// # Example
+    /// ```
+    /// draw_editor_ui();
+    /// world.render_to_region((300, 0, 800, 600));
+    /// world.env.present();
+    /// ```
+    pub fn render_to_region(&mut self, rect: Rect) {
+        let (x, y, width, height) = rect;
+        let _viewport = ViewportGuard::new(x, y, width, height);
+        let _scissor = ScissorGuard::new(x, y, width, height);
+        self.render();
+    }
+
+    /// Clears the screen and draws the world
+    ///
+    /// If [Enviroment::depth_prepass] is set the world is first drawn with color writes masked
+    /// then drawn again with the depth func set to GL_EQUAL and depth writes off
+    ///
+    /// If [Enviroment::keep_depth_texture] is set the world is drawn offscreen and copied to the
+    /// window so [Enviroment::depth_texture] can be sampled afterwards
+    ///
+    /// If [Enviroment::show_depth] is set the depth is drawn over the window in grayscale with
+    /// the near and far planes of the camera
+    pub fn render(&mut self) {
+        crate::profile!("render");
+        self.env.next_frame();
+        self.env.camera_programs.borrow_mut().clear();
+        let view_projection = self.camera_view_projection();
+        self.env.prev_view_projection = previous_matrix(self.env.view_projection, view_projection);
+        self.env.view_projection = Some(view_projection);
+        // binding the scene and copying it to the window set the viewport to the whole window
+        let (x, y, width, height) = get_viewport();
+        // showing the depth needs the depth texture but only for the frames it is shown
+        let offscreen = self.env.keep_depth_texture || self.env.show_depth;
+        if offscreen {
+            self.env.bind_scene();
+            viewport(x, y, width, height);
+        }
+        clear(GL_COLOR_BUFFER_BIT | GL_DEPTH_BUFFER_BIT);
+
+        for pass in frame_passes(self.env.depth_prepass) {
+            pass.apply();
+            self.objects.draw()(self);
+        }
+
+        // glClear respects the depth mask so it has to be turned back on for the next frame
+        frame_passes(false)[0].apply();
+        VertexArray::clear_binding();
+
+        if offscreen {
+            if let Some(scene) = &self.env.scene {
+                scene.blit_to_default(self.env.win_size.x as u32, self.env.win_size.y as u32);
+                viewport(x, y, width, height);
+            }
+        }
+        if self.env.show_depth {
+            self.show_depth();
+        }
+    }
+
+    /// The view projection matrix of the camera, it is only computed again when the camera moved
+    /// or its settings changed since the last frame
+    fn camera_view_projection(&mut self) -> Mat4 {
+        let camera = self.objects.get_camera();
+        let settings = camera.get_camera_settings();
+        let state = (
+            *camera.get_pos(),
+            *camera.get_rot(),
+            camera.get_up(),
+            settings.screen_size,
+            vec3(settings.fov, settings.near_plane, settings.far_plane),
+            settings.handedness,
+        );
+        match self.env.camera_cache {
+            Some((cached, matrix)) if cached == state => matrix,
+            _ => {
+                let matrix = camera.view_projection();
+                self.env.camera_cache = Some((state, matrix));
+                matrix
+            }
+        }
+    }
+
+    /// Draws the depth texture over the window for [Enviroment::show_depth]
+    fn show_depth(&mut self) {
+        if self.env.depth_view.is_none() {
+            match DepthView::new() {
+                Ok(depth_view) => self.env.depth_view = Some(depth_view),
+                Err(err) => {
+                    warn!("Couldn't make the depth view: {}", err);
+                    self.env.show_depth = false;
+                    return;
+                }
+            }
+        }
+        let settings = self.objects.get_camera().get_camera_settings();
+        if let (Some(scene), Some(depth_view)) = (&self.env.scene, &self.env.depth_view) {
+            depth_view.draw(scene, settings.near_plane, settings.far_plane);
+        }
+    }
+}
+
+impl<GameObject: GameObjectTrait> FrameLoop for World<GameObject> {
+    fn start(&mut self) {
+        self.env.running = true;
+        self.env.accumulator = 0.0;
+        self.env.last_step = self.env.clock.now();
+    }
+
+    fn is_running(&self) -> bool {
+        self.env.running
+    }
+
+    fn poll(&mut self) -> bool {
+        self.env.events = self.env.poll_events();
+        for event in &self.env.events {
+            self.env.window_state.update(event);
+        }
+        if self.env.events.iter().any(event::is_resize) {
+            self.env.resize_to_drawable();
+        }
+        self.env.update_input();
+        !should_quit(&self.env.events, self.env.quit_key_pressed())
+    }
+
+    fn is_active(&self) -> bool {
+        self.env.window_state.is_active()
+    }
+
+    fn background_mode(&self) -> BackgroundMode {
+        self.env.background_mode
+    }
+
+    fn wait_paused(&mut self) {
+        // sleep until something happens instead of spinning
+        unsafe { fermium::SDL_WaitEventTimeout(std::ptr::null_mut(), 100) };
+        self.env.clock.skip();
+        self.env.last_step = self.env.clock.now();
+    }
+
+    fn validate_state(&self) -> bool {
+        self.env.validate_state
+    }
+
+    fn step(&mut self) {
+        match self.env.fixed_timestep {
+            Some(step) => {
+                let now = self.env.clock.now();
+                self.env.accumulator += now.saturating_sub(self.env.last_step).as_secs_f32();
+                self.env.last_step = now;
+
+                let mut steps = 0;
+                while self.env.accumulator >= step && steps < MAX_STEPS_PER_FRAME {
+                    self.objects.store_previous();
+                    self.update();
+                    self.env.accumulator -= step;
+                    steps += 1;
+                }
+                // drop the time that couldn't be caught up so a slow frame dosen't spiral
+                self.env.accumulator = self.env.accumulator.min(step);
+                self.env.alpha = (self.env.accumulator / step).min(1.0);
+            }
+            None => {
+                self.objects.store_previous();
+                self.update();
+                self.env.alpha = 1.0;
+            }
+        }
+    }
+
+    fn render_frame(&mut self) {
+        self.render();
+    }
+
+    fn present_frame(&mut self) {
+        self.env.present();
+    }
+
+    fn end_frame(&mut self, active: bool, frame_start: Instant) {
+        profiler::end_frame();
+
+        let throttle = match (active, self.env.background_mode) {
+            (false, BackgroundMode::Throttle(fps)) => Some(fps),
+            _ => None,
+        };
+        if let Some(fps) = throttle.into_iter().chain(self.env.frame_cap_fps()).min() {
+            let frame_time = Duration::from_secs_f32(1.0 / fps.max(1) as f32);
+            if let Some(remaining) = frame_time.checked_sub(frame_start.elapsed()) {
+                thread::sleep(remaining);
+            }
+        }
+    }
+
+    fn finish(&mut self) {
+        self.env.events.clear();
+        self.env.running = false;
+
+        if let Some(mut on_quit) = self.env.on_quit.take() {
+            on_quit(&mut self.env);
+            // keep it in case the loop is run again
+            if self.env.on_quit.is_none() {
+                self.env.on_quit = Some(on_quit);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Runs the frame loop without a window, it writes down what the loop did
+    struct FakeLoop {
+        /// How many more frames poll lets through
+        frames: u32,
+        /// The frame the quit key is pressed on
+        quit_frame: Option<u32>,
+        input: InputState,
+        running: bool,
+        window_state: WindowState,
+        background_mode: BackgroundMode,
+        log: Vec<&'static str>,
+    }
+
+    impl FakeLoop {
+        fn new(frames: u32) -> Self {
+            FakeLoop {
+                frames,
+                quit_frame: None,
+                input: InputState::new(),
+                running: false,
+                window_state: WindowState::default(),
+                background_mode: BackgroundMode::Full,
+                log: Vec::new(),
+            }
+        }
+
+        fn count(&self, entry: &str) -> usize {
+            self.log.iter().filter(|logged| **logged == entry).count()
+        }
+    }
+
+    impl FrameLoop for FakeLoop {
+        fn start(&mut self) {
+            self.running = true;
+        }
+
+        fn is_running(&self) -> bool {
+            self.running
+        }
+
+        fn poll(&mut self) -> bool {
+            if self.frames == 0 {
+                return false;
+            }
+            self.frames -= 1;
+
+            let frame = self.count("present") as u32;
+            let keys = match self.quit_frame {
+                Some(quit_frame) if quit_frame == frame => vec![Keycode::Escape],
+                _ => Vec::new(),
+            };
+            let mouse = device_query::MouseState {
+                coords: (0, 0),
+                button_pressed: vec![false; 4],
+            };
+            self.input.update(keys, &mouse);
+            let quit_key = quit_key_down(Some(Keycode::Escape), self.window_state, &self.input);
+            !should_quit(&[], quit_key)
+        }
+
+        fn is_active(&self) -> bool {
+            self.window_state.is_active()
+        }
+
+        fn background_mode(&self) -> BackgroundMode {
+            self.background_mode
+        }
+
+        fn wait_paused(&mut self) {
+            self.log.push("wait_paused");
+        }
+
+        fn validate_state(&self) -> bool {
+            false
+        }
+
+        fn step(&mut self) {
+            self.log.push("step");
+        }
+
+        fn render_frame(&mut self) {
+            self.log.push("render");
+        }
+
+        fn present_frame(&mut self) {
+            self.log.push("present");
+        }
+
+        fn end_frame(&mut self, _: bool, _: Instant) {}
+
+        fn finish(&mut self) {
+            self.running = false;
+            self.log.push("finish");
+        }
+    }
+
+    fn window_event(event: beryllium::WindowEventEnum) -> EngineEvent {
+        EngineEvent::Sdl(Event::Window(beryllium::WindowEvent {
+            timestamp: 0,
+            window_id: 1,
+            event,
+        }))
+    }
+
+    #[test]
+    fn the_loop_pauses_while_the_window_is_unfocused() {
+        let mut host = FakeLoop::new(2);
+        host.background_mode = BackgroundMode::Pause;
+        host.window_state
+            .update(&window_event(beryllium::WindowEventEnum::KeyboardFocusLost));
+        run_frames(&mut host, |_, _| ());
+        assert_eq!(host.count("wait_paused"), 2);
+        assert_eq!(host.count("step") + host.count("render"), 0);
+
+        // throttling keeps running in the background
+        let mut host = FakeLoop::new(2);
+        host.background_mode = BackgroundMode::Throttle(10);
+        host.window_state
+            .update(&window_event(beryllium::WindowEventEnum::Minimized));
+        run_frames(&mut host, |_, _| ());
+        assert_eq!(host.count("step"), 2);
+
+        // focusing the window again resumes it
+        let mut host = FakeLoop::new(1);
+        host.background_mode = BackgroundMode::Pause;
+        host.window_state
+            .update(&window_event(beryllium::WindowEventEnum::KeyboardFocusLost));
+        host.window_state.update(&window_event(
+            beryllium::WindowEventEnum::KeyboardFocusGained,
+        ));
+        run_frames(&mut host, |_, _| ());
+        assert_eq!(host.count("step"), 1);
+    }
+
+    #[test]
+    fn the_quit_key_ends_the_loop_once() {
+        let mut host = FakeLoop::new(10);
+        host.quit_frame = Some(2);
+        run_frames(&mut host, |_, _| ());
+        assert_eq!(host.count("present"), 2);
+        assert_eq!(host.count("finish"), 1);
+
+        // the quit key is ignored while the window isn't focused
+        let mut input = InputState::new();
+        let mouse = device_query::MouseState {
+            coords: (0, 0),
+            button_pressed: vec![false; 4],
+        };
+        input.update(vec![Keycode::Escape], &mouse);
+        let unfocused = WindowState {
+            focused: false,
+            minimized: false,
+        };
+        assert!(quit_key_down(
+            Some(Keycode::Escape),
+            WindowState::default(),
+            &input
+        ));
+        assert!(!quit_key_down(Some(Keycode::Escape), unfocused, &input));
+        assert!(!quit_key_down(None, WindowState::default(), &input));
+
+        let quit = EngineEvent::Sdl(Event::Quit(beryllium::QuitEvent { timestamp: 0 }));
+        assert!(should_quit(&[quit], false));
+        assert!(!should_quit(&[], false));
+    }
+
+    #[test]
+    fn the_loop_runs_until_poll_stops_it() {
+        let mut host = FakeLoop::new(3);
+        run_frames(&mut host, |_, _| ());
+        assert_eq!(host.count("step"), 3);
+        assert_eq!(host.count("render"), 3);
+        assert_eq!(host.count("present"), 3);
+        assert_eq!(host.log.last(), Some(&"finish"));
+        assert!(!host.running);
+    }
+
+    #[test]
+    fn the_phases_are_called_in_order_around_the_frame() {
+        let mut host = FakeLoop::new(2);
+        run_frames(&mut host, |host, phase| {
+            host.log.push(match phase {
+                Phase::PreUpdate => "pre_update",
+                Phase::Update => "update",
+                Phase::PreRender => "pre_render",
+                Phase::PostRender => "post_render",
+            })
+        });
+        let frame = [
+            "pre_update",
+            "step",
+            "update",
+            "pre_render",
+            "render",
+            "post_render",
+            "present",
+        ];
+        assert_eq!(host.log, [&frame[..], &frame, &["finish"]].concat());
+    }
+
+    #[test]
+    fn the_refresh_rate_is_read_from_the_display_mode() {
+        let mut mode: fermium::SDL_DisplayMode = unsafe { std::mem::zeroed() };
+        assert_eq!(mode_refresh_rate(&mode), None);
+
+        mode.refresh_rate = 144;
+        assert_eq!(mode_refresh_rate(&mode), Some(144));
+    }
+
+    #[test]
+    fn match_refresh_caps_at_the_refresh_rate() {
+        assert_eq!(FrameCap::MatchRefresh.fps(Some(144)), Some(144));
+        assert_eq!(FrameCap::MatchRefresh.fps(None), None);
+        assert_eq!(FrameCap::Fps(30).fps(Some(144)), Some(30));
+        assert_eq!(FrameCap::Unlimited.fps(Some(144)), None);
+    }
+
+    #[test]
+    fn a_builder_with_only_a_window_uses_the_drawable_size() {
+        let mut builder = EnviromentBuilder::new();
+        assert_eq!(builder.win_size_or((1600, 1200)), vec2(1600.0, 1200.0));
+        assert!(builder.shader_program.is_none() && builder.device.is_none());
+        assert!(builder.mouse.is_none() && builder.msaa_samples.is_none());
+
+        builder.win_size(vec2(800.0, 600.0));
+        assert_eq!(builder.win_size_or((1600, 1200)), vec2(800.0, 600.0));
+    }
+
+    #[test]
+    #[should_panic(expected = "you can call .win")]
+    fn building_without_a_window_panics() {
+        EnviromentBuilder::new().build();
+    }
+
+    #[test]
+    fn the_previous_view_projection_is_last_frames() {
+        let first = Mat4::new_translation(&vec3(1.0, 0.0, 0.0));
+        let second = Mat4::new_translation(&vec3(2.0, 0.0, 0.0));
+        // nothing moved yet on the first frame
+        assert_eq!(previous_matrix(None, first), first);
+        assert_eq!(previous_matrix(Some(first), second), first);
+    }
+
+    #[test]
+    fn the_main_pass_after_a_depth_prepass_only_tests_for_equal_depth() {
+        let passes = frame_passes(true);
+        assert_eq!(passes.len(), 2);
+        assert!(!passes[0].color_write);
+        assert!(passes[0].depth_write);
+        assert_eq!(passes[0].depth_func, GL_LESS);
+        assert!(passes[1].color_write);
+        assert!(!passes[1].depth_write);
+        assert_eq!(passes[1].depth_func, GL_EQUAL);
+    }
+
+    #[test]
+    fn without_a_prepass_the_world_is_drawn_once_writing_depth() {
+        let passes = frame_passes(false);
+        assert_eq!(passes.len(), 1);
+        assert!(passes[0].color_write && passes[0].depth_write);
+        assert_eq!(passes[0].depth_func, GL_LESS);
     }
 }
@@ -14,30 +14,87 @@
 
 #![deny(missing_docs)]
 
+/// Module containing all things related to [self::Bloom]
+pub mod bloom;
 /// Module containing all things related to [self::Buffer]
 pub mod buffer;
+/// Module containing all things related to [self::GlCapabilities]
+pub mod capabilities;
+/// Module containing all things related to [self::capture_frame]
+pub mod capture;
+/// Module containing all things related to [self::CompressedImage]
+pub mod compressed;
+/// Module containing all things related to [self::Crosshair]
+pub mod crosshair;
+/// Module containing all things related to [self::Cubemap]
+pub mod cubemap;
+/// Module containing all things related to [self::Frustum] and [self::OcclusionQuery]
+pub mod culling;
+/// Module containing all things related to [self::DebugDraw]
+pub mod debug;
+/// Module containing all things related to [self::DrawQueue]
+pub mod draw_queue;
+/// Module containing all things related to [self::load_extensions_with]
+pub mod extension;
+/// Module containing all things related to [self::SdfFont]
+pub mod font;
+/// Module containing all things related to [self::Framebuffer]
+pub mod framebuffer;
+/// Module containing all things related to [self::fullscreen_pass]
+pub mod fullscreen;
+/// Module containing all things related to [self::DrawIndirectBuffer]
+pub mod indirect;
+/// Module containing all things related to [self::Light]
+pub mod light;
+/// Module containing all things related to [self::Material]
+pub mod material;
+/// Module containing all things related to [self::texture_memory_bytes]
+pub mod memory;
 /// Module containing all things related to [self::MultiSingularNumber]
 pub mod number;
+/// Module containing all things related to [self::Outline]
+pub mod outline;
+/// Module containing all things related to [self::Palette] and [self::IndexedTexture]
+pub mod palette;
+/// Module containing all things related to [self::RenderGraph]
+pub mod render_graph;
 /// Module containing all things related to [self::Shader]
 pub mod shader;
+/// Module containing all things related to [self::CascadedShadowMap]
+pub mod shadow;
+/// Module containing all things related to [self::SolidColor]
+pub mod solid_color;
+/// Module containing all things related to [self::SpatialGrid]
+pub mod spatial;
+/// Module containing all things related to [self::StateGuard]
+pub mod state;
+/// Module containing all things related to [self::Fence]
+pub mod sync;
 /// Module containing all things related to [self::Texture]
 pub mod texture;
 /// Module containing all things related to [self::Uniform]
 pub mod uniform;
 /// Module containing all things related to [self::VertexArray]
 pub mod vertex;
+/// Module containing all things related to [self::SharedWindow]
+pub mod window;
 
 // imports
 pub use beryllium::*;
 pub use image::DynamicImage::{self, *};
+use log::error;
 pub use ogl33::*;
-use std::ffi::CString;
+use std::ffi::{CStr, CString};
+use std::sync::atomic::{AtomicBool, Ordering};
 use texture::TextureError;
+use uniform::UniformError;
 
 /// This is an error enum, It contains several more specific enums in it as well as a misc error
 pub enum Error {
     /// This is a texture error, it is used by [Texture]
     TextureError(TextureError),
+    /// This is a uniform error, it is used by [Uniform]
+    UniformError(UniformError),
     /// For all other error that do not fit
     Misc(String),
 }
@@ -81,3 +138,388 @@ pub fn clear_color(r: f32, g: f32, b: f32, a: f32) {
 pub fn enable(cap: u32) {
     unsafe { glEnable(cap) }
 }
+
+/// glDisable disables various capabilities
+pub fn disable(cap: u32) {
+    unsafe { glDisable(cap) }
+}
+
+/// glIsEnabled checks if a capability like GL_BLEND is on
+pub fn is_enabled(cap: u32) -> bool {
+    unsafe { glIsEnabled(cap) == GL_TRUE }
+}
+
+/// A safe version of glClear, mask is a bitwise or of GL_COLOR_BUFFER_BIT, GL_DEPTH_BUFFER_BIT
+/// and GL_STENCIL_BUFFER_BIT
+pub fn clear(mask: u32) {
+    unsafe { glClear(mask) }
+}
+
+/// A safe version of glDepthFunc, func is something like GL_LESS or GL_EQUAL
+pub fn depth_func(func: u32) {
+    unsafe { glDepthFunc(func) }
+}
+
+/// A safe version of glClearDepth, sets the value the depth buffer is cleared to
+pub fn clear_depth(depth: f32) {
+    unsafe { glClearDepth(depth.into()) }
+}
+
+/// A safe version of glDepthRange, maps the depth from normalized device coordinates to near..far
+pub fn depth_range(near: f32, far: f32) {
+    unsafe { glDepthRange(near.into(), far.into()) }
+}
+
+/// A safe version of glViewport, sets the rectangle of the window that is drawn to
+pub fn viewport(x: i32, y: i32, width: i32, height: i32) {
+    unsafe { glViewport(x, y, width, height) }
+}
+
+/// Gets the current viewport as (x, y, width, height)
+pub fn get_viewport() -> (i32, i32, i32, i32) {
+    let mut out = [0; 4];
+    unsafe { glGetIntegerv(GL_VIEWPORT, out.as_mut_ptr()) };
+    (out[0], out[1], out[2], out[3])
+}
+
+/// Sets the viewport and restores the previous one when it is dropped
+///
+/// # Example
+/// ```
+/// draw_level();
+/// {
+///     let _minimap = ViewportGuard::new(10, 10, 200, 200);
+///     draw_minimap();
+/// } // the viewport is the whole window again
+/// ```
+#[must_use = "the previous viewport is restored as soon as the guard is dropped"]
+pub struct ViewportGuard {
+    previous: (i32, i32, i32, i32),
+}
+
+impl ViewportGuard {
+    /// Remembers the current viewport then sets the new one
+    pub fn new(x: i32, y: i32, width: i32, height: i32) -> Self {
+        let previous = get_viewport();
+        viewport(x, y, width, height);
+        ViewportGuard { previous }
+    }
+
+    /// The viewport that will be restored as (x, y, width, height)
+    pub fn previous(&self) -> (i32, i32, i32, i32) {
+        self.previous
+    }
+}
+
+impl Drop for ViewportGuard {
+    fn drop(&mut self) {
+        let (x, y, width, height) = self.previous;
+        viewport(x, y, width, height);
+    }
+}
+
+/// A rectangle of the window in pixels as (x, y, width, height) from the bottom left, like
+/// [get_viewport] and [get_scissor] return
+pub type Rect = (i32, i32, i32, i32);
+
+/// A safe version of glScissor, sets the rectangle that drawing and clearing is clipped to when
+/// GL_SCISSOR_TEST is enabled
+pub fn scissor(x: i32, y: i32, width: i32, height: i32) {
+    unsafe { glScissor(x, y, width, height) }
+}
+
+/// Gets the current scissor rectangle as (x, y, width, height)
+pub fn get_scissor() -> Rect {
+    let mut out = [0; 4];
+    unsafe { glGetIntegerv(GL_SCISSOR_BOX, out.as_mut_ptr()) };
+    (out[0], out[1], out[2], out[3])
+}
+
+/// Enables GL_SCISSOR_TEST with a rectangle and restores the previous rectangle and whether the
+/// test was enabled when it is dropped
+///
+/// # Example
+/// ```
+/// {
+///     let _panel = ScissorGuard::new(10, 10, 200, 200);
+///     clear(GL_COLOR_BUFFER_BIT); // only clears the panel
+/// }
+/// ```
+#[must_use = "the previous scissor is restored as soon as the guard is dropped"]
+pub struct ScissorGuard {
+    previous: Rect,
+    was_enabled: bool,
+}
+
+impl ScissorGuard {
+    /// Remembers the current scissor then enables the test with the new rectangle
+    pub fn new(x: i32, y: i32, width: i32, height: i32) -> Self {
+        let guard = ScissorGuard {
+            previous: get_scissor(),
+            was_enabled: is_enabled(GL_SCISSOR_TEST),
+        };
+        enable(GL_SCISSOR_TEST);
+        scissor(x, y, width, height);
+        guard
+    }
+}
+
+impl Drop for ScissorGuard {
+    fn drop(&mut self) {
+        let (x, y, width, height) = self.previous;
+        scissor(x, y, width, height);
+        if !self.was_enabled {
+            disable(GL_SCISSOR_TEST);
+        }
+    }
+}
+
+/// A safe version of glDepthMask, enables or disables writing into the depth buffer
+pub fn depth_mask(flag: bool) {
+    unsafe { glDepthMask(flag as u8) }
+}
+
+/// A safe version of glColorMask, enables or disables writing of the color components
+pub fn color_mask(r: bool, g: bool, b: bool, a: bool) {
+    unsafe { glColorMask(r as u8, g as u8, b as u8, a as u8) }
+}
+
+/// The winding order of the vertices of a front facing triangle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Winding {
+    /// Triangles wound clockwise are front facing
+    Clockwise = GL_CW as isize,
+    /// Triangles wound counter clockwise are front facing, this is the opengl default
+    CounterClockwise = GL_CCW as isize,
+}
+
+/// A safe version of glFrontFace, sets which [Winding] is front facing
+pub fn front_face(winding: Winding) {
+    unsafe { glFrontFace(winding as u32) }
+}
+
+/// How the triangles are rasterized, see [polygon_mode]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolygonMode {
+    /// The triangles are filled, this is the opengl default
+    Fill = GL_FILL as isize,
+    /// Only the edges are drawn, i.e. wireframe
+    Line = GL_LINE as isize,
+    /// Only the vertices are drawn
+    Point = GL_POINT as isize,
+}
+
+/// A safe version of glPolygonMode, sets the [PolygonMode] of both front and back faces
+pub fn polygon_mode(mode: PolygonMode) {
+    unsafe { glPolygonMode(GL_FRONT_AND_BACK, mode as u32) }
+}
+
+/// Gets the current [PolygonMode] of the front faces
+pub fn get_polygon_mode() -> PolygonMode {
+    // some drivers still return the front and back mode
+    let mut modes = [GL_FILL as i32; 2];
+    unsafe { glGetIntegerv(GL_POLYGON_MODE, modes.as_mut_ptr()) };
+    match modes[0] as u32 {
+        GL_LINE => PolygonMode::Line,
+        GL_POINT => PolygonMode::Point,
+        _ => PolygonMode::Fill,
+    }
+}
+
+/// How the color of what is drawn is combined with what is already on screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlendMode {
+    /// No blending, what is drawn replaces what is on screen
+    Opaque,
+    /// Standard alpha blending for straight alpha, SRC_ALPHA, ONE_MINUS_SRC_ALPHA
+    Alpha,
+    /// Alpha blending for colors that are already multiplied by their alpha, ONE, ONE_MINUS_SRC_ALPHA
+    PremultipliedAlpha,
+    /// The colors are added together, ONE, ONE
+    Additive,
+}
+
+/// Enables blending and sets the blend function for the [BlendMode]
+pub fn set_blend_mode(mode: BlendMode) {
+    let (src, dst) = match mode {
+        BlendMode::Opaque => {
+            disable(GL_BLEND);
+            return;
+        }
+        BlendMode::Alpha => (GL_SRC_ALPHA, GL_ONE_MINUS_SRC_ALPHA),
+        BlendMode::PremultipliedAlpha => (GL_ONE, GL_ONE_MINUS_SRC_ALPHA),
+        BlendMode::Additive => (GL_ONE, GL_ONE),
+    };
+    enable(GL_BLEND);
+    unsafe { glBlendFunc(src, dst) }
+}
+
+/// A safe version of glStencilFunc, func is something like GL_ALWAYS or GL_NOTEQUAL
+pub fn stencil_func(func: u32, reference: i32, mask: u32) {
+    unsafe { glStencilFunc(func, reference, mask) }
+}
+
+/// A safe version of glStencilOp, sets what happens to the stencil buffer when the stencil test
+/// fails, the depth test fails and when both pass
+pub fn stencil_op(stencil_fail: u32, depth_fail: u32, pass: u32) {
+    unsafe { glStencilOp(stencil_fail, depth_fail, pass) }
+}
+
+/// A safe version of glStencilMask, controls which bits of the stencil buffer are written
+pub fn stencil_mask(mask: u32) {
+    unsafe { glStencilMask(mask) }
+}
+
+/// A safe version of glGetError, returns GL_NO_ERROR if there is no error
+pub fn get_error() -> u32 {
+    unsafe { glGetError() }
+}
+
+/// If [gl_check] checks for errors in debug builds
+static DEBUG_GL: AtomicBool = AtomicBool::new(true);
+
+/// Turns the checks of [gl_check] on or off in debug builds, they are on by default. Release
+/// builds never check since gl_check compiles to just the call there
+pub fn set_debug_gl(on: bool) {
+    DEBUG_GL.store(on, Ordering::Relaxed);
+}
+
+/// Checks if [gl_check] checks for errors, it never does in release builds
+pub fn debug_gl() -> bool {
+    cfg!(debug_assertions) && DEBUG_GL.load(Ordering::Relaxed)
+}
+
+/// The name of an error from glGetError e.g. "GL_INVALID_ENUM"
+pub fn error_name(error: u32) -> &'static str {
+    match error {
+        GL_NO_ERROR => "GL_NO_ERROR",
+        GL_INVALID_ENUM => "GL_INVALID_ENUM",
+        GL_INVALID_VALUE => "GL_INVALID_VALUE",
+        GL_INVALID_OPERATION => "GL_INVALID_OPERATION",
+        GL_INVALID_FRAMEBUFFER_OPERATION => "GL_INVALID_FRAMEBUFFER_OPERATION",
+        GL_OUT_OF_MEMORY => "GL_OUT_OF_MEMORY",
+        _ => "unknown GL error",
+    }
+}
+
+/// Reads every pending GL error and logs them with the call and where it was made, returns the
+/// first one or None if there was no error. It is used by [gl_check]
+///
+/// # Example
+/// ```
+/// unsafe { glEnable(0xFFFF) };
+/// assert_eq!(check_gl_error("glEnable(0xFFFF)", "main.rs:2"), Some(GL_INVALID_ENUM));
+/// ```
+pub fn check_gl_error(call: &str, location: &str) -> Option<u32> {
+    drain_errors(get_error, call, location)
+}
+
+/// [check_gl_error] with the errors read from next_error instead of glGetError
+fn drain_errors(mut next_error: impl FnMut() -> u32, call: &str, location: &str) -> Option<u32> {
+    let mut first = None;
+    // a context without errors returns GL_NO_ERROR, the limit stops a lost context spinning
+    for _ in 0..16 {
+        let error = next_error();
+        if error == GL_NO_ERROR {
+            break;
+        }
+        error!("{} after {} at {}", error_name(error), call, location);
+        first.get_or_insert(error);
+    }
+    first
+}
+
+#[macro_export]
+/// Runs a GL call then panics with the name of the error and where the call is if it made a GL
+/// error, it evaluates to what the call returns
+///
+/// Errors left by earlier calls are logged and cleared before the call so they aren't blamed on
+/// it. It only checks in debug builds while [debug_gl] is on, in release builds it compiles to
+/// just the call
+///
+/// # Example
+/// ```
+/// gl_check!(unsafe { glBufferData(GL_ARRAY_BUFFER, size, data, GL_STATIC_DRAW) });
+/// ```
+macro_rules! gl_check {
+    ($call: expr) => {{
+        #[cfg(debug_assertions)]
+        let checking = $crate::graphics::debug_gl();
+        #[cfg(debug_assertions)]
+        let location = concat!(file!(), ":", line!());
+        #[cfg(debug_assertions)]
+        if checking {
+            $crate::graphics::check_gl_error(
+                concat!("the calls before ", stringify!($call)),
+                location,
+            );
+        }
+        let out = $call;
+        #[cfg(debug_assertions)]
+        if checking {
+            if let Some(error) = $crate::graphics::check_gl_error(stringify!($call), location) {
+                panic!(
+                    "{} after {} at {}",
+                    $crate::graphics::error_name(error),
+                    stringify!($call),
+                    location
+                );
+            }
+        }
+        out
+    }};
+}
+
+/// A safe version of glGetString, name is something like GL_VERSION or GL_RENDERER
+///
+/// Returns an empty string if there is no current context
+pub fn get_string(name: u32) -> String {
+    let ptr = unsafe { glGetString(name) };
+    if ptr.is_null() {
+        String::new()
+    } else {
+        unsafe { CStr::from_ptr(ptr.cast()) }
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_gl_error_has_its_name() {
+        assert_eq!(error_name(GL_NO_ERROR), "GL_NO_ERROR");
+        assert_eq!(error_name(GL_INVALID_ENUM), "GL_INVALID_ENUM");
+        assert_eq!(error_name(GL_INVALID_VALUE), "GL_INVALID_VALUE");
+        assert_eq!(error_name(GL_INVALID_OPERATION), "GL_INVALID_OPERATION");
+        assert_eq!(
+            error_name(GL_INVALID_FRAMEBUFFER_OPERATION),
+            "GL_INVALID_FRAMEBUFFER_OPERATION"
+        );
+        assert_eq!(error_name(GL_OUT_OF_MEMORY), "GL_OUT_OF_MEMORY");
+        assert_eq!(error_name(0xFFFF), "unknown GL error");
+    }
+
+    #[test]
+    fn an_invalid_enum_is_reported_and_every_error_is_cleared() {
+        let mut pending = vec![GL_INVALID_VALUE, GL_INVALID_ENUM];
+        let next_error = || pending.pop().unwrap_or(GL_NO_ERROR);
+        assert_eq!(
+            drain_errors(next_error, "glEnable(0xFFFF)", "main.rs:2"),
+            Some(GL_INVALID_ENUM)
+        );
+        assert!(pending.is_empty());
+
+        assert_eq!(
+            drain_errors(|| GL_NO_ERROR, "glEnable(GL_BLEND)", "main.rs:3"),
+            None
+        );
+        // a lost context keeps returning an error, it must not hang
+        assert_eq!(
+            drain_errors(|| GL_OUT_OF_MEMORY, "glClear(0)", "main.rs:4"),
+            Some(GL_OUT_OF_MEMORY)
+        );
+    }
+}
@@ -0,0 +1,178 @@
+use super::{
+    capabilities::get_integer, framebuffer::*, fullscreen::*, shader::*, state::StateGuard,
+    texture::Texture, uniform::*, *,
+};
+
+/// A bloom post-process effect, light brighter than a threshold bleeds into the pixels around it
+///
+/// The bright parts of the scene are extracted into a half size texture, which is downsampled
+/// and Gaussian blurred once per iteration. Every blurred level is then added onto the scene,
+/// so later iterations spread the light further. The scene should be rendered into
+/// [Framebuffer::hdr] so there is light above 1.0 to extract
+///
+/// # Example
+/// ```
+/// let scene = Framebuffer::hdr(800, 600)?;
+/// let mut bloom = Bloom::new(800, 600, 4)?;
+/// bloom.threshold = 1.0;
+///
+/// scene.bind();
+/// draw_scene();
+/// bloom.apply(&scene, None); // draws the scene with bloom to the window
+/// ```
+pub struct Bloom {
+    /// How bright a pixel has to be to bloom, the brightest channel is compared
+    pub threshold: f32,
+    /// How much of the blurred light is added onto the scene
+    pub intensity: f32,
+    width: u32,
+    height: u32,
+    /// Two framebuffers per iteration, the result is in the first one
+    levels: Vec<(Framebuffer, Framebuffer)>,
+    extract: ShaderProgram,
+    blur: ShaderProgram,
+    composite: ShaderProgram,
+    /// The threshold uniform of extract
+    threshold_uniform: Uniform,
+    /// The direction uniform of blur
+    direction_uniform: Uniform,
+    /// The intensity uniform of composite
+    intensity_uniform: Uniform,
+}
+
+impl Bloom {
+    /// Makes a bloom effect for a scene of width x height with iterations blur levels
+    ///
+    /// The threshold is 1.0 and the intensity is 1.0 by default
+    pub fn new(width: u32, height: u32, iterations: u32) -> Result<Self, String> {
+        let extract =
+            ShaderProgram::fullscreen(include_str!("../../shaders/bloom_extract_frag.glsl"))?;
+        let blur = ShaderProgram::fullscreen(include_str!("../../shaders/bloom_blur_frag.glsl"))?;
+        let composite =
+            ShaderProgram::fullscreen(include_str!("../../shaders/bloom_composite_frag.glsl"))?;
+        // every program samples the image from texture unit 0
+        for program in [&extract, &blur, &composite] {
+            program.use_program();
+            if let Some(uniform) = Uniform::find(program, "image") {
+                uniform.set_uniform_i(&[0]);
+            }
+        }
+
+        let mut bloom = Bloom {
+            threshold: 1.0,
+            intensity: 1.0,
+            width,
+            height,
+            levels: Vec::new(),
+            threshold_uniform: Uniform::new(&extract, "threshold"),
+            direction_uniform: Uniform::new(&blur, "direction"),
+            intensity_uniform: Uniform::new(&composite, "intensity"),
+            extract,
+            blur,
+            composite,
+        };
+        bloom.set_iterations(iterations)?;
+        Ok(bloom)
+    }
+
+    /// How many levels are blurred
+    pub fn iterations(&self) -> u32 {
+        self.levels.len() as u32
+    }
+
+    /// Changes how many levels are blurred, the framebuffers are made again
+    ///
+    /// Every level is half the size of the one before it, levels that would be smaller than a
+    /// pixel are skipped
+    pub fn set_iterations(&mut self, iterations: u32) -> Result<(), String> {
+        self.delete_levels();
+        for i in 1..=iterations {
+            let (width, height) = (self.width >> i, self.height >> i);
+            if width == 0 || height == 0 {
+                break;
+            }
+            self.levels.push((
+                Framebuffer::hdr(width, height)?,
+                Framebuffer::hdr(width, height)?,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Draws the color of scene with bloom into target, or the window if it is None
+    ///
+    /// The GL state it changes is restored after it draws, see [StateGuard], except that target
+    /// stays bound
+    pub fn apply(&self, scene: &Framebuffer, target: Option<&Framebuffer>) {
+        let guard = StateGuard::new();
+        disable(GL_DEPTH_TEST);
+        disable(GL_BLEND);
+        Texture::set_tex_unit(GL_TEXTURE0);
+
+        for (i, (level, temp)) in self.levels.iter().enumerate() {
+            level.bind();
+            if i == 0 {
+                // the first level only has the light above the threshold
+                self.extract.use_program();
+                self.threshold_uniform.set_uniform_f(&[self.threshold]);
+                draw_texture(&self.extract, &scene.color);
+            } else {
+                // downsample the previous level
+                self.composite.use_program();
+                self.intensity_uniform.set_uniform_f(&[1.0]);
+                draw_texture(&self.composite, &self.levels[i - 1].0.color);
+            }
+
+            self.blur.use_program();
+            let texel = (1.0 / level.width as f32, 1.0 / level.height as f32);
+            temp.bind();
+            self.direction_uniform.set_uniform_f(&[texel.0, 0.0]);
+            draw_texture(&self.blur, &level.color);
+            level.bind();
+            self.direction_uniform.set_uniform_f(&[0.0, texel.1]);
+            draw_texture(&self.blur, &temp.color);
+        }
+
+        match target {
+            Some(target) => target.bind(),
+            None => Framebuffer::bind_default(self.width, self.height),
+        }
+        self.composite.use_program();
+        self.intensity_uniform.set_uniform_f(&[1.0]);
+        draw_texture(&self.composite, &scene.color);
+
+        set_blend_mode(BlendMode::Additive);
+        self.intensity_uniform.set_uniform_f(&[self.intensity]);
+        for (level, _) in &self.levels {
+            draw_texture(&self.composite, &level.color);
+        }
+
+        let mut state = *guard.start();
+        state.framebuffer = get_integer(GL_DRAW_FRAMEBUFFER_BINDING) as u32;
+        state.viewport = get_viewport();
+        state.restore();
+    }
+
+    /// Deletes the framebuffers
+    fn delete_levels(&mut self) {
+        for (level, temp) in self.levels.drain(..) {
+            level.delete();
+            temp.delete();
+        }
+    }
+
+    /// Deletes the framebuffers and programs
+    pub fn delete(mut self) {
+        self.delete_levels();
+        self.extract.delete();
+        self.blur.delete();
+        self.composite.delete();
+    }
+}
+
+/// Draws texture over the whole viewport with program, its image sampler must read texture
+/// unit 0
+fn draw_texture(program: &ShaderProgram, texture: &Texture) {
+    unsafe { glBindTexture(GL_TEXTURE_2D, texture.id) };
+    fullscreen_pass(program);
+}
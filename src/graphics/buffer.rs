@@ -9,6 +9,18 @@ pub enum BufferType {
     ElementArray = GL_ELEMENT_ARRAY_BUFFER as isize,
 }
 
+/// A hint of how often the data of a [Buffer] changes, it is passed to [buffer_data]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BufferUsage {
+    /// The data is set once and drawn many times
+    #[default]
+    Static = GL_STATIC_DRAW as isize,
+    /// The data is changed often and drawn many times, e.g. every frame
+    Dynamic = GL_DYNAMIC_DRAW as isize,
+    /// The data is set once and drawn only a few times
+    Stream = GL_STREAM_DRAW as isize,
+}
+
 /// Implementation of [VBO](https://www.khronos.org/opengl/wiki/Vertex_Specification#Vertex_Buffer_Object)
 pub struct Buffer(pub u32);
 impl Buffer {
@@ -34,16 +46,125 @@ impl Buffer {
     pub fn clear_binding(ty: BufferType) {
         unsafe { glBindBuffer(ty as u32, 0) }
     }
+
+    /// Gets the size of the data of the buffer in bytes from opengl
+    ///
+    /// The buffer is bound to GL_COPY_READ_BUFFER so the array and element array bindings (which
+    /// are part of the VAO) don't change, the previous copy read binding is restored
+    pub fn size(&self) -> usize {
+        let (mut previous, mut size) = (0, 0);
+        unsafe {
+            // GL_COPY_READ_BUFFER_BINDING has the same value as GL_COPY_READ_BUFFER
+            glGetIntegerv(GL_COPY_READ_BUFFER, &mut previous);
+            glBindBuffer(GL_COPY_READ_BUFFER, self.0);
+            glGetBufferParameteriv(GL_COPY_READ_BUFFER, GL_BUFFER_SIZE, &mut size);
+            glBindBuffer(GL_COPY_READ_BUFFER, previous as u32);
+        }
+        size as usize
+    }
+
+    /// Deletes the buffer
+    pub fn delete(&self) {
+        unsafe { glDeleteBuffers(1, &self.0) }
+    }
 }
 
 /// Store the data in the buffer
 pub fn buffer_data(ty: BufferType, data: &[u8], usage: u32) {
-    unsafe {
+    crate::gl_check!(unsafe {
         glBufferData(
             ty as u32,
             data.len().try_into().unwrap(),
             data.as_ptr().cast(),
             usage,
-        );
+        )
+    });
+}
+
+/// Gets the size in bytes of the buffer bound to ty, it is 0 if nothing was stored in it
+pub fn buffer_capacity(ty: BufferType) -> usize {
+    let mut size = 0;
+    unsafe { glGetBufferParameteriv(ty as u32, GL_BUFFER_SIZE, &mut size) };
+    size.max(0) as usize
+}
+
+/// Stores the data in the buffer bound to ty but only reallocates it when the data dosen't fit,
+/// otherwise the data is written over the start of it with [buffer_sub_data]. Returns true if
+/// the buffer was reallocated
+///
+/// A buffer that has to grow is made growth times bigger, or as big as the data if that is
+/// bigger, so data that keeps growing is only reallocated a logarithmic number of times. A
+/// growth of 1.0 makes it exactly as big as the data
+///
+/// # Example
+/// ```
+/// // the buffer grows 10 -> 15 -> 23 bytes while the data grows 10 -> 11 -> ... -> 20
+/// buffer_data_growing(BufferType::Array, &data, BufferUsage::Dynamic as u32, 1.5);
+/// ```
+pub fn buffer_data_growing(ty: BufferType, data: &[u8], usage: u32, growth: f32) -> bool {
+    let Some(size) = grown_capacity(buffer_capacity(ty), data.len(), growth) else {
+        if !data.is_empty() {
+            buffer_sub_data(ty, 0, data);
+        }
+        return false;
+    };
+
+    crate::gl_check!(unsafe {
+        glBufferData(ty as u32, size.try_into().unwrap(), std::ptr::null(), usage)
+    });
+    if !data.is_empty() {
+        buffer_sub_data(ty, 0, data);
+    }
+    true
+}
+
+/// The size [buffer_data_growing] reallocates a buffer of capacity bytes to so that needed
+/// bytes fit, None if they already fit and it isn't reallocated
+fn grown_capacity(capacity: usize, needed: usize, growth: f32) -> Option<usize> {
+    if needed <= capacity && capacity > 0 {
+        return None;
+    }
+    let grown = (capacity as f32 * growth.max(1.0)).ceil() as usize;
+    Some(grown.max(needed))
+}
+
+/// Replaces part of the data of the buffer starting at offset bytes, the buffer must already be
+/// big enough, see [buffer_data]
+pub fn buffer_sub_data(ty: BufferType, offset: usize, data: &[u8]) {
+    crate::gl_check!(unsafe {
+        glBufferSubData(
+            ty as u32,
+            offset.try_into().unwrap(),
+            data.len().try_into().unwrap(),
+            data.as_ptr().cast(),
+        )
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buffers_grow_by_the_growth_factor() {
+        let mut capacity = 0;
+        let mut sizes = Vec::new();
+        for needed in 10..=20 {
+            if let Some(size) = grown_capacity(capacity, needed, 1.5) {
+                capacity = size;
+                sizes.push(size);
+            }
+        }
+        assert_eq!(sizes, vec![10, 15, 23]);
+    }
+
+    #[test]
+    fn data_bigger_than_the_growth_gets_its_own_size() {
+        assert_eq!(grown_capacity(10, 100, 1.5), Some(100));
+        assert_eq!(grown_capacity(10, 11, 1.0), Some(11));
+        // a growth below 1.0 can't shrink the buffer
+        assert_eq!(grown_capacity(10, 11, 0.5), Some(11));
+        assert_eq!(grown_capacity(10, 10, 2.0), None);
+        assert_eq!(grown_capacity(0, 0, 2.0), Some(0));
     }
 }
@@ -0,0 +1,160 @@
+use std::collections::HashSet;
+
+use super::{compressed::*, *};
+
+/// GL_MAX_TEXTURE_MAX_ANISOTROPY from GL_EXT_texture_filter_anisotropic, ogl33 dosen't have it
+pub const GL_MAX_TEXTURE_MAX_ANISOTROPY: u32 = 0x84FF;
+
+/// The limits of the driver and the extensions it supports
+///
+/// # Example
+/// ```
+/// let capabilities = GlCapabilities::query();
+///
+/// if capabilities.max_texture_size < 4096 {
+///     // load the smaller textures
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct GlCapabilities {
+    /// The largest width or height a texture can have
+    pub max_texture_size: i32,
+    /// How many texture units can be used at once by the fragment shader
+    pub max_texture_units: i32,
+    /// The largest size of a uniform block in bytes
+    pub max_uniform_block_size: i32,
+    /// How many vertex attributes a vertex can have
+    pub max_vertex_attribs: i32,
+    /// The most samples a multisampled framebuffer or renderbuffer can have
+    pub max_samples: i32,
+    /// The maximum anisotropy, it is 1.0 if anisotropic filtering isn't supported
+    pub max_anisotropy: f32,
+    /// The names of all the supported extensions e.g. "GL_ARB_multi_draw_indirect"
+    pub extensions: HashSet<String>,
+    /// The compressed internal formats that can be uploaded, see [compressed_formats]
+    pub compressed_formats: HashSet<u32>,
+}
+
+impl GlCapabilities {
+    /// Queries the capabilities of the current context
+    ///
+    /// NOTE: a context must be current, prefer [crate::ECS::world::Enviroment::capabilities] as it
+    /// only queries once
+    pub fn query() -> Self {
+        let extensions = extensions();
+
+        let max_anisotropy = if extensions.contains("GL_EXT_texture_filter_anisotropic")
+            || extensions.contains("GL_ARB_texture_filter_anisotropic")
+        {
+            get_float(GL_MAX_TEXTURE_MAX_ANISOTROPY)
+        } else {
+            1.0
+        };
+
+        GlCapabilities {
+            max_texture_size: get_integer(GL_MAX_TEXTURE_SIZE),
+            max_texture_units: get_integer(GL_MAX_TEXTURE_IMAGE_UNITS),
+            max_uniform_block_size: get_integer(GL_MAX_UNIFORM_BLOCK_SIZE),
+            max_vertex_attribs: get_integer(GL_MAX_VERTEX_ATTRIBS),
+            max_samples: get_integer(GL_MAX_SAMPLES),
+            max_anisotropy,
+            compressed_formats: compressed_formats_from(&extensions),
+            extensions,
+        }
+    }
+
+    /// Checks if an extension is supported e.g. "GL_ARB_multi_draw_indirect"
+    pub fn has_extension(&self, name: &str) -> bool {
+        self.extensions.contains(name)
+    }
+
+    /// Clamps a requested MSAA sample count to [GlCapabilities::max_samples]
+    pub fn clamp_samples(&self, requested: u8) -> u8 {
+        requested.min(self.max_samples.clamp(0, u8::MAX as i32) as u8)
+    }
+
+    /// Checks if textures with a compressed internal format can be uploaded e.g.
+    /// GL_COMPRESSED_RGBA_S3TC_DXT5_EXT
+    pub fn supports_compressed_format(&self, format: u32) -> bool {
+        self.compressed_formats.contains(&format)
+    }
+}
+
+/// Gets the names of all the supported extensions of the current context
+pub fn extensions() -> HashSet<String> {
+    (0..get_integer(GL_NUM_EXTENSIONS))
+        .filter_map(|i| {
+            let ptr = unsafe { glGetStringi(GL_EXTENSIONS, i as u32) };
+            if ptr.is_null() {
+                None
+            } else {
+                Some(
+                    unsafe { CStr::from_ptr(ptr.cast()) }
+                        .to_string_lossy()
+                        .into_owned(),
+                )
+            }
+        })
+        .collect()
+}
+
+/// Gets the compressed internal formats the current context can upload
+///
+/// Core profiles don't have to list every format in GL_COMPRESSED_TEXTURE_FORMATS so the formats
+/// of the compression extensions are added as well, RGTC is always supported since it is core
+pub fn compressed_formats() -> HashSet<u32> {
+    compressed_formats_from(&extensions())
+}
+
+fn compressed_formats_from(extensions: &HashSet<String>) -> HashSet<u32> {
+    let mut formats = vec![0; get_integer(GL_NUM_COMPRESSED_TEXTURE_FORMATS).max(0) as usize];
+    if !formats.is_empty() {
+        unsafe { glGetIntegerv(GL_COMPRESSED_TEXTURE_FORMATS, formats.as_mut_ptr()) };
+    }
+    let mut formats: HashSet<u32> = formats.into_iter().map(|format| format as u32).collect();
+
+    formats.extend([
+        GL_COMPRESSED_RED_RGTC1,
+        GL_COMPRESSED_SIGNED_RED_RGTC1,
+        GL_COMPRESSED_RG_RGTC2,
+        GL_COMPRESSED_SIGNED_RG_RGTC2,
+    ]);
+    if extensions.contains("GL_EXT_texture_compression_s3tc") {
+        formats.extend([
+            GL_COMPRESSED_RGB_S3TC_DXT1_EXT,
+            GL_COMPRESSED_RGBA_S3TC_DXT1_EXT,
+            GL_COMPRESSED_RGBA_S3TC_DXT3_EXT,
+            GL_COMPRESSED_RGBA_S3TC_DXT5_EXT,
+        ]);
+    }
+    if extensions.contains("GL_ARB_texture_compression_bptc") {
+        formats.extend([
+            GL_COMPRESSED_RGBA_BPTC_UNORM,
+            GL_COMPRESSED_SRGB_ALPHA_BPTC_UNORM,
+        ]);
+    }
+    if extensions.contains("GL_ARB_ES3_compatibility") {
+        formats.extend([
+            GL_COMPRESSED_RGB8_ETC2,
+            GL_COMPRESSED_SRGB8_ETC2,
+            GL_COMPRESSED_RGBA8_ETC2_EAC,
+            GL_COMPRESSED_SRGB8_ALPHA8_ETC2_EAC,
+        ]);
+    }
+
+    formats
+}
+
+/// A safe version of glGetIntegerv for parameters with a single value
+pub fn get_integer(pname: u32) -> i32 {
+    let mut out = 0;
+    unsafe { glGetIntegerv(pname, &mut out) };
+    out
+}
+
+/// A safe version of glGetFloatv for parameters with a single value
+pub fn get_float(pname: u32) -> f32 {
+    let mut out = 0.0;
+    unsafe { glGetFloatv(pname, &mut out) };
+    out
+}
@@ -0,0 +1,182 @@
+use super::{
+    capabilities::get_integer,
+    framebuffer::{Framebuffer, MultisampledFramebuffer},
+    *,
+};
+use image::{imageops::flip_vertical_in_place, RgbaImage};
+
+/// Reads the pixels of the framebuffer bound to GL_READ_FRAMEBUFFER inside the viewport into an
+/// 8 bit image with the top row first, e.g. for screenshots
+///
+/// A multisampled target is resolved into a temporary framebuffer first since multisampled
+/// pixels can't be read directly. A floating point (HDR) target is tone mapped with Reinhard
+/// (color / (1 + color)) so light above 1.0 isn't clipped
+///
+/// # Example
+/// ```
+/// world.render();
+/// capture_frame()?.save("screenshot.png").map_err(|err| err.to_string())?;
+/// world.env.present();
+/// ```
+pub fn capture_frame() -> Result<RgbaImage, String> {
+    let (x, y, width, height) = get_viewport();
+    if width <= 0 || height <= 0 {
+        return Err(format!("Can't capture a {}x{} viewport", width, height));
+    }
+
+    let source = get_integer(GL_READ_FRAMEBUFFER_BINDING) as u32;
+    let samples = get_integer(GL_SAMPLES);
+    let hdr = is_float_target(source);
+
+    let mut pixels = if samples > 0 {
+        resolve_and_read(source, (x, y, width, height), hdr)?
+    } else {
+        read_pixels(x, y, width, height, hdr)
+    };
+    unsafe { glBindFramebuffer(GL_READ_FRAMEBUFFER, source) };
+
+    flip_vertical_in_place(&mut pixels);
+    Ok(pixels)
+}
+
+impl Framebuffer {
+    /// Reads the color texture into an image, see [capture_frame]. The framebuffer is left bound
+    pub fn capture(&self) -> Result<RgbaImage, String> {
+        self.bind();
+        capture_frame()
+    }
+}
+
+impl MultisampledFramebuffer {
+    /// Resolves the color and reads it into an image, see [capture_frame]. The framebuffer is left
+    /// bound
+    pub fn capture(&self) -> Result<RgbaImage, String> {
+        self.bind();
+        capture_frame()
+    }
+}
+
+/// Checks if the color of a framebuffer is stored as floats
+fn is_float_target(framebuffer: u32) -> bool {
+    // the window calls its color buffer GL_BACK_LEFT instead of an attachment
+    let attachment = if framebuffer == 0 {
+        GL_BACK_LEFT
+    } else {
+        GL_COLOR_ATTACHMENT0
+    };
+    let mut component_type = 0;
+    unsafe {
+        glGetFramebufferAttachmentParameteriv(
+            GL_READ_FRAMEBUFFER,
+            attachment,
+            GL_FRAMEBUFFER_ATTACHMENT_COMPONENT_TYPE,
+            &mut component_type,
+        )
+    };
+    component_type as u32 == GL_FLOAT
+}
+
+/// Blits the multisampled read framebuffer into a temporary single sampled one and reads it
+fn resolve_and_read(
+    source: u32,
+    (x, y, width, height): (i32, i32, i32, i32),
+    hdr: bool,
+) -> Result<RgbaImage, String> {
+    let (mut framebuffer, mut renderbuffer) = (0, 0);
+    unsafe {
+        glGenFramebuffers(1, &mut framebuffer);
+        glGenRenderbuffers(1, &mut renderbuffer);
+        glBindRenderbuffer(GL_RENDERBUFFER, renderbuffer);
+        glRenderbufferStorage(
+            GL_RENDERBUFFER,
+            if hdr { GL_RGBA16F } else { GL_RGBA8 },
+            width,
+            height,
+        );
+        glBindRenderbuffer(GL_RENDERBUFFER, 0);
+
+        let draw_framebuffer = get_integer(GL_DRAW_FRAMEBUFFER_BINDING) as u32;
+        glBindFramebuffer(GL_DRAW_FRAMEBUFFER, framebuffer);
+        glFramebufferRenderbuffer(
+            GL_DRAW_FRAMEBUFFER,
+            GL_COLOR_ATTACHMENT0,
+            GL_RENDERBUFFER,
+            renderbuffer,
+        );
+        let complete = glCheckFramebufferStatus(GL_DRAW_FRAMEBUFFER) == GL_FRAMEBUFFER_COMPLETE;
+        if complete {
+            glBindFramebuffer(GL_READ_FRAMEBUFFER, source);
+            glBlitFramebuffer(
+                x,
+                y,
+                x + width,
+                y + height,
+                0,
+                0,
+                width,
+                height,
+                GL_COLOR_BUFFER_BIT,
+                GL_NEAREST,
+            );
+            glBindFramebuffer(GL_READ_FRAMEBUFFER, framebuffer);
+        }
+        glBindFramebuffer(GL_DRAW_FRAMEBUFFER, draw_framebuffer);
+
+        let pixels = complete.then(|| read_pixels(0, 0, width, height, hdr));
+        glDeleteFramebuffers(1, &framebuffer);
+        glDeleteRenderbuffers(1, &renderbuffer);
+        pixels.ok_or_else(|| {
+            "The framebuffer to resolve the capture into is not complete".to_string()
+        })
+    }
+}
+
+/// Reads the pixels of the read framebuffer, floats are tone mapped to 8 bits
+fn read_pixels(x: i32, y: i32, width: i32, height: i32, hdr: bool) -> RgbaImage {
+    let (w, h) = (width as u32, height as u32);
+    unsafe { glPixelStorei(GL_PACK_ALIGNMENT, 1) };
+    let image = if hdr {
+        let mut floats = vec![0.0f32; (w * h * 4) as usize];
+        unsafe {
+            glReadPixels(
+                x,
+                y,
+                width,
+                height,
+                GL_RGBA,
+                GL_FLOAT,
+                floats.as_mut_ptr().cast(),
+            )
+        };
+        let bytes = floats
+            .chunks_exact(4)
+            .flat_map(|pixel| {
+                let tone_map = |c: f32| (c.max(0.0) / (1.0 + c.max(0.0)) * 255.0).round() as u8;
+                [
+                    tone_map(pixel[0]),
+                    tone_map(pixel[1]),
+                    tone_map(pixel[2]),
+                    (pixel[3].clamp(0.0, 1.0) * 255.0).round() as u8,
+                ]
+            })
+            .collect();
+        RgbaImage::from_raw(w, h, bytes)
+    } else {
+        let mut bytes = vec![0u8; (w * h * 4) as usize];
+        unsafe {
+            glReadPixels(
+                x,
+                y,
+                width,
+                height,
+                GL_RGBA,
+                GL_UNSIGNED_BYTE,
+                bytes.as_mut_ptr().cast(),
+            )
+        };
+        RgbaImage::from_raw(w, h, bytes)
+    };
+    unsafe { glPixelStorei(GL_PACK_ALIGNMENT, 4) };
+    // the buffer is always width * height * 4 bytes
+    image.unwrap()
+}
@@ -0,0 +1,341 @@
+use std::fs;
+use std::path::Path;
+
+use super::{capabilities::compressed_formats, texture::*, *};
+
+/// GL_COMPRESSED_RGB_S3TC_DXT1_EXT from GL_EXT_texture_compression_s3tc (BC1 without alpha)
+pub const GL_COMPRESSED_RGB_S3TC_DXT1_EXT: u32 = 0x83F0;
+/// GL_COMPRESSED_RGBA_S3TC_DXT1_EXT from GL_EXT_texture_compression_s3tc (BC1)
+pub const GL_COMPRESSED_RGBA_S3TC_DXT1_EXT: u32 = 0x83F1;
+/// GL_COMPRESSED_RGBA_S3TC_DXT3_EXT from GL_EXT_texture_compression_s3tc (BC2)
+pub const GL_COMPRESSED_RGBA_S3TC_DXT3_EXT: u32 = 0x83F2;
+/// GL_COMPRESSED_RGBA_S3TC_DXT5_EXT from GL_EXT_texture_compression_s3tc (BC3)
+pub const GL_COMPRESSED_RGBA_S3TC_DXT5_EXT: u32 = 0x83F3;
+/// GL_COMPRESSED_RGBA_BPTC_UNORM from GL_ARB_texture_compression_bptc (BC7)
+pub const GL_COMPRESSED_RGBA_BPTC_UNORM: u32 = 0x8E8C;
+/// GL_COMPRESSED_SRGB_ALPHA_BPTC_UNORM from GL_ARB_texture_compression_bptc (BC7 sRGB)
+pub const GL_COMPRESSED_SRGB_ALPHA_BPTC_UNORM: u32 = 0x8E8D;
+/// GL_COMPRESSED_RGB8_ETC2 from GL_ARB_ES3_compatibility
+pub const GL_COMPRESSED_RGB8_ETC2: u32 = 0x9274;
+/// GL_COMPRESSED_SRGB8_ETC2 from GL_ARB_ES3_compatibility
+pub const GL_COMPRESSED_SRGB8_ETC2: u32 = 0x9275;
+/// GL_COMPRESSED_RGBA8_ETC2_EAC from GL_ARB_ES3_compatibility
+pub const GL_COMPRESSED_RGBA8_ETC2_EAC: u32 = 0x9278;
+/// GL_COMPRESSED_SRGB8_ALPHA8_ETC2_EAC from GL_ARB_ES3_compatibility
+pub const GL_COMPRESSED_SRGB8_ALPHA8_ETC2_EAC: u32 = 0x9279;
+
+const KTX_IDENTIFIER: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x31, 0x31, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+const KTX_HEADER_SIZE: usize = 64;
+const DDS_HEADER_SIZE: usize = 128;
+const DDS_DX10_HEADER_SIZE: usize = 20;
+const DDSD_MIPMAPCOUNT: u32 = 0x20000;
+
+/// How many bytes a 4x4 block of a compressed format takes, None if the format isn't known
+pub fn block_size(format: u32) -> Option<usize> {
+    match format {
+        GL_COMPRESSED_RGB_S3TC_DXT1_EXT
+        | GL_COMPRESSED_RGBA_S3TC_DXT1_EXT
+        | GL_COMPRESSED_RED_RGTC1
+        | GL_COMPRESSED_SIGNED_RED_RGTC1
+        | GL_COMPRESSED_RGB8_ETC2
+        | GL_COMPRESSED_SRGB8_ETC2 => Some(8),
+        GL_COMPRESSED_RGBA_S3TC_DXT3_EXT
+        | GL_COMPRESSED_RGBA_S3TC_DXT5_EXT
+        | GL_COMPRESSED_RG_RGTC2
+        | GL_COMPRESSED_SIGNED_RG_RGTC2
+        | GL_COMPRESSED_RGBA_BPTC_UNORM
+        | GL_COMPRESSED_SRGB_ALPHA_BPTC_UNORM
+        | GL_COMPRESSED_RGBA8_ETC2_EAC
+        | GL_COMPRESSED_SRGB8_ALPHA8_ETC2_EAC => Some(16),
+        _ => None,
+    }
+}
+
+/// The size in bytes of a mipmap level of a compressed texture
+fn level_size(format: u32, width: u32, height: u32) -> Option<usize> {
+    Some(
+        (width as usize).div_ceil(4).max(1)
+            * (height as usize).div_ceil(4).max(1)
+            * block_size(format)?,
+    )
+}
+
+/// Checks that an image of width x height can have mip_count mipmap levels, a chain ends at 1x1
+/// so it has at most log2(max(width, height)) + 1 levels. It is checked before the levels are
+/// read since the count comes from the file
+fn check_mip_count(width: u32, height: u32, mip_count: u32) -> Result<(), TextureError> {
+    let max = u32::BITS - width.max(height).max(1).leading_zeros();
+    if mip_count > max {
+        return Err(TextureError::InvalidFile(format!(
+            "A {}x{} image can't have {} mipmap levels, it has at most {}",
+            width, height, mip_count, max
+        )));
+    }
+    Ok(())
+}
+
+/// Reads a little endian u32 at offset
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, TextureError> {
+    data.get(offset..offset + 4)
+        .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .ok_or_else(|| TextureError::InvalidFile("The file ended early".to_string()))
+}
+
+/// A compressed texture read from a container, it isn't uploaded yet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompressedImage {
+    /// The compressed internal format e.g. [GL_COMPRESSED_RGBA_S3TC_DXT5_EXT]
+    pub format: u32,
+    /// The width of the first mipmap level
+    pub width: u32,
+    /// The height of the first mipmap level
+    pub height: u32,
+    /// The data of every mipmap level starting with the largest
+    pub levels: Vec<Vec<u8>>,
+}
+
+impl CompressedImage {
+    /// Parses a DDS file with DXT1, DXT3, DXT5, ATI1/BC4U, ATI2/BC5U or a DX10 header with
+    /// BC1-BC5 or BC7
+    pub fn from_dds(data: &[u8]) -> Result<Self, TextureError> {
+        if data.get(0..4) != Some(b"DDS ") {
+            return Err(TextureError::InvalidFile(
+                "This isn't a DDS file".to_string(),
+            ));
+        }
+
+        let flags = read_u32(data, 8)?;
+        let height = read_u32(data, 12)?;
+        let width = read_u32(data, 16)?;
+        let mip_count = if flags & DDSD_MIPMAPCOUNT != 0 {
+            read_u32(data, 28)?.max(1)
+        } else {
+            1
+        };
+
+        let four_cc = data.get(84..88).unwrap_or_default();
+        let (format, mut offset) = match four_cc {
+            b"DXT1" => (GL_COMPRESSED_RGBA_S3TC_DXT1_EXT, DDS_HEADER_SIZE),
+            b"DXT3" => (GL_COMPRESSED_RGBA_S3TC_DXT3_EXT, DDS_HEADER_SIZE),
+            b"DXT5" => (GL_COMPRESSED_RGBA_S3TC_DXT5_EXT, DDS_HEADER_SIZE),
+            b"ATI1" | b"BC4U" => (GL_COMPRESSED_RED_RGTC1, DDS_HEADER_SIZE),
+            b"ATI2" | b"BC5U" => (GL_COMPRESSED_RG_RGTC2, DDS_HEADER_SIZE),
+            b"DX10" => {
+                let format = match read_u32(data, DDS_HEADER_SIZE)? {
+                    71 => GL_COMPRESSED_RGBA_S3TC_DXT1_EXT,
+                    74 => GL_COMPRESSED_RGBA_S3TC_DXT3_EXT,
+                    77 => GL_COMPRESSED_RGBA_S3TC_DXT5_EXT,
+                    80 => GL_COMPRESSED_RED_RGTC1,
+                    81 => GL_COMPRESSED_SIGNED_RED_RGTC1,
+                    83 => GL_COMPRESSED_RG_RGTC2,
+                    84 => GL_COMPRESSED_SIGNED_RG_RGTC2,
+                    98 => GL_COMPRESSED_RGBA_BPTC_UNORM,
+                    99 => GL_COMPRESSED_SRGB_ALPHA_BPTC_UNORM,
+                    dxgi => {
+                        return Err(TextureError::UnsupportedFormat(format!(
+                            "The DXGI format {} isn't supported",
+                            dxgi
+                        )))
+                    }
+                };
+                (format, DDS_HEADER_SIZE + DDS_DX10_HEADER_SIZE)
+            }
+            other => {
+                return Err(TextureError::UnsupportedFormat(format!(
+                    "The DDS format {:?} isn't supported",
+                    String::from_utf8_lossy(other)
+                )))
+            }
+        };
+
+        check_mip_count(width, height, mip_count)?;
+        let mut levels = Vec::with_capacity(mip_count as usize);
+        for level in 0..mip_count {
+            let size = level_size(format, (width >> level).max(1), (height >> level).max(1))
+                .unwrap_or_default();
+            let bytes = data.get(offset..offset + size).ok_or_else(|| {
+                TextureError::InvalidFile(format!("Mipmap level {} is cut off", level))
+            })?;
+            levels.push(bytes.to_vec());
+            offset += size;
+        }
+
+        Ok(CompressedImage {
+            format,
+            width,
+            height,
+            levels,
+        })
+    }
+
+    /// Parses a KTX (version 1) file with a single 2D compressed texture
+    pub fn from_ktx(data: &[u8]) -> Result<Self, TextureError> {
+        if data.get(0..12) != Some(&KTX_IDENTIFIER[..]) {
+            return Err(TextureError::InvalidFile(
+                "This isn't a KTX file".to_string(),
+            ));
+        }
+        if read_u32(data, 12)? != 0x04030201 {
+            return Err(TextureError::InvalidFile(
+                "Big endian KTX files aren't supported".to_string(),
+            ));
+        }
+
+        let gl_type = read_u32(data, 16)?;
+        let format = read_u32(data, 28)?;
+        let width = read_u32(data, 36)?;
+        let height = read_u32(data, 40)?;
+        let depth = read_u32(data, 44)?;
+        let array_elements = read_u32(data, 48)?;
+        let faces = read_u32(data, 52)?;
+        let mip_count = read_u32(data, 56)?.max(1);
+        let key_value_size = read_u32(data, 60)? as usize;
+
+        if gl_type != 0 || block_size(format).is_none() {
+            return Err(TextureError::UnsupportedFormat(format!(
+                "The KTX internal format {:#X} isn't a supported compressed format",
+                format
+            )));
+        }
+        if depth > 1 || array_elements > 0 || faces != 1 {
+            return Err(TextureError::UnsupportedFormat(
+                "Only 2D KTX textures are supported".to_string(),
+            ));
+        }
+
+        check_mip_count(width, height, mip_count)?;
+        let mut offset = KTX_HEADER_SIZE + key_value_size;
+        let mut levels = Vec::with_capacity(mip_count as usize);
+        for level in 0..mip_count {
+            let size = read_u32(data, offset)? as usize;
+            offset += 4;
+            let bytes = data.get(offset..offset + size).ok_or_else(|| {
+                TextureError::InvalidFile(format!("Mipmap level {} is cut off", level))
+            })?;
+            levels.push(bytes.to_vec());
+            // every level is padded to 4 bytes
+            offset += (size + 3) & !3;
+        }
+
+        Ok(CompressedImage {
+            format,
+            width,
+            height,
+            levels,
+        })
+    }
+}
+
+impl Texture {
+    /// Loads a GPU compressed texture from a DDS file, see [CompressedImage::from_dds] for the
+    /// supported formats
+    ///
+    /// The texture is bound to the active texture unit as GL_TEXTURE_2D. Unlike
+    /// [Texture::from_image] the image isn't flipped, compressed blocks can't be flipped cheaply
+    /// so the texture coordinates have to be flipped instead
+    ///
+    /// # Example
+    /// ```
+    /// Texture::set_tex_unit(GL_TEXTURE0);
+    /// let texture = Texture::from_dds("data/bricks.dds")?;
+    /// ```
+    pub fn from_dds<P: AsRef<Path>>(path: P) -> Result<Texture, TextureError> {
+        Texture::from_compressed(&CompressedImage::from_dds(&read_file(path.as_ref())?)?)
+    }
+
+    /// Loads a GPU compressed texture from a KTX file, see [Texture::from_dds]
+    pub fn from_ktx<P: AsRef<Path>>(path: P) -> Result<Texture, TextureError> {
+        Texture::from_compressed(&CompressedImage::from_ktx(&read_file(path.as_ref())?)?)
+    }
+
+    /// Uploads a compressed image and all its mipmap levels with glCompressedTexImage2D
+    ///
+    /// It is an error if the driver can't upload the format or the image has more mipmap levels
+    /// than its size allows
+    pub fn from_compressed(img: &CompressedImage) -> Result<Texture, TextureError> {
+        check_mip_count(img.width, img.height, img.levels.len() as u32)?;
+        if !compressed_formats().contains(&img.format) {
+            return Err(TextureError::UnsupportedFormat(format!(
+                "The driver dosen't support the compressed format {:#X}",
+                img.format
+            )));
+        }
+
+        let mut texture = Texture::new();
+        texture.bind(GL_TEXTURE_2D);
+        for (level, data) in img.levels.iter().enumerate() {
+            unsafe {
+                glCompressedTexImage2D(
+                    GL_TEXTURE_2D,
+                    level as i32,
+                    img.format,
+                    (img.width >> level).max(1) as i32,
+                    (img.height >> level).max(1) as i32,
+                    0,
+                    data.len() as i32,
+                    to_carray(data).cast(),
+                );
+            }
+        }
+        texture.track_memory();
+
+        let min_filter = if img.levels.len() > 1 {
+            GL_LINEAR_MIPMAP_LINEAR
+        } else {
+            GL_LINEAR
+        };
+        unsafe {
+            glTexParameteri(
+                GL_TEXTURE_2D,
+                GL_TEXTURE_MAX_LEVEL,
+                img.levels.len() as i32 - 1,
+            );
+            glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MIN_FILTER, min_filter as i32);
+            glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MAG_FILTER, GL_LINEAR as i32);
+        }
+
+        Ok(texture)
+    }
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>, TextureError> {
+    fs::read(path).map_err(|err| {
+        TextureError::InvalidFile(format!("Couldn't read {}: {}", path.display(), err))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A DDS header for a DXT1 image of size x size with mip_count levels and no data
+    fn dds_header(size: u32, mip_count: u32) -> Vec<u8> {
+        let mut data = vec![0; DDS_HEADER_SIZE];
+        data[0..4].copy_from_slice(b"DDS ");
+        data[8..12].copy_from_slice(&DDSD_MIPMAPCOUNT.to_le_bytes());
+        data[12..16].copy_from_slice(&size.to_le_bytes());
+        data[16..20].copy_from_slice(&size.to_le_bytes());
+        data[28..32].copy_from_slice(&mip_count.to_le_bytes());
+        data[84..88].copy_from_slice(b"DXT1");
+        data
+    }
+
+    #[test]
+    fn a_full_mip_chain_is_read() {
+        let mut data = dds_header(4, 3);
+        data.extend(vec![0; 3 * 8]);
+        let img = CompressedImage::from_dds(&data).unwrap();
+        assert_eq!(img.levels.len(), 3);
+    }
+
+    #[test]
+    fn too_many_mip_levels_are_rejected() {
+        assert!(check_mip_count(4, 4, 3).is_ok());
+        assert!(check_mip_count(4, 4, 4).is_err());
+        assert!(check_mip_count(u32::MAX, 1, 32).is_ok());
+        assert!(check_mip_count(u32::MAX, 1, 33).is_err());
+        assert!(CompressedImage::from_dds(&dds_header(4, u32::MAX)).is_err());
+    }
+}
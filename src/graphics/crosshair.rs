@@ -0,0 +1,134 @@
+use std::mem::size_of;
+
+use super::{buffer::*, shader::*, uniform::*, vertex::*, *};
+use nalgebra_glm::{ortho, vec2, Vec2, Vec4};
+
+/// A reticle of 4 arms around the center of the window, e.g. for first person shooters
+///
+/// It is drawn on top of everything with the depth test off, so it should be drawn after the
+/// scene. Its size is in pixels so it stays the same size whatever the resolution
+///
+/// # Example
+/// ```
+/// let mut crosshair = Crosshair::new(vec4(1.0, 1.0, 1.0, 0.8))?;
+/// crosshair.size = 12.0;
+///
+/// // every frame after the world is rendered
+/// crosshair.draw(world.env.win_size.x, world.env.win_size.y);
+/// ```
+pub struct Crosshair {
+    /// How long every arm is in pixels
+    pub size: f32,
+    /// How far the arms start from the center in pixels, 0.0 makes a plus
+    pub gap: f32,
+    /// How wide the arms are in pixels
+    pub thickness: f32,
+    /// The color of the arms in rgba
+    pub color: Vec4,
+    program: ShaderProgram,
+    vao: VertexArray,
+    vbo: Buffer,
+}
+
+impl Crosshair {
+    /// Makes a crosshair with 8 pixel arms, a gap of 4 pixels and a thickness of 2 pixels and
+    /// compiles the vertex color program
+    pub fn new(color: Vec4) -> Result<Self, String> {
+        let vao = VertexArray::new().ok_or_else(|| "Couldn't make a VAO".to_string())?;
+        let vbo = Buffer::new().ok_or_else(|| "Couldn't make a VBO".to_string())?;
+
+        vao.bind();
+        vbo.bind(BufferType::Array);
+        let stride = (7 * size_of::<f32>()) as i32;
+        unsafe {
+            glVertexAttribPointer(0, 3, GL_FLOAT, GL_FALSE, stride, std::ptr::null());
+            glEnableVertexAttribArray(0);
+            glVertexAttribPointer(
+                1,
+                4,
+                GL_FLOAT,
+                GL_FALSE,
+                stride,
+                (3 * size_of::<f32>()) as *const _,
+            );
+            glEnableVertexAttribArray(1);
+        }
+        VertexArray::clear_binding();
+
+        Ok(Crosshair {
+            size: 8.0,
+            gap: 4.0,
+            thickness: 2.0,
+            color,
+            program: ShaderProgram::vertex_color()?,
+            vao,
+            vbo,
+        })
+    }
+
+    /// The center of a window in pixels, it is where the crosshair is drawn
+    pub fn center(width: f32, height: f32) -> Vec2 {
+        vec2(width / 2.0, height / 2.0)
+    }
+
+    /// The rectangles of the right, left, top and bottom arms as (min, max) corners in pixels
+    /// from the bottom left of a window
+    pub fn arms(&self, width: f32, height: f32) -> [(Vec2, Vec2); 4] {
+        let center = Self::center(width, height);
+        let half = self.thickness / 2.0;
+        let (near, far) = (self.gap, self.gap + self.size);
+        [
+            (vec2(near, -half), vec2(far, half)),
+            (vec2(-far, -half), vec2(-near, half)),
+            (vec2(-half, near), vec2(half, far)),
+            (vec2(-half, -far), vec2(half, -near)),
+        ]
+        .map(|(min, max)| (center + min, center + max))
+    }
+
+    /// Draws the crosshair in the center of a window of width and height pixels, the depth test
+    /// is turned off while it is drawn and restored afterwards
+    pub fn draw(&self, width: f32, height: f32) {
+        let color = self.color;
+        let data: Vec<f32> = self
+            .arms(width, height)
+            .iter()
+            .flat_map(|(min, max)| {
+                [
+                    vec2(min.x, min.y),
+                    vec2(max.x, min.y),
+                    vec2(max.x, max.y),
+                    vec2(min.x, min.y),
+                    vec2(max.x, max.y),
+                    vec2(min.x, max.y),
+                ]
+            })
+            .flat_map(|pos| [pos.x, pos.y, 0.0, color.x, color.y, color.z, color.w])
+            .collect();
+
+        let depth_test = is_enabled(GL_DEPTH_TEST);
+        disable(GL_DEPTH_TEST);
+        self.program.bind();
+        Uniform::new(&self.program, "camera_matrix")
+            .set_uniform_matrix(false, ortho(0.0, width, 0.0, height, -1.0, 1.0).into());
+        self.vao.bind();
+        self.vbo.bind(BufferType::Array);
+        buffer_data(
+            BufferType::Array,
+            bytemuck::cast_slice(&data),
+            BufferUsage::Stream as u32,
+        );
+        unsafe { glDrawArrays(GL_TRIANGLES, 0, (data.len() / 7) as i32) };
+        VertexArray::clear_binding();
+        if depth_test {
+            enable(GL_DEPTH_TEST);
+        }
+    }
+
+    /// Deletes the program, VAO and VBO
+    pub fn delete(self) {
+        self.program.delete();
+        self.vao.delete();
+        self.vbo.delete();
+    }
+}
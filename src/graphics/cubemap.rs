@@ -0,0 +1,103 @@
+use super::*;
+use image::RgbaImage;
+
+/// A [cubemap](https://www.khronos.org/opengl/wiki/Cubemap_Texture) texture made of 6 square
+/// faces, e.g. for skyboxes and environment reflections
+///
+/// # Example
+/// ```
+/// let faces = ["right", "left", "top", "bottom", "front", "back"]
+///     .map(|face| image::open(format!("data/sky/{}.png", face)).unwrap());
+/// let sky = Cubemap::from_images(faces)?;
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cubemap(pub u32);
+
+impl Cubemap {
+    /// Makes a new empty cubemap
+    pub fn new() -> Option<Self> {
+        let mut id = 0;
+        unsafe { glGenTextures(1, &mut id) };
+        if id != 0 {
+            Some(Cubemap(id))
+        } else {
+            None
+        }
+    }
+
+    /// Makes a cubemap from its faces in the order +x, -x, +y, -y, +z, -z
+    ///
+    /// Every face must be square and the same size. Unlike 2D textures the faces aren't
+    /// flipped, cubemaps have their origin in the top left
+    pub fn from_images(faces: [DynamicImage; 6]) -> Result<Self, String> {
+        let faces = faces.map(|face| face.to_rgba8());
+        let size = faces[0].width();
+        if faces
+            .iter()
+            .any(|face| face.width() != size || face.height() != size)
+        {
+            return Err("Every face of a cubemap must be square and the same size".to_string());
+        }
+
+        let cubemap = Cubemap::new().ok_or_else(|| "Couldn't make a cubemap".to_string())?;
+        cubemap.bind();
+        for (i, face) in faces.iter().enumerate() {
+            unsafe {
+                glTexImage2D(
+                    GL_TEXTURE_CUBE_MAP_POSITIVE_X + i as u32,
+                    0,
+                    GL_RGBA8 as i32,
+                    size as i32,
+                    size as i32,
+                    0,
+                    GL_RGBA,
+                    GL_UNSIGNED_BYTE,
+                    to_carray(face.as_raw()).cast(),
+                );
+            }
+        }
+        unsafe {
+            glTexParameteri(GL_TEXTURE_CUBE_MAP, GL_TEXTURE_MIN_FILTER, GL_LINEAR as i32);
+            glTexParameteri(GL_TEXTURE_CUBE_MAP, GL_TEXTURE_MAG_FILTER, GL_LINEAR as i32);
+            glTexParameteri(
+                GL_TEXTURE_CUBE_MAP,
+                GL_TEXTURE_WRAP_S,
+                GL_CLAMP_TO_EDGE as i32,
+            );
+            glTexParameteri(
+                GL_TEXTURE_CUBE_MAP,
+                GL_TEXTURE_WRAP_T,
+                GL_CLAMP_TO_EDGE as i32,
+            );
+            glTexParameteri(
+                GL_TEXTURE_CUBE_MAP,
+                GL_TEXTURE_WRAP_R,
+                GL_CLAMP_TO_EDGE as i32,
+            );
+        }
+        Cubemap::unbind();
+
+        Ok(cubemap)
+    }
+
+    /// Makes a 1x1 cubemap where every face is color
+    pub fn from_color(color: [u8; 4]) -> Result<Self, String> {
+        let face = || ImageRgba8(RgbaImage::from_pixel(1, 1, image::Rgba(color)));
+        Cubemap::from_images([face(), face(), face(), face(), face(), face()])
+    }
+
+    /// Binds the cubemap to the active texture unit
+    pub fn bind(&self) {
+        unsafe { glBindTexture(GL_TEXTURE_CUBE_MAP, self.0) }
+    }
+
+    /// Unbinds the cubemap of the active texture unit
+    pub fn unbind() {
+        unsafe { glBindTexture(GL_TEXTURE_CUBE_MAP, 0) }
+    }
+
+    /// Deletes the cubemap
+    pub fn delete(&self) {
+        unsafe { glDeleteTextures(1, &self.0) }
+    }
+}
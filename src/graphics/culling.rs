@@ -0,0 +1,216 @@
+use super::*;
+use nalgebra_glm::*;
+
+/// An axis aligned bounding box
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    /// The corner with the smallest coordinates
+    pub min: Vec3,
+    /// The corner with the largest coordinates
+    pub max: Vec3,
+}
+
+impl Aabb {
+    /// Creates a new bounding box from its corners
+    pub fn new(min: Vec3, max: Vec3) -> Self {
+        Aabb { min, max }
+    }
+
+    /// Creates the smallest bounding box containing all the points, None if there are no points
+    pub fn from_points<I: IntoIterator<Item = Vec3>>(points: I) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        Some(points.fold(Aabb::new(first, first), |aabb, point| Aabb {
+            min: aabb.min.inf(&point),
+            max: aabb.max.sup(&point),
+        }))
+    }
+
+    /// The center of the box
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) / 2.0
+    }
+
+    /// Half of the size of the box
+    pub fn extent(&self) -> Vec3 {
+        (self.max - self.min) / 2.0
+    }
+
+    /// Checks if the boxes overlap, touching boxes overlap
+    pub fn intersects(&self, other: &Aabb) -> bool {
+        self.min.x <= other.max.x
+            && self.max.x >= other.min.x
+            && self.min.y <= other.max.y
+            && self.max.y >= other.min.y
+            && self.min.z <= other.max.z
+            && self.max.z >= other.min.z
+    }
+
+    /// Checks if the point is inside the box
+    pub fn contains_point(&self, point: &Vec3) -> bool {
+        self.intersects(&Aabb::new(*point, *point))
+    }
+
+    /// Moves the point to the closest point inside the box
+    pub fn clamp_point(&self, point: &Vec3) -> Vec3 {
+        clamp_vec(point, &self.min, &self.max)
+    }
+
+    /// The 8 corners of the box
+    pub fn corners(&self) -> [Vec3; 8] {
+        let (min, max) = (self.min, self.max);
+        [
+            vec3(min.x, min.y, min.z),
+            vec3(max.x, min.y, min.z),
+            vec3(min.x, max.y, min.z),
+            vec3(max.x, max.y, min.z),
+            vec3(min.x, min.y, max.z),
+            vec3(max.x, min.y, max.z),
+            vec3(min.x, max.y, max.z),
+            vec3(max.x, max.y, max.z),
+        ]
+    }
+}
+
+/// The 6 planes of a camera frustum, used to skip objects that are off screen
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frustum {
+    /// The planes as (normal, distance), the normals point into the frustum
+    pub planes: [Vec4; 6],
+}
+
+impl Frustum {
+    /// Extracts the frustum from a view projection matrix (proj * view)
+    pub fn from_matrix(matrix: &Mat4) -> Self {
+        let row = |i: usize| matrix.row(i).transpose();
+        let planes = [
+            row(3) + row(0), // left
+            row(3) - row(0), // right
+            row(3) + row(1), // bottom
+            row(3) - row(1), // top
+            row(3) + row(2), // near
+            row(3) - row(2), // far
+        ]
+        .map(|plane| plane / plane.xyz().norm());
+        Frustum { planes }
+    }
+
+    /// Checks if any part of the bounding box may be inside the frustum
+    pub fn contains_aabb(&self, aabb: &Aabb) -> bool {
+        self.planes.iter().all(|plane| {
+            // the corner furthest along the normal of the plane
+            let corner = vec3(
+                if plane.x >= 0.0 {
+                    aabb.max.x
+                } else {
+                    aabb.min.x
+                },
+                if plane.y >= 0.0 {
+                    aabb.max.y
+                } else {
+                    aabb.min.y
+                },
+                if plane.z >= 0.0 {
+                    aabb.max.z
+                } else {
+                    aabb.min.z
+                },
+            );
+            plane.xyz().dot(&corner) + plane.w >= 0.0
+        })
+    }
+
+    /// Checks if any part of the sphere may be inside the frustum
+    pub fn contains_sphere(&self, center: &Vec3, radius: f32) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.xyz().dot(center) + plane.w >= -radius)
+    }
+}
+
+/// A hardware occlusion query, it checks if any pixel of a cheap proxy (e.g. the bounding box)
+/// passes the depth test so the full object can be skipped when it is hidden
+///
+/// The result of the previous frame is used so the cpu never waits on the gpu, an object is
+/// visible until a query says otherwise
+///
+/// # Example
+/// ```
+/// // draw the occluders first, then for every object
+/// if frustum.contains_aabb(&aabb) {
+///     query.test(|| draw_box(&aabb));
+///     if query.is_visible() {
+///         draw_object();
+///     }
+/// }
+/// ```
+pub struct OcclusionQuery {
+    /// The query id
+    pub id: u32,
+    visible: bool,
+    pending: bool,
+}
+
+impl OcclusionQuery {
+    /// Makes a new occlusion query
+    pub fn new() -> Option<Self> {
+        let mut id = 0;
+        unsafe { glGenQueries(1, &mut id) };
+        if id != 0 {
+            Some(OcclusionQuery {
+                id,
+                visible: true,
+                pending: false,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Draws the proxy with color and depth writes off while counting the samples that pass
+    ///
+    /// Nothing is drawn if the previous query hasn't finished yet
+    pub fn test<F: FnOnce()>(&mut self, draw_proxy: F) {
+        self.poll();
+        if self.pending {
+            return;
+        }
+
+        color_mask(false, false, false, false);
+        depth_mask(false);
+        unsafe { glBeginQuery(GL_ANY_SAMPLES_PASSED, self.id) };
+        draw_proxy();
+        unsafe { glEndQuery(GL_ANY_SAMPLES_PASSED) };
+        color_mask(true, true, true, true);
+        depth_mask(true);
+
+        self.pending = true;
+    }
+
+    /// Reads the result of the query if it is available, it never waits for the gpu
+    pub fn poll(&mut self) {
+        if !self.pending {
+            return;
+        }
+
+        let mut available = 0;
+        unsafe { glGetQueryObjectuiv(self.id, GL_QUERY_RESULT_AVAILABLE, &mut available) };
+        if available != 0 {
+            let mut samples = 0;
+            unsafe { glGetQueryObjectuiv(self.id, GL_QUERY_RESULT, &mut samples) };
+            self.visible = samples != 0;
+            self.pending = false;
+        }
+    }
+
+    /// Checks if the proxy was visible in the last finished query
+    pub fn is_visible(&mut self) -> bool {
+        self.poll();
+        self.visible
+    }
+
+    /// Deletes the query
+    pub fn delete(&self) {
+        unsafe { glDeleteQueries(1, &self.id) }
+    }
+}
@@ -0,0 +1,263 @@
+use std::mem::size_of;
+
+use super::{
+    buffer::*,
+    culling::Aabb,
+    framebuffer::{linearize_depth, Framebuffer, LINEARIZE_DEPTH_GLSL},
+    fullscreen::fullscreen_pass,
+    shader::*,
+    uniform::*,
+    vertex::*,
+    *,
+};
+use crate::ECS::mesh::{Mesh, VertexTrait};
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+
+/// Batches colored lines for debugging, e.g. bounding boxes and normals, and draws them all at
+/// once with [ShaderProgram::vertex_color]
+///
+/// # Example
+/// ```
+/// let mut debug = DebugDraw::new()?;
+///
+/// // every frame
+/// debug.aabb(&pyramid.mesh.aabb(), vec4(0.0, 1.0, 0.0, 1.0));
+/// debug.mesh_normals(&pyramid.mesh, 0.2, vec4(0.0, 0.0, 1.0, 1.0));
+/// debug.flush(&world.env.view_projection());
+/// ```
+pub struct DebugDraw {
+    /// The lines as pairs of (position, color)
+    lines: Vec<[(Vec3, Vec4); 2]>,
+    program: ShaderProgram,
+    vao: VertexArray,
+    vbo: Buffer,
+}
+
+impl DebugDraw {
+    /// Makes an empty batch and compiles the vertex color program
+    pub fn new() -> Result<Self, String> {
+        let vao = VertexArray::new().ok_or_else(|| "Couldn't make a VAO".to_string())?;
+        let vbo = Buffer::new().ok_or_else(|| "Couldn't make a VBO".to_string())?;
+
+        vao.bind();
+        vbo.bind(BufferType::Array);
+        let stride = (7 * size_of::<f32>()) as i32;
+        unsafe {
+            glVertexAttribPointer(0, 3, GL_FLOAT, GL_FALSE, stride, std::ptr::null());
+            glEnableVertexAttribArray(0);
+            glVertexAttribPointer(
+                1,
+                4,
+                GL_FLOAT,
+                GL_FALSE,
+                stride,
+                (3 * size_of::<f32>()) as *const _,
+            );
+            glEnableVertexAttribArray(1);
+        }
+        VertexArray::clear_binding();
+
+        Ok(DebugDraw {
+            lines: Vec::new(),
+            program: ShaderProgram::vertex_color()?,
+            vao,
+            vbo,
+        })
+    }
+
+    /// Adds a line from start to end
+    pub fn line(&mut self, start: Vec3, end: Vec3, color: Vec4) {
+        self.lines.push([(start, color), (end, color)]);
+    }
+
+    /// Adds the 12 edges of a bounding box
+    pub fn aabb(&mut self, aabb: &Aabb, color: Vec4) {
+        let corners = aabb.corners();
+        // corners differ in one axis when their indices differ in one bit
+        for (a, b) in [
+            (0, 1),
+            (2, 3),
+            (4, 5),
+            (6, 7),
+            (0, 2),
+            (1, 3),
+            (4, 6),
+            (5, 7),
+            (0, 4),
+            (1, 5),
+            (2, 6),
+            (3, 7),
+        ] {
+            self.line(corners[a], corners[b], color);
+        }
+    }
+
+    /// Adds a line of length from every vertex of the mesh along its normal, see
+    /// [VertexTrait::normal]. Vertices without a normal are skipped
+    ///
+    /// The lines are in the local space of the mesh
+    pub fn mesh_normals<Vertex: VertexTrait + 'static + Sync + Send>(
+        &mut self,
+        mesh: &Mesh<Vertex>,
+        length: f32,
+        color: Vec4,
+    ) {
+        for vertex in mesh.vertices() {
+            if let Some(normal) = vertex.normal() {
+                let start = vertex.position();
+                self.line(start, start + normal.normalize() * length, color);
+            }
+        }
+    }
+
+    /// Adds a line of length from every vertex of the mesh along its tangent, see
+    /// [VertexTrait::tangent]. Vertices without a tangent are skipped
+    pub fn mesh_tangents<Vertex: VertexTrait + 'static + Sync + Send>(
+        &mut self,
+        mesh: &Mesh<Vertex>,
+        length: f32,
+        color: Vec4,
+    ) {
+        for vertex in mesh.vertices() {
+            if let Some(tangent) = vertex.tangent() {
+                let start = vertex.position();
+                self.line(start, start + tangent.normalize() * length, color);
+            }
+        }
+    }
+
+    /// How many lines are waiting to be drawn
+    pub fn line_count(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// The lines waiting to be drawn as (start, end)
+    pub fn lines(&self) -> impl Iterator<Item = (Vec3, Vec3)> + '_ {
+        self.lines.iter().map(|[start, end]| (start.0, end.0))
+    }
+
+    /// Removes the lines without drawing them
+    pub fn clear(&mut self) {
+        self.lines.clear();
+    }
+
+    /// Draws all the lines with camera_matrix then removes them
+    pub fn flush(&mut self, camera_matrix: &Mat4) {
+        if self.lines.is_empty() {
+            return;
+        }
+
+        let data: Vec<f32> = self
+            .lines
+            .iter()
+            .flatten()
+            .flat_map(|(pos, color)| [pos.x, pos.y, pos.z, color.x, color.y, color.z, color.w])
+            .collect();
+
+        self.program.use_program();
+        Uniform::new(&self.program, "camera_matrix")
+            .set_uniform_matrix(false, (*camera_matrix).into());
+        self.vao.bind();
+        self.vbo.bind(BufferType::Array);
+        buffer_data(
+            BufferType::Array,
+            bytemuck::cast_slice(&data),
+            BufferUsage::Stream as u32,
+        );
+        unsafe { glDrawArrays(GL_LINES, 0, (self.lines.len() * 2) as i32) };
+        VertexArray::clear_binding();
+        ShaderProgram::invalidate_binding();
+
+        self.lines.clear();
+    }
+
+    /// Deletes the program, VAO and VBO
+    pub fn delete(self) {
+        self.program.delete();
+        self.vao.delete();
+        self.vbo.delete();
+    }
+}
+
+/// The gray [DepthView] shows for a depth sampled from a depth texture, 1.0 (white) at the near
+/// plane and 0.0 (black) at the far plane. The distance is linear so z-fighting far away is
+/// visible too
+pub fn depth_to_gray(depth: f32, near: f32, far: f32) -> f32 {
+    1.0 - ((linearize_depth(depth, near, far) - near) / (far - near)).clamp(0.0, 1.0)
+}
+
+/// Shows the depth texture of a framebuffer on screen in grayscale for debugging depth and
+/// z-fighting, see [depth_to_gray]. Near things are white and far things are black
+///
+/// [Enviroment::show_depth](crate::ECS::world::Enviroment::show_depth) draws it after every
+/// frame
+///
+/// # Example
+/// ```
+/// let depth_view = DepthView::new()?;
+///
+/// world.env.keep_depth_texture = true;
+/// world.render();
+/// if let Some(scene) = world.env.depth_texture() {
+///     depth_view.draw(scene, 0.1, 100.0);
+/// }
+/// ```
+pub struct DepthView {
+    program: ShaderProgram,
+}
+
+impl DepthView {
+    /// Compiles the depth view program
+    pub fn new() -> Result<Self, String> {
+        Ok(DepthView {
+            program: ShaderProgram::fullscreen(&format!(
+                "#version 330 core\n{}\n{}",
+                LINEARIZE_DEPTH_GLSL,
+                include_str!("../../shaders/depth_view_frag.glsl")
+            ))?,
+        })
+    }
+
+    /// Draws the depth of scene over the whole viewport with [fullscreen_pass], near and far
+    /// are the planes of the camera the scene was drawn with
+    ///
+    /// scene must not be bound, the depth test is turned off while drawing and texture unit 0
+    /// is used
+    pub fn draw(&self, scene: &Framebuffer, near: f32, far: f32) {
+        let depth_test = is_enabled(GL_DEPTH_TEST);
+        disable(GL_DEPTH_TEST);
+        scene.bind_depth(GL_TEXTURE0);
+        self.program.bind();
+        Uniform::new(&self.program, "scene_depth").set_uniform_i(&[0]);
+        Uniform::new(&self.program, "near").set_uniform_f(&[near]);
+        Uniform::new(&self.program, "far").set_uniform_f(&[far]);
+        fullscreen_pass(&self.program);
+        if depth_test {
+            enable(GL_DEPTH_TEST);
+        }
+    }
+
+    /// Deletes the program
+    pub fn delete(self) {
+        self.program.delete();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn near_is_white_and_far_is_black() {
+        let (near, far) = (0.1, 100.0);
+        assert!((depth_to_gray(0.0, near, far) - 1.0).abs() < 1e-4);
+        assert!(depth_to_gray(1.0, near, far).abs() < 1e-4);
+
+        // halfway between the planes is middle gray even though the depth is close to 1.0
+        let distance: f32 = (near + far) / 2.0;
+        let z = (far + near - 2.0 * near * far / distance) / (far - near);
+        let depth = (z + 1.0) / 2.0;
+        assert!(depth > 0.99);
+        assert!((depth_to_gray(depth, near, far) - 0.5).abs() < 1e-2);
+    }
+}
@@ -0,0 +1,139 @@
+use super::{shader::*, *};
+
+/// How many times [DrawQueue::flush] changed the program
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DrawStats {
+    /// How many draws there were
+    pub draws: usize,
+    /// How many times a different program was used
+    pub program_changes: usize,
+}
+
+/// A queue of opaque draws that are sorted by program then texture before they are drawn, so
+/// draws that share a material are drawn together and each program and texture is only bound
+/// once
+///
+/// The order only matters for the number of state changes so it shouldn't be used for
+/// transparent draws, which have to be drawn back to front
+///
+/// # Example
+/// ```
+/// let mut queue = DrawQueue::new();
+/// for rock in &rocks {
+///     world.queue_mesh(&mut queue, &rock.mesh, Some(&stone));
+/// }
+/// world.queue_mesh(&mut queue, &tree.mesh, Some(&bark));
+/// let stats = queue.flush();
+/// ```
+#[derive(Default)]
+pub struct DrawQueue<'a> {
+    draws: Vec<QueuedDraw<'a>>,
+}
+
+/// A draw waiting in a [DrawQueue]
+struct QueuedDraw<'a> {
+    program: ShaderProgram,
+    /// The texture bound to GL_TEXTURE_2D, 0 for none
+    texture: u32,
+    draw: Box<dyn FnOnce() + 'a>,
+}
+
+impl<'a> DrawQueue<'a> {
+    /// Creates an empty queue
+    pub fn new() -> Self {
+        DrawQueue { draws: Vec::new() }
+    }
+
+    /// Adds a draw that needs program and the texture with the id texture(0 for none) bound to
+    /// GL_TEXTURE_2D of the active texture unit, draw must issue the draw calls
+    pub fn push<F: FnOnce() + 'a>(&mut self, program: ShaderProgram, texture: u32, draw: F) {
+        self.draws.push(QueuedDraw {
+            program,
+            texture,
+            draw: Box::new(draw),
+        });
+    }
+
+    /// How many draws are waiting
+    pub fn len(&self) -> usize {
+        self.draws.len()
+    }
+
+    /// Checks if there are no draws
+    pub fn is_empty(&self) -> bool {
+        self.draws.is_empty()
+    }
+
+    /// Sorts the draws by program then texture and draws them, skipping binds of the program and
+    /// texture that are already bound. Draws with the same program and texture keep the order
+    /// they were pushed in
+    ///
+    /// Textures bound by the draws themselves, e.g. by a
+    /// [Material](super::material::Material), aren't known to the queue so they aren't counted
+    pub fn flush(&mut self) -> DrawStats {
+        self.flush_with(ShaderProgram::bind, |texture| unsafe {
+            glBindTexture(GL_TEXTURE_2D, texture)
+        })
+    }
+
+    /// [DrawQueue::flush] with the binding of programs and textures given, bind_program returns
+    /// true if the program was changed
+    fn flush_with(
+        &mut self,
+        mut bind_program: impl FnMut(&ShaderProgram) -> bool,
+        mut bind_texture: impl FnMut(u32),
+    ) -> DrawStats {
+        let mut draws = std::mem::take(&mut self.draws);
+        draws.sort_by_key(|draw| (draw.program.0, draw.texture));
+
+        let mut stats = DrawStats {
+            draws: draws.len(),
+            ..Default::default()
+        };
+        let mut texture = None;
+        for draw in draws {
+            if bind_program(&draw.program) {
+                stats.program_changes += 1;
+            }
+            if texture != Some(draw.texture) {
+                bind_texture(draw.texture);
+                texture = Some(draw.texture);
+            }
+            (draw.draw)();
+        }
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    #[test]
+    fn draws_are_grouped_so_each_program_is_bound_once() {
+        let drawn = RefCell::new(Vec::new());
+        let mut queue = DrawQueue::new();
+        for (i, program) in [2, 1, 2, 1, 3].into_iter().enumerate() {
+            let drawn = &drawn;
+            queue.push(ShaderProgram(program), 0, move || {
+                drawn.borrow_mut().push((program, i))
+            });
+        }
+
+        let mut current = 0;
+        let stats = queue.flush_with(
+            |program| std::mem::replace(&mut current, program.0) != program.0,
+            |_| {},
+        );
+        assert_eq!(
+            stats,
+            DrawStats {
+                draws: 5,
+                program_changes: 3,
+            }
+        );
+        assert_eq!(*drawn.borrow(), [(1, 1), (1, 3), (2, 0), (2, 2), (3, 4)]);
+        assert!(queue.is_empty());
+    }
+}
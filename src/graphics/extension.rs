@@ -0,0 +1,179 @@
+use std::ffi::{c_char, c_void};
+use std::mem::transmute;
+use std::ptr::null_mut;
+use std::sync::atomic::{AtomicPtr, Ordering};
+
+use super::*;
+
+/// GL_DRAW_INDIRECT_BUFFER from OpenGL 4.0, ogl33 dosen't have it
+pub const GL_DRAW_INDIRECT_BUFFER: u32 = 0x8F3F;
+
+/// GL_PROGRAM_BINARY_RETRIEVABLE_HINT from GL_ARB_get_program_binary, ogl33 dosen't have it
+pub const GL_PROGRAM_BINARY_RETRIEVABLE_HINT: u32 = 0x8257;
+/// GL_PROGRAM_BINARY_LENGTH from GL_ARB_get_program_binary, ogl33 dosen't have it
+pub const GL_PROGRAM_BINARY_LENGTH: u32 = 0x8741;
+/// GL_NUM_PROGRAM_BINARY_FORMATS from GL_ARB_get_program_binary, ogl33 dosen't have it
+pub const GL_NUM_PROGRAM_BINARY_FORMATS: u32 = 0x87FE;
+
+/// GL_SAMPLE_SHADING from OpenGL 4.0 and GL_ARB_sample_shading, ogl33 dosen't have it
+pub const GL_SAMPLE_SHADING: u32 = 0x8C36;
+/// GL_MIN_SAMPLE_SHADING_VALUE from OpenGL 4.0 and GL_ARB_sample_shading, ogl33 dosen't have it
+pub const GL_MIN_SAMPLE_SHADING_VALUE: u32 = 0x8C37;
+
+static MULTI_DRAW_ELEMENTS_INDIRECT: AtomicPtr<c_void> = AtomicPtr::new(null_mut());
+static GET_PROGRAM_BINARY: AtomicPtr<c_void> = AtomicPtr::new(null_mut());
+static PROGRAM_BINARY: AtomicPtr<c_void> = AtomicPtr::new(null_mut());
+static PROGRAM_PARAMETERI: AtomicPtr<c_void> = AtomicPtr::new(null_mut());
+static MIN_SAMPLE_SHADING: AtomicPtr<c_void> = AtomicPtr::new(null_mut());
+
+/// Loads the functions that are newer than OpenGL 3.3 and so aren't loaded by [load_gl_with]
+///
+/// Functions the driver dosen't have are left unloaded, use [is_loaded] or
+/// [crate::graphics::capabilities::GlCapabilities] to check before calling them
+///
+/// # Example
+/// ```
+/// unsafe {
+///     load_gl_with(|f_name| win.get_proc_address(f_name));
+///     load_extensions_with(|f_name| win.get_proc_address(f_name));
+/// }
+/// ```
+///
+/// # Safety
+/// load_fn must return either null or a pointer to the function with the given name
+pub unsafe fn load_extensions_with<F>(mut load_fn: F)
+where
+    F: FnMut(*const c_char) -> *const c_void,
+{
+    load(
+        &mut load_fn,
+        b"glMultiDrawElementsIndirect\0",
+        &MULTI_DRAW_ELEMENTS_INDIRECT,
+    );
+    load(&mut load_fn, b"glGetProgramBinary\0", &GET_PROGRAM_BINARY);
+    load(&mut load_fn, b"glProgramBinary\0", &PROGRAM_BINARY);
+    load(&mut load_fn, b"glProgramParameteri\0", &PROGRAM_PARAMETERI);
+    load(&mut load_fn, b"glMinSampleShading\0", &MIN_SAMPLE_SHADING);
+    // drivers older than OpenGL 4.0 only have the extension version
+    if MIN_SAMPLE_SHADING.load(Ordering::Relaxed).is_null() {
+        load(
+            &mut load_fn,
+            b"glMinSampleShadingARB\0",
+            &MIN_SAMPLE_SHADING,
+        );
+    }
+}
+
+fn load<F>(load_fn: &mut F, name: &[u8], storage: &AtomicPtr<c_void>)
+where
+    F: FnMut(*const c_char) -> *const c_void,
+{
+    storage.store(
+        load_fn(name.as_ptr().cast()) as *mut c_void,
+        Ordering::Relaxed,
+    );
+}
+
+fn get(name: &str, storage: &AtomicPtr<c_void>) -> *mut c_void {
+    let ptr = storage.load(Ordering::Relaxed);
+    if ptr.is_null() {
+        panic!("{} not loaded, see load_extensions_with", name);
+    }
+    ptr
+}
+
+/// Checks if an extension function was loaded by [load_extensions_with] e.g. "glMultiDrawElementsIndirect"
+pub fn is_loaded(name: &str) -> bool {
+    let storage = match name {
+        "glMultiDrawElementsIndirect" => &MULTI_DRAW_ELEMENTS_INDIRECT,
+        "glGetProgramBinary" => &GET_PROGRAM_BINARY,
+        "glProgramBinary" => &PROGRAM_BINARY,
+        "glProgramParameteri" => &PROGRAM_PARAMETERI,
+        "glMinSampleShading" => &MIN_SAMPLE_SHADING,
+        _ => return false,
+    };
+    !storage.load(Ordering::Relaxed).is_null()
+}
+
+/// See [glMultiDrawElementsIndirect](https://www.khronos.org/registry/OpenGL-Refpages/gl4/html/glMultiDrawElementsIndirect.xhtml)
+///
+/// # Safety
+/// The indirect buffer must be bound and hold drawcount commands
+#[allow(non_snake_case)]
+pub unsafe fn glMultiDrawElementsIndirect(
+    mode: GLenum,
+    type_: GLenum,
+    indirect: *const c_void,
+    drawcount: GLsizei,
+    stride: GLsizei,
+) {
+    transmute::<*mut c_void, extern "system" fn(GLenum, GLenum, *const c_void, GLsizei, GLsizei)>(
+        get("glMultiDrawElementsIndirect", &MULTI_DRAW_ELEMENTS_INDIRECT),
+    )(mode, type_, indirect, drawcount, stride)
+}
+
+/// See [glGetProgramBinary](https://www.khronos.org/registry/OpenGL-Refpages/gl4/html/glGetProgramBinary.xhtml)
+///
+/// # Safety
+/// binary must point to at least buf_size writable bytes
+#[allow(non_snake_case)]
+pub unsafe fn glGetProgramBinary(
+    program: GLuint,
+    buf_size: GLsizei,
+    length: *mut GLsizei,
+    binary_format: *mut GLenum,
+    binary: *mut c_void,
+) {
+    transmute::<
+        *mut c_void,
+        extern "system" fn(GLuint, GLsizei, *mut GLsizei, *mut GLenum, *mut c_void),
+    >(get("glGetProgramBinary", &GET_PROGRAM_BINARY))(
+        program,
+        buf_size,
+        length,
+        binary_format,
+        binary,
+    )
+}
+
+/// See [glProgramBinary](https://www.khronos.org/registry/OpenGL-Refpages/gl4/html/glProgramBinary.xhtml)
+///
+/// # Safety
+/// binary must point to length readable bytes
+#[allow(non_snake_case)]
+pub unsafe fn glProgramBinary(
+    program: GLuint,
+    binary_format: GLenum,
+    binary: *const c_void,
+    length: GLsizei,
+) {
+    transmute::<*mut c_void, extern "system" fn(GLuint, GLenum, *const c_void, GLsizei)>(get(
+        "glProgramBinary",
+        &PROGRAM_BINARY,
+    ))(program, binary_format, binary, length)
+}
+
+/// See [glProgramParameteri](https://www.khronos.org/registry/OpenGL-Refpages/gl4/html/glProgramParameter.xhtml)
+///
+/// # Safety
+/// program must be a program object
+#[allow(non_snake_case)]
+pub unsafe fn glProgramParameteri(program: GLuint, pname: GLenum, value: GLint) {
+    transmute::<*mut c_void, extern "system" fn(GLuint, GLenum, GLint)>(get(
+        "glProgramParameteri",
+        &PROGRAM_PARAMETERI,
+    ))(program, pname, value)
+}
+
+/// See [glMinSampleShading](https://www.khronos.org/registry/OpenGL-Refpages/gl4/html/glMinSampleShading.xhtml),
+/// glMinSampleShadingARB is used if the driver dosen't have the OpenGL 4.0 function
+///
+/// # Safety
+/// The function must be loaded, see [is_loaded]
+#[allow(non_snake_case)]
+pub unsafe fn glMinSampleShading(value: GLfloat) {
+    transmute::<*mut c_void, extern "system" fn(GLfloat)>(get(
+        "glMinSampleShading",
+        &MIN_SAMPLE_SHADING,
+    ))(value)
+}
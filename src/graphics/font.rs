@@ -0,0 +1,319 @@
+use std::collections::HashMap;
+use std::mem::size_of;
+use std::path::Path;
+
+use super::{buffer::*, shader::*, texture::*, uniform::*, vertex::*, *};
+use image::{DynamicImage, GrayImage, Luma};
+use nalgebra_glm::{vec2, Mat4, Vec2, Vec4};
+
+/// Where a glyph is in the atlas and how it is placed, all in pixels of the atlas
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Glyph {
+    /// The top left corner of the glyph in the atlas
+    pub pos: Vec2,
+    /// The width and height of the glyph in the atlas
+    pub size: Vec2,
+    /// How far the glyph is drawn from the cursor, +y is down
+    pub offset: Vec2,
+    /// How far the cursor moves after the glyph
+    pub advance: f32,
+}
+
+/// A font drawn from a signed distance field atlas, so the text stays crisp at any scale
+///
+/// Every texel of the atlas is the distance to the edge of the nearest glyph, where 0.5 is the
+/// edge. The shader thresholds the distance with [SdfFont::smoothing] to antialias the edge and
+/// can draw an outline around it
+///
+/// The metrics are in the text format of BMFont, which most SDF generators such as Hiero and
+/// msdf-bmfont can write. Only the `common` and `char` lines are used
+///
+/// # Example
+/// ```
+/// let mut font = SdfFont::load("data/font.png", "data/font.fnt")?;
+/// font.outline_width = 0.1;
+///
+/// // every frame, after the world is rendered
+/// let projection = ortho(0.0, 800.0, 600.0, 0.0, -1.0, 1.0);
+/// font.draw("Hello world", vec2(10.0, 10.0), 2.0, &projection);
+/// ```
+pub struct SdfFont {
+    /// The distance field atlas, it is sampled linearly
+    pub texture: Texture,
+    /// The glyphs of the font by their character
+    pub glyphs: HashMap<char, Glyph>,
+    /// How far apart lines are in pixels of the atlas
+    pub line_height: f32,
+    /// The color of the text, it is white by default
+    pub color: Vec4,
+    /// The color of the outline, it is black by default
+    pub outline_color: Vec4,
+    /// Half the width of the antialiased edge in distance units, it is 0.1 by default. Smaller
+    /// values make sharper edges
+    pub smoothing: f32,
+    /// How far outside the edge the outline goes in distance units, it is 0.0(no outline) by
+    /// default and must be less than 0.5
+    pub outline_width: f32,
+    atlas_size: Vec2,
+    program: ShaderProgram,
+    vao: VertexArray,
+    vbo: Buffer,
+}
+
+impl SdfFont {
+    /// Loads the atlas image and the BMFont text metrics
+    pub fn load<A: AsRef<Path>, M: AsRef<Path>>(atlas: A, metrics: M) -> Result<Self, String> {
+        let (atlas, metrics) = (atlas.as_ref(), metrics.as_ref());
+        let img = image::io::Reader::open(atlas)
+            .map_err(|err| err.to_string())
+            .and_then(|reader| reader.decode().map_err(|err| err.to_string()))
+            .map_err(|err| format!("Couldn't load {}: {}", atlas.display(), err))?;
+        let metrics = std::fs::read_to_string(metrics)
+            .map_err(|err| format!("Couldn't read {}: {}", metrics.display(), err))?;
+        Self::from_image(img, &metrics)
+    }
+
+    /// Makes a font from an atlas image and BMFont text metrics, the distance is read from the
+    /// alpha channel like Hiero writes it, or from the gray value if the image has no alpha
+    pub fn from_image(img: DynamicImage, metrics: &str) -> Result<Self, String> {
+        let (glyphs, line_height) = parse_metrics(metrics)?;
+
+        let mut texture = Texture::new();
+        // the metrics count rows from the top like the image
+        texture.flip_v = false;
+        texture.bind(GL_TEXTURE_2D);
+        texture.tex_2d_r8(0, DynamicImage::ImageLuma8(distance_channel(img)));
+        unsafe {
+            glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MIN_FILTER, GL_LINEAR as i32);
+            glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MAG_FILTER, GL_LINEAR as i32);
+            glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_S, GL_CLAMP_TO_EDGE as i32);
+            glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_T, GL_CLAMP_TO_EDGE as i32);
+        }
+        let (width, height) = texture.get_size(0);
+        Texture::unbind(GL_TEXTURE_2D);
+
+        let vao = VertexArray::new().ok_or_else(|| "Couldn't make a VAO".to_string())?;
+        let vbo = Buffer::new().ok_or_else(|| "Couldn't make a VBO".to_string())?;
+        vao.bind();
+        vbo.bind(BufferType::Array);
+        let stride = (4 * size_of::<f32>()) as i32;
+        unsafe {
+            glVertexAttribPointer(0, 2, GL_FLOAT, GL_FALSE, stride, std::ptr::null());
+            glEnableVertexAttribArray(0);
+            glVertexAttribPointer(
+                1,
+                2,
+                GL_FLOAT,
+                GL_FALSE,
+                stride,
+                (2 * size_of::<f32>()) as *const _,
+            );
+            glEnableVertexAttribArray(1);
+        }
+        VertexArray::clear_binding();
+
+        Ok(SdfFont {
+            texture,
+            glyphs,
+            line_height,
+            color: Vec4::new(1.0, 1.0, 1.0, 1.0),
+            outline_color: Vec4::new(0.0, 0.0, 0.0, 1.0),
+            smoothing: 0.1,
+            outline_width: 0.0,
+            atlas_size: vec2(width as f32, height as f32),
+            program: ShaderProgram::sdf_text()?,
+            vao,
+            vbo,
+        })
+    }
+
+    /// Gets the glyph of a character, characters that aren't in the font are None
+    pub fn glyph(&self, c: char) -> Option<&Glyph> {
+        self.glyphs.get(&c)
+    }
+
+    /// The width and height of text drawn at scale, '\n' starts a new line
+    pub fn measure(&self, text: &str, scale: f32) -> Vec2 {
+        let (mut width, mut line_width, mut lines) = (0.0f32, 0.0, 1);
+        for c in text.chars() {
+            if c == '\n' {
+                width = width.max(line_width);
+                line_width = 0.0;
+                lines += 1;
+            } else if let Some(glyph) = self.glyph(c) {
+                line_width += glyph.advance;
+            }
+        }
+        vec2(width.max(line_width), lines as f32 * self.line_height) * scale
+    }
+
+    /// Draws text with its top left corner at pos, scale is how many pixels of projection a
+    /// pixel of the atlas covers. '\n' starts a new line and characters that aren't in the font
+    /// are skipped
+    ///
+    /// projection should have +y going down, e.g. ortho(0.0, width, height, 0.0, -1.0, 1.0).
+    /// The text is alpha blended on top of what is already drawn, the blend func is left as
+    /// [BlendMode::Alpha]
+    pub fn draw(&self, text: &str, pos: Vec2, scale: f32, projection: &Mat4) {
+        let mut data: Vec<f32> = Vec::new();
+        let mut cursor = pos;
+        for c in text.chars() {
+            if c == '\n' {
+                cursor = vec2(pos.x, cursor.y + self.line_height * scale);
+                continue;
+            }
+            let glyph = match self.glyph(c) {
+                Some(glyph) => glyph,
+                None => continue,
+            };
+
+            let min = cursor + glyph.offset * scale;
+            let max = min + glyph.size * scale;
+            let uv_min = glyph.pos.component_div(&self.atlas_size);
+            let uv_max = (glyph.pos + glyph.size).component_div(&self.atlas_size);
+            for (x, y, u, v) in [
+                (min.x, min.y, uv_min.x, uv_min.y),
+                (max.x, min.y, uv_max.x, uv_min.y),
+                (max.x, max.y, uv_max.x, uv_max.y),
+                (min.x, min.y, uv_min.x, uv_min.y),
+                (max.x, max.y, uv_max.x, uv_max.y),
+                (min.x, max.y, uv_min.x, uv_max.y),
+            ] {
+                data.extend([x, y, u, v]);
+            }
+            cursor.x += glyph.advance * scale;
+        }
+        if data.is_empty() {
+            return;
+        }
+
+        let depth_test = is_enabled(GL_DEPTH_TEST);
+        disable(GL_DEPTH_TEST);
+        set_blend_mode(BlendMode::Alpha);
+
+        self.program.use_program();
+        Uniform::new(&self.program, "projection").set_uniform_matrix(false, (*projection).into());
+        Uniform::new(&self.program, "atlas").set_uniform_i(&[0]);
+        Uniform::new(&self.program, "text_color").set_uniform_f(self.color.as_slice());
+        Uniform::new(&self.program, "outline_color").set_uniform_f(self.outline_color.as_slice());
+        Uniform::new(&self.program, "smoothing").set_uniform_f(&[self.smoothing]);
+        Uniform::new(&self.program, "outline_width").set_uniform_f(&[self.outline_width]);
+
+        Texture::set_tex_unit(GL_TEXTURE0);
+        unsafe { glBindTexture(GL_TEXTURE_2D, self.texture.id) };
+        self.vao.bind();
+        self.vbo.bind(BufferType::Array);
+        buffer_data(
+            BufferType::Array,
+            bytemuck::cast_slice(&data),
+            BufferUsage::Stream as u32,
+        );
+        unsafe { glDrawArrays(GL_TRIANGLES, 0, (data.len() / 4) as i32) };
+        VertexArray::clear_binding();
+        ShaderProgram::invalidate_binding();
+
+        if depth_test {
+            enable(GL_DEPTH_TEST);
+        }
+    }
+
+    /// Deletes the atlas, program, VAO and VBO
+    pub fn delete(self) {
+        self.texture.delete();
+        self.program.delete();
+        self.vao.delete();
+        self.vbo.delete();
+    }
+}
+
+impl ShaderProgram {
+    /// Makes the built in signed distance field text program used by [SdfFont::draw]
+    ///
+    /// The vertex has a vec2 position at location 0 and a vec2 uv at location 1, the uniforms
+    /// are projection, atlas, text_color, outline_color, smoothing and outline_width
+    pub fn sdf_text() -> Result<Self, String> {
+        Self::from_vert_frag(
+            include_str!("../../shaders/sdf_text_vert.glsl"),
+            include_str!("../../shaders/sdf_text_frag.glsl"),
+        )
+    }
+}
+
+/// Gets the distance of every texel of an atlas, it is the alpha channel if the image has one and
+/// the gray value otherwise
+fn distance_channel(img: DynamicImage) -> GrayImage {
+    if !img.color().has_alpha() {
+        return img.to_luma8();
+    }
+    let img = img.to_rgba8();
+    GrayImage::from_fn(img.width(), img.height(), |x, y| {
+        Luma([img.get_pixel(x, y).0[3]])
+    })
+}
+
+/// Parses the glyphs and line height of BMFont text metrics
+fn parse_metrics(metrics: &str) -> Result<(HashMap<char, Glyph>, f32), String> {
+    let mut glyphs = HashMap::new();
+    let mut line_height = None;
+
+    for (i, line) in metrics.lines().enumerate() {
+        let mut tokens = line.split_whitespace();
+        let tag = tokens.next();
+        let values: HashMap<&str, &str> =
+            tokens.filter_map(|token| token.split_once('=')).collect();
+        let get = |key: &str| -> Result<f32, String> {
+            values
+                .get(key)
+                .ok_or_else(|| format!("Line {} of the font metrics has no {}", i + 1, key))?
+                .parse::<f32>()
+                .map_err(|err| format!("Line {} of the font metrics: {}: {}", i + 1, key, err))
+        };
+
+        match tag {
+            Some("common") => line_height = Some(get("lineHeight")?),
+            Some("char") => {
+                let id = get("id")? as u32;
+                let c = char::from_u32(id).ok_or_else(|| {
+                    format!(
+                        "Line {} of the font metrics: {} isn't a character",
+                        i + 1,
+                        id
+                    )
+                })?;
+                glyphs.insert(
+                    c,
+                    Glyph {
+                        pos: vec2(get("x")?, get("y")?),
+                        size: vec2(get("width")?, get("height")?),
+                        offset: vec2(get("xoffset")?, get("yoffset")?),
+                        advance: get("xadvance")?,
+                    },
+                );
+            }
+            _ => (),
+        }
+    }
+
+    match line_height {
+        Some(line_height) => Ok((glyphs, line_height)),
+        None => Err("The font metrics have no common line with a lineHeight".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgba, RgbaImage};
+
+    #[test]
+    fn the_distance_is_read_from_the_alpha_channel() {
+        // Hiero writes white glyphs and puts the distance in alpha
+        let img = RgbaImage::from_pixel(2, 1, Rgba([255, 255, 255, 128]));
+        let distance = distance_channel(DynamicImage::ImageRgba8(img));
+        assert_eq!(distance.get_pixel(0, 0).0, [128]);
+
+        let gray = GrayImage::from_pixel(2, 1, Luma([64]));
+        let distance = distance_channel(DynamicImage::ImageLuma8(gray));
+        assert_eq!(distance.get_pixel(1, 0).0, [64]);
+    }
+}
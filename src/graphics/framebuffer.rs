@@ -0,0 +1,458 @@
+use super::{capabilities::get_integer, texture::*, *};
+
+/// A GLSL function for effect shaders that turns a depth sampled from a depth texture into the
+/// distance from the camera, it is the same as [linearize_depth]
+///
+/// # Example
+/// ```
+/// let frag = format!("#version 330 core\n{}\n{}", LINEARIZE_DEPTH_GLSL, FOG_FRAG);
+/// ```
+pub const LINEARIZE_DEPTH_GLSL: &str = "float linearize_depth(float depth, float near, float far) {
+    float z = depth * 2.0 - 1.0;
+    return 2.0 * near * far / (far + near - z * (far - near));
+}";
+
+/// Turns a depth sampled from a depth texture(0.0 to 1.0) into the distance from the camera
+/// along its view direction, near and far are the planes of the perspective projection
+pub fn linearize_depth(depth: f32, near: f32, far: f32) -> f32 {
+    let z = depth * 2.0 - 1.0;
+    2.0 * near * far / (far + near - z * (far - near))
+}
+
+/// How the pixels are filtered when [Framebuffer::blit_to] copies between rectangles of
+/// different sizes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlitFilter {
+    /// The closest pixel is copied, it is the only filter depth can be copied with
+    Nearest = GL_NEAREST as isize,
+    /// Neighbouring pixels are averaged, e.g. for downsampling and thumbnails
+    #[default]
+    Linear = GL_LINEAR as isize,
+}
+
+/// An offscreen [framebuffer](https://www.khronos.org/opengl/wiki/Framebuffer_Object) with a
+/// color texture and a depth texture that can both be sampled afterwards
+///
+/// # Example
+/// ```
+/// let shadow_map = Framebuffer::new(1024, 1024)?;
+///
+/// shadow_map.bind();
+/// clear(GL_COLOR_BUFFER_BIT | GL_DEPTH_BUFFER_BIT);
+/// draw_scene();
+/// Framebuffer::bind_default(800, 600);
+/// ```
+pub struct Framebuffer {
+    /// The framebuffer id
+    pub id: u32,
+    /// The RGBA color attachment
+    pub color: Texture,
+    /// The depth attachment
+    pub depth: Texture,
+    /// The width of the attachments
+    pub width: u32,
+    /// The height of the attachments
+    pub height: u32,
+}
+
+impl Framebuffer {
+    /// Makes a new framebuffer with an RGBA8 color texture and a 24 bit depth texture
+    pub fn new(width: u32, height: u32) -> Result<Self, String> {
+        Self::with_color_format(width, height, GL_RGBA8, GL_UNSIGNED_BYTE)
+    }
+
+    /// Makes a new framebuffer with an RGBA16F color texture and a 24 bit depth texture, the
+    /// color isn't clamped to [0, 1] so it can hold HDR light before tone mapping or bloom
+    pub fn hdr(width: u32, height: u32) -> Result<Self, String> {
+        Self::with_color_format(width, height, GL_RGBA16F, GL_FLOAT)
+    }
+
+    /// Makes a new framebuffer with an RGBA color texture of internal_format and a 24 bit depth
+    /// texture, ty is the type of the color data e.g. GL_FLOAT
+    fn with_color_format(
+        width: u32,
+        height: u32,
+        internal_format: u32,
+        ty: u32,
+    ) -> Result<Self, String> {
+        let mut id = 0;
+        unsafe { glGenFramebuffers(1, &mut id) };
+        if id == 0 {
+            return Err("Couldn't make a framebuffer".to_string());
+        }
+
+        let color = Self::attachment(width, height, internal_format, GL_RGBA, ty);
+        let depth = Self::attachment(
+            width,
+            height,
+            GL_DEPTH_COMPONENT24,
+            GL_DEPTH_COMPONENT,
+            GL_UNSIGNED_INT,
+        );
+
+        let framebuffer = Framebuffer {
+            id,
+            color,
+            depth,
+            width,
+            height,
+        };
+
+        unsafe {
+            glBindFramebuffer(GL_FRAMEBUFFER, id);
+            glFramebufferTexture2D(
+                GL_FRAMEBUFFER,
+                GL_COLOR_ATTACHMENT0,
+                GL_TEXTURE_2D,
+                framebuffer.color.id,
+                0,
+            );
+            glFramebufferTexture2D(
+                GL_FRAMEBUFFER,
+                GL_DEPTH_ATTACHMENT,
+                GL_TEXTURE_2D,
+                framebuffer.depth.id,
+                0,
+            );
+        }
+        let complete = framebuffer.is_complete();
+        unsafe { glBindFramebuffer(GL_FRAMEBUFFER, 0) };
+
+        if complete {
+            Ok(framebuffer)
+        } else {
+            framebuffer.delete();
+            Err("The framebuffer is not complete".to_string())
+        }
+    }
+
+    /// Makes a new multisampled framebuffer for anti-aliased rendering to texture
+    ///
+    /// The attachments are multisampled renderbuffers so they can't be sampled, use
+    /// [MultisampledFramebuffer::resolve_to] to copy them into a normal [Framebuffer]. samples is
+    /// clamped to GL_MAX_SAMPLES
+    ///
+    /// # Example
+    /// ```
+    /// let msaa = Framebuffer::multisampled(800, 600, 4)?;
+    /// let scene = Framebuffer::new(800, 600)?;
+    ///
+    /// msaa.bind();
+    /// draw_scene();
+    /// msaa.resolve_to(&scene)?;
+    /// scene.color.bind(GL_TEXTURE_2D);
+    /// ```
+    pub fn multisampled(
+        width: u32,
+        height: u32,
+        samples: u32,
+    ) -> Result<MultisampledFramebuffer, String> {
+        let samples = samples.clamp(1, get_integer(GL_MAX_SAMPLES).max(1) as u32);
+
+        let mut id = 0;
+        unsafe { glGenFramebuffers(1, &mut id) };
+        if id == 0 {
+            return Err("Couldn't make a framebuffer".to_string());
+        }
+
+        let framebuffer = MultisampledFramebuffer {
+            id,
+            color: MultisampledFramebuffer::attachment(width, height, samples, GL_RGBA8),
+            depth: MultisampledFramebuffer::attachment(
+                width,
+                height,
+                samples,
+                GL_DEPTH_COMPONENT24,
+            ),
+            width,
+            height,
+            samples,
+        };
+
+        unsafe {
+            glBindFramebuffer(GL_FRAMEBUFFER, id);
+            glFramebufferRenderbuffer(
+                GL_FRAMEBUFFER,
+                GL_COLOR_ATTACHMENT0,
+                GL_RENDERBUFFER,
+                framebuffer.color,
+            );
+            glFramebufferRenderbuffer(
+                GL_FRAMEBUFFER,
+                GL_DEPTH_ATTACHMENT,
+                GL_RENDERBUFFER,
+                framebuffer.depth,
+            );
+        }
+        let complete = framebuffer.is_complete();
+        unsafe { glBindFramebuffer(GL_FRAMEBUFFER, 0) };
+
+        if complete {
+            Ok(framebuffer)
+        } else {
+            framebuffer.delete();
+            Err("The multisampled framebuffer is not complete".to_string())
+        }
+    }
+
+    /// Makes an empty texture for an attachment
+    fn attachment(width: u32, height: u32, internal_format: u32, format: u32, ty: u32) -> Texture {
+        let mut texture = Texture::new();
+        texture.bind(GL_TEXTURE_2D);
+        unsafe {
+            glTexImage2D(
+                GL_TEXTURE_2D,
+                0,
+                internal_format as i32,
+                width as i32,
+                height as i32,
+                0,
+                format,
+                ty,
+                std::ptr::null(),
+            );
+            glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MIN_FILTER, GL_LINEAR as i32);
+            glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MAG_FILTER, GL_LINEAR as i32);
+            glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_S, GL_CLAMP_TO_EDGE as i32);
+            glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_T, GL_CLAMP_TO_EDGE as i32);
+        }
+        Texture::unbind(GL_TEXTURE_2D);
+        texture
+    }
+
+    /// Checks if the framebuffer can be drawn to, it must be bound
+    pub fn is_complete(&self) -> bool {
+        unsafe { glCheckFramebufferStatus(GL_FRAMEBUFFER) == GL_FRAMEBUFFER_COMPLETE }
+    }
+
+    /// Binds the framebuffer so everything is drawn into it and sets the viewport to its size
+    pub fn bind(&self) {
+        unsafe {
+            glBindFramebuffer(GL_FRAMEBUFFER, self.id);
+            glViewport(0, 0, self.width as i32, self.height as i32);
+        }
+    }
+
+    /// Binds the window so everything is drawn to the screen again and sets the viewport to
+    /// the size of the window
+    pub fn bind_default(width: u32, height: u32) {
+        unsafe {
+            glBindFramebuffer(GL_FRAMEBUFFER, 0);
+            glViewport(0, 0, width as i32, height as i32);
+        }
+    }
+
+    /// Binds the depth texture to a texture unit e.g. GL_TEXTURE1 so a shader can sample it,
+    /// the active texture unit is left as texture_unit
+    ///
+    /// The framebuffer must not be bound while it is sampled
+    pub fn bind_depth(&self, texture_unit: u32) {
+        Texture::set_tex_unit(texture_unit);
+        unsafe { glBindTexture(GL_TEXTURE_2D, self.depth.id) };
+    }
+
+    /// Copies the color texture to the window and binds the window with
+    /// [Framebuffer::bind_default], the color is scaled linearly if the sizes are different
+    pub fn blit_to_default(&self, width: u32, height: u32) {
+        unsafe {
+            glBindFramebuffer(GL_READ_FRAMEBUFFER, self.id);
+            glBindFramebuffer(GL_DRAW_FRAMEBUFFER, 0);
+            glBlitFramebuffer(
+                0,
+                0,
+                self.width as i32,
+                self.height as i32,
+                0,
+                0,
+                width as i32,
+                height as i32,
+                GL_COLOR_BUFFER_BIT,
+                GL_LINEAR,
+            );
+        }
+        Self::bind_default(width, height);
+    }
+
+    /// Copies a rectangle of the color texture into a rectangle of the color texture of dst with
+    /// glBlitFramebuffer, the pixels are scaled with filter if the rectangles are different
+    /// sizes. The rectangles are (x, y, width, height), the bound framebuffers are restored
+    /// afterwards
+    ///
+    /// # Example
+    /// ```
+    /// // a quarter size thumbnail of the scene
+    /// let thumbnail = Framebuffer::new(200, 150)?;
+    /// scene.blit_to(&thumbnail, (0, 0, 800, 600), (0, 0, 200, 150), BlitFilter::Linear);
+    /// ```
+    pub fn blit_to(&self, dst: &Framebuffer, src_rect: Rect, dst_rect: Rect, filter: BlitFilter) {
+        self.blit(dst, src_rect, dst_rect, GL_COLOR_BUFFER_BIT, filter);
+    }
+
+    /// Copies a rectangle of the depth texture into a rectangle of the depth texture of dst like
+    /// [Framebuffer::blit_to], depth can't be averaged so it is always copied with
+    /// [BlitFilter::Nearest]
+    pub fn blit_depth_to(&self, dst: &Framebuffer, src_rect: Rect, dst_rect: Rect) {
+        self.blit(
+            dst,
+            src_rect,
+            dst_rect,
+            GL_DEPTH_BUFFER_BIT,
+            BlitFilter::Nearest,
+        );
+    }
+
+    fn blit(
+        &self,
+        dst: &Framebuffer,
+        src_rect: Rect,
+        dst_rect: Rect,
+        mask: u32,
+        filter: BlitFilter,
+    ) {
+        let read = get_integer(GL_READ_FRAMEBUFFER_BINDING);
+        let draw = get_integer(GL_DRAW_FRAMEBUFFER_BINDING);
+        let (src_x, src_y, src_width, src_height) = src_rect;
+        let (dst_x, dst_y, dst_width, dst_height) = dst_rect;
+        unsafe {
+            glBindFramebuffer(GL_READ_FRAMEBUFFER, self.id);
+            glBindFramebuffer(GL_DRAW_FRAMEBUFFER, dst.id);
+            crate::gl_check!(glBlitFramebuffer(
+                src_x,
+                src_y,
+                src_x + src_width,
+                src_y + src_height,
+                dst_x,
+                dst_y,
+                dst_x + dst_width,
+                dst_y + dst_height,
+                mask,
+                filter as u32,
+            ));
+            glBindFramebuffer(GL_READ_FRAMEBUFFER, read as u32);
+            glBindFramebuffer(GL_DRAW_FRAMEBUFFER, draw as u32);
+        }
+    }
+
+    /// Deletes the framebuffer and its textures
+    pub fn delete(&self) {
+        self.color.delete();
+        self.depth.delete();
+        unsafe { glDeleteFramebuffers(1, &self.id) }
+    }
+}
+
+/// A [framebuffer](https://www.khronos.org/opengl/wiki/Framebuffer_Object) with multisampled
+/// color and depth renderbuffers, made with [Framebuffer::multisampled]
+pub struct MultisampledFramebuffer {
+    /// The framebuffer id
+    pub id: u32,
+    /// The RGBA8 color renderbuffer
+    pub color: u32,
+    /// The depth renderbuffer
+    pub depth: u32,
+    /// The width of the attachments
+    pub width: u32,
+    /// The height of the attachments
+    pub height: u32,
+    /// How many samples every pixel has
+    pub samples: u32,
+}
+
+impl MultisampledFramebuffer {
+    /// Makes an empty multisampled renderbuffer for an attachment
+    fn attachment(width: u32, height: u32, samples: u32, internal_format: u32) -> u32 {
+        let mut renderbuffer = 0;
+        unsafe {
+            glGenRenderbuffers(1, &mut renderbuffer);
+            glBindRenderbuffer(GL_RENDERBUFFER, renderbuffer);
+            glRenderbufferStorageMultisample(
+                GL_RENDERBUFFER,
+                samples as i32,
+                internal_format,
+                width as i32,
+                height as i32,
+            );
+            glBindRenderbuffer(GL_RENDERBUFFER, 0);
+        }
+        renderbuffer
+    }
+
+    /// Checks if the framebuffer can be drawn to, it must be bound
+    pub fn is_complete(&self) -> bool {
+        unsafe { glCheckFramebufferStatus(GL_FRAMEBUFFER) == GL_FRAMEBUFFER_COMPLETE }
+    }
+
+    /// Binds the framebuffer so everything is drawn into it and sets the viewport to its size
+    pub fn bind(&self) {
+        unsafe {
+            glBindFramebuffer(GL_FRAMEBUFFER, self.id);
+            glViewport(0, 0, self.width as i32, self.height as i32);
+        }
+    }
+
+    /// Averages the samples of every pixel into the color texture of target with
+    /// glBlitFramebuffer
+    ///
+    /// target must be the same size, a multisampled framebuffer can't be resolved and scaled at
+    /// the same time. The framebuffer bindings are reset to the default framebuffer afterwards
+    pub fn resolve_to(&self, target: &Framebuffer) -> Result<(), String> {
+        if (self.width, self.height) != (target.width, target.height) {
+            return Err(format!(
+                "Can't resolve a {}x{} framebuffer into a {}x{} one",
+                self.width, self.height, target.width, target.height
+            ));
+        }
+
+        unsafe {
+            glBindFramebuffer(GL_READ_FRAMEBUFFER, self.id);
+            glBindFramebuffer(GL_DRAW_FRAMEBUFFER, target.id);
+            glBlitFramebuffer(
+                0,
+                0,
+                self.width as i32,
+                self.height as i32,
+                0,
+                0,
+                target.width as i32,
+                target.height as i32,
+                GL_COLOR_BUFFER_BIT,
+                GL_NEAREST,
+            );
+            glBindFramebuffer(GL_FRAMEBUFFER, 0);
+        }
+        Ok(())
+    }
+
+    /// Deletes the framebuffer and its renderbuffers
+    pub fn delete(&self) {
+        unsafe {
+            glDeleteRenderbuffers(1, &self.color);
+            glDeleteRenderbuffers(1, &self.depth);
+            glDeleteFramebuffers(1, &self.id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra_glm::{perspective, vec4};
+
+    #[test]
+    fn linearized_depth_is_the_distance_that_was_projected() {
+        let (near, far) = (0.1, 100.0);
+        let projection = perspective(1.0, 45.0_f32.to_radians(), near, far);
+        for distance in [near, 0.5, 1.0, 10.0, 50.0, far] {
+            let clip = projection * vec4(0.0, 0.0, -distance, 1.0);
+            let depth = clip.z / clip.w * 0.5 + 0.5;
+            let linear = linearize_depth(depth, near, far);
+            assert!(
+                (linear - distance).abs() < distance * 1e-3,
+                "{linear} != {distance}"
+            );
+        }
+        assert!((linearize_depth(0.0, near, far) - near).abs() < 1e-6);
+        // far + near - (far - near) loses precision so far is only close
+        assert!((linearize_depth(1.0, near, far) - far).abs() < far * 1e-3);
+    }
+}
@@ -0,0 +1,59 @@
+use super::{shader::*, vertex::*, *};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+static FULLSCREEN_VAO: AtomicU32 = AtomicU32::new(0);
+
+/// The source of the built in fullscreen vertex shader
+///
+/// It dosen't take any vertex attributes, the positions are made from gl_VertexID and it
+/// outputs a vec2 called uv that goes from (0, 0) in the bottom left to (1, 1) in the top right
+pub const FULLSCREEN_VERT: &str = include_str!("../../shaders/fullscreen_vert.glsl");
+
+/// Draws a single triangle that covers the whole viewport with shader, e.g. for post-processing
+///
+/// The triangle is bigger than the screen so there is no seam down the middle like with a quad.
+/// It is drawn with an empty cached VAO so no vertex buffer is needed, the vertex shader should
+/// be [FULLSCREEN_VERT], see [ShaderProgram::fullscreen]
+///
+/// # Example
+/// ```
+/// let blur = ShaderProgram::fullscreen(include_str!("blur_frag.glsl"))?;
+///
+/// Framebuffer::bind_default(800, 600);
+/// scene.color.bind(GL_TEXTURE_2D);
+/// fullscreen_pass(&blur);
+/// ```
+pub fn fullscreen_pass(shader: &ShaderProgram) {
+    shader.use_program();
+    fullscreen_vao().bind();
+    unsafe { glDrawArrays(GL_TRIANGLES, 0, 3) };
+    VertexArray::clear_binding();
+}
+
+/// Gets the empty VAO used by [fullscreen_pass], it is made the first time it is needed
+fn fullscreen_vao() -> VertexArray {
+    let vao = FULLSCREEN_VAO.load(Ordering::Relaxed);
+    if vao != 0 {
+        return VertexArray(vao);
+    }
+    let vao = VertexArray::new().expect("Couldn't make the fullscreen VAO");
+    FULLSCREEN_VAO.store(vao.0, Ordering::Relaxed);
+    vao
+}
+
+/// Deletes the cached VAO used by [fullscreen_pass], it will be made again if it is needed
+pub fn delete_fullscreen_vao() {
+    let vao = FULLSCREEN_VAO.swap(0, Ordering::Relaxed);
+    if vao != 0 {
+        VertexArray(vao).delete();
+    }
+}
+
+impl ShaderProgram {
+    /// Makes a program for [fullscreen_pass] from a fragment shader and [FULLSCREEN_VERT]
+    ///
+    /// The fragment shader gets the vec2 uv from the vertex shader
+    pub fn fullscreen(frag: &str) -> Result<Self, String> {
+        Self::from_vert_frag(FULLSCREEN_VERT, frag)
+    }
+}
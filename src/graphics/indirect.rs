@@ -0,0 +1,110 @@
+use std::mem::size_of;
+
+use super::{buffer::*, extension::*, *};
+
+/// A single draw of [glMultiDrawElementsIndirect], the layout is defined by opengl
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DrawElementsIndirectCommand {
+    /// How many indicies to draw
+    pub count: u32,
+    /// How many instances to draw
+    pub instance_count: u32,
+    /// The first index to draw in the element array buffer
+    pub first_index: u32,
+    /// Added to every index before fetching the vertex
+    pub base_vertex: i32,
+    /// The first instance, offsets instanced attributes
+    pub base_instance: u32,
+}
+
+unsafe impl bytemuck::Zeroable for DrawElementsIndirectCommand {}
+unsafe impl bytemuck::Pod for DrawElementsIndirectCommand {}
+
+/// A buffer of [DrawElementsIndirectCommand] so that many objects can be drawn in one call
+///
+/// NOTE: it needs OpenGL 4.3 or GL_ARB_multi_draw_indirect and [load_extensions_with]
+///
+/// # Example
+/// ```
+/// let mut commands = DrawIndirectBuffer::new().unwrap();
+/// commands.push(DrawElementsIndirectCommand { count: 12, instance_count: 1, ..Default::default() });
+/// commands.push(DrawElementsIndirectCommand { count: 6, instance_count: 1, first_index: 12, base_vertex: 5, base_instance: 0 });
+/// commands.upload();
+///
+/// // in GameObjectTrait::draw
+/// commands.draw_indirect(2)?;
+/// ```
+pub struct DrawIndirectBuffer {
+    /// The commands, call [DrawIndirectBuffer::upload] after changing them
+    pub commands: Vec<DrawElementsIndirectCommand>,
+    /// How many commands the buffer on the gpu has
+    uploaded: usize,
+    buffer: Buffer,
+}
+
+impl DrawIndirectBuffer {
+    /// Makes a new empty indirect buffer
+    pub fn new() -> Option<Self> {
+        Some(DrawIndirectBuffer {
+            commands: Vec::new(),
+            uploaded: 0,
+            buffer: Buffer::new()?,
+        })
+    }
+
+    /// Adds a command, it won't be drawn until [DrawIndirectBuffer::upload] is called
+    pub fn push(&mut self, command: DrawElementsIndirectCommand) {
+        self.commands.push(command);
+    }
+
+    /// Binds the buffer to GL_DRAW_INDIRECT_BUFFER
+    pub fn bind(&self) {
+        unsafe { glBindBuffer(GL_DRAW_INDIRECT_BUFFER, self.buffer.0) }
+    }
+
+    /// Sends the commands to the gpu
+    pub fn upload(&mut self) {
+        self.bind();
+        let data: &[u8] = bytemuck::cast_slice(&self.commands);
+        unsafe {
+            glBufferData(
+                GL_DRAW_INDIRECT_BUFFER,
+                data.len().try_into().unwrap(),
+                data.as_ptr().cast(),
+                GL_DYNAMIC_DRAW,
+            )
+        }
+        self.uploaded = self.commands.len();
+    }
+
+    /// How many commands were sent to the gpu by the last [DrawIndirectBuffer::upload]
+    pub fn uploaded(&self) -> usize {
+        self.uploaded
+    }
+
+    /// Draws the first count commands as triangles in a single call
+    ///
+    /// The vertex array and element array buffer of the objects must be bound. It is an error
+    /// to draw more commands than were uploaded, commands that were pushed since the last
+    /// [DrawIndirectBuffer::upload] aren't on the gpu yet
+    pub fn draw_indirect(&self, count: usize) -> Result<(), String> {
+        if count > self.uploaded {
+            return Err(format!(
+                "Cannot draw {} indirect commands, only {} were uploaded",
+                count, self.uploaded
+            ));
+        }
+        self.bind();
+        unsafe {
+            glMultiDrawElementsIndirect(
+                GL_TRIANGLES,
+                GL_UNSIGNED_INT,
+                std::ptr::null(),
+                count.try_into().unwrap(),
+                size_of::<DrawElementsIndirectCommand>().try_into().unwrap(),
+            )
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,59 @@
+use super::{shader::*, uniform::*};
+use nalgebra_glm::{vec3, Vec3};
+
+/// A point light that matches the GLSL struct
+///
+/// ```glsl
+/// struct Light {
+///     vec3 pos;
+///     vec3 color;
+///     float intensity;
+/// };
+/// uniform Light lights[8];
+/// uniform int lights_count;
+/// ```
+///
+/// # Example
+/// ```
+/// let lights = [
+///     Light::new(vec3(0.0, 5.0, 0.0), vec3(1.0, 1.0, 0.9), 1.0),
+///     Light::new(vec3(3.0, 1.0, 2.0), vec3(1.0, 0.5, 0.2), 0.5),
+/// ];
+/// program.use_program();
+/// Uniform::set_struct_array(&program, "lights", &lights);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Light {
+    /// The position of the light in world space
+    pub pos: Vec3,
+    /// The color of the light
+    pub color: Vec3,
+    /// How bright the light is, the color is multiplied by it
+    pub intensity: f32,
+}
+
+impl Light {
+    /// Creates a new light
+    pub fn new(pos: Vec3, color: Vec3, intensity: f32) -> Self {
+        Light {
+            pos,
+            color,
+            intensity,
+        }
+    }
+}
+
+impl Default for Light {
+    /// A white light at the origin
+    fn default() -> Self {
+        Self::new(Vec3::zeros(), vec3(1.0, 1.0, 1.0), 1.0)
+    }
+}
+
+impl UniformStruct for Light {
+    fn upload(&self, program: &ShaderProgram, name: &str) {
+        set_field(program, name, "pos", self.pos.as_slice());
+        set_field(program, name, "color", self.color.as_slice());
+        set_field(program, name, "intensity", &[self.intensity]);
+    }
+}
@@ -0,0 +1,182 @@
+use super::{cubemap::*, shader::*, texture::Texture, uniform::*, *};
+use nalgebra_glm::{Vec3, Vec4};
+
+/// A texture of a [Material], the environment cubemap always uses GL_TEXTURE0 so the slots
+/// start at GL_TEXTURE1
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TextureSlot {
+    /// The color of the surface, the sampler is called diffuse_map
+    Diffuse,
+    /// The normals of the surface, the sampler is called normal_map
+    Normal,
+    /// How shiny the surface is, the sampler is called specular_map
+    Specular,
+    /// Light given off by the surface, the sampler is called emissive_map
+    Emissive,
+}
+
+impl TextureSlot {
+    /// Every slot in the order of their texture units
+    pub const ALL: [TextureSlot; 4] = [
+        TextureSlot::Diffuse,
+        TextureSlot::Normal,
+        TextureSlot::Specular,
+        TextureSlot::Emissive,
+    ];
+
+    /// The texture unit the slot is bound to e.g. GL_TEXTURE1
+    pub fn texture_unit(&self) -> u32 {
+        GL_TEXTURE1 + *self as u32
+    }
+
+    /// The name of the sampler uniform of the slot
+    pub fn sampler(&self) -> &'static str {
+        match self {
+            TextureSlot::Diffuse => "diffuse_map",
+            TextureSlot::Normal => "normal_map",
+            TextureSlot::Specular => "specular_map",
+            TextureSlot::Emissive => "emissive_map",
+        }
+    }
+}
+
+/// How the surface of a mesh looks, it is drawn with [ShaderProgram::reflective]
+///
+/// The base color is mixed with the reflection of the environment cubemap by reflectivity, so a
+/// reflectivity of 0 is a flat color and 1 is a perfect mirror
+///
+/// # Example
+/// ```
+/// let program = ShaderProgram::reflective()?;
+/// let chrome = Material::new(vec4(0.8, 0.8, 0.8, 1.0))
+///     .reflectivity(0.9)
+///     .environment(sky);
+///
+/// program.use_program();
+/// chrome.apply(&program, world.objects.get_camera().get_pos());
+/// transform.upload(&program, false);
+/// mesh.draw();
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Material {
+    /// The color of the surface in rgba
+    pub base_color: Vec4,
+    /// How much of the environment is reflected from 0 to 1
+    pub reflectivity: f32,
+    /// The cubemap that is reflected, nothing is reflected if it is None
+    pub environment: Option<Cubemap>,
+    /// When true back faces aren't culled and are shaded with the flipped normal, e.g. for
+    /// leaves and flags. It is false by default, see [Material::two_sided]
+    pub two_sided: bool,
+    /// The ids of the textures in every [TextureSlot], see [Material::set_texture]
+    textures: [Option<u32>; 4],
+}
+
+impl Material {
+    /// Makes a material with a base color that dosen't reflect anything
+    pub fn new(base_color: Vec4) -> Self {
+        Material {
+            base_color,
+            reflectivity: 0.0,
+            environment: None,
+            two_sided: false,
+            textures: [None; 4],
+        }
+    }
+
+    /// Sets how much of the environment is reflected, it is clamped between 0 and 1
+    pub fn reflectivity(mut self, reflectivity: f32) -> Self {
+        self.reflectivity = reflectivity.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Sets the cubemap that is reflected
+    pub fn environment(mut self, environment: Cubemap) -> Self {
+        self.environment = Some(environment);
+        self
+    }
+
+    /// Sets if back faces are drawn and shaded with the flipped normal
+    ///
+    /// Culling is only turned off by [World::draw_mesh](crate::ECS::world::World::draw_mesh),
+    /// when the material is applied by hand GL_CULL_FACE has to be disabled by hand too
+    pub fn two_sided(mut self, two_sided: bool) -> Self {
+        self.two_sided = two_sided;
+        self
+    }
+
+    /// Puts a texture in a slot, replacing the one that was there. It takes effect the next time
+    /// the material is applied so meshes using it don't have to be rebuilt, e.g. for damage
+    /// states
+    ///
+    /// Only the id of the texture is kept so it must not be deleted while the material uses it
+    pub fn set_texture(&mut self, slot: TextureSlot, texture: &Texture) {
+        self.textures[slot as usize] = Some(texture.id);
+    }
+
+    /// Empties a slot
+    pub fn remove_texture(&mut self, slot: TextureSlot) {
+        self.textures[slot as usize] = None;
+    }
+
+    /// Gets the id of the texture in a slot
+    pub fn get_texture(&self, slot: TextureSlot) -> Option<u32> {
+        self.textures[slot as usize]
+    }
+
+    /// Sets the uniforms of program, binds the environment to GL_TEXTURE0 and the textures to
+    /// the units of their slots, program must be in use. The active texture unit is left as
+    /// GL_TEXTURE0
+    ///
+    /// camera_pos is the position of the camera in world space, the reflection depends on it
+    pub fn apply(&self, program: &ShaderProgram, camera_pos: &Vec3) {
+        Texture::set_tex_unit(GL_TEXTURE0);
+        let reflectivity = match self.environment {
+            Some(environment) => {
+                environment.bind();
+                self.reflectivity
+            }
+            None => 0.0,
+        };
+
+        let set = |name: &str, values: &[f32]| {
+            if let Some(uniform) = Uniform::find(program, name) {
+                uniform.set_uniform_f(values);
+            }
+        };
+        set("base_color", self.base_color.as_slice());
+        set("reflectivity", &[reflectivity]);
+        set("camera_pos", camera_pos.as_slice());
+        if let Some(uniform) = Uniform::find(program, "environment") {
+            uniform.set_uniform_i(&[0]);
+        }
+        if let Some(uniform) = Uniform::find(program, "two_sided") {
+            uniform.set_uniform_i(&[self.two_sided as i32]);
+        }
+
+        for slot in TextureSlot::ALL {
+            if let Some(texture) = self.get_texture(slot) {
+                Texture::set_tex_unit(slot.texture_unit());
+                unsafe { glBindTexture(GL_TEXTURE_2D, texture) };
+                if let Some(uniform) = Uniform::find(program, slot.sampler()) {
+                    uniform.set_uniform_i(&[(slot.texture_unit() - GL_TEXTURE0) as i32]);
+                }
+            }
+        }
+        Texture::set_tex_unit(GL_TEXTURE0);
+    }
+}
+
+impl ShaderProgram {
+    /// Makes the built in reflective program used by [Material]
+    ///
+    /// The vertex has a vec3 position at location 0 and a vec3 normal at location 1. The camera
+    /// matrix uniform is called camera_matrix and the model matrix u_model, see
+    /// [Transform::upload](crate::ECS::transform::Transform::upload)
+    pub fn reflective() -> Result<Self, String> {
+        Self::from_vert_frag(
+            include_str!("../../shaders/reflect_vert.glsl"),
+            include_str!("../../shaders/reflect_frag.glsl"),
+        )
+    }
+}
@@ -0,0 +1,94 @@
+use super::{compressed::*, *};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+/// The bytes of every live mesh, by the id of its VAO
+static MESH_MEMORY: Mutex<BTreeMap<u32, usize>> = Mutex::new(BTreeMap::new());
+/// The estimated bytes of every live texture, by its id
+static TEXTURE_MEMORY: Mutex<BTreeMap<u32, usize>> = Mutex::new(BTreeMap::new());
+
+/// How many bits a pixel of an internal format takes, e.g. 32 for GL_RGBA8
+///
+/// Compressed formats are averaged over their 4x4 blocks so DXT1 is 4 bits, formats that aren't
+/// known are assumed to be 32 bits
+pub fn bits_per_pixel(internal_format: u32) -> usize {
+    match internal_format {
+        GL_R8 | GL_RED => 8,
+        GL_RG8 | GL_R16F | GL_DEPTH_COMPONENT16 => 16,
+        GL_RGB8 | GL_SRGB8 | GL_RGB => 24,
+        GL_RG16F
+        | GL_R32F
+        | GL_DEPTH_COMPONENT24
+        | GL_DEPTH_COMPONENT32F
+        | GL_DEPTH24_STENCIL8
+        | GL_R11F_G11F_B10F => 32,
+        GL_RGB16F => 48,
+        GL_RGBA16F | GL_RG32F | GL_DEPTH32F_STENCIL8 => 64,
+        GL_RGB32F => 96,
+        GL_RGBA32F => 128,
+        GL_COMPRESSED_RGB_S3TC_DXT1_EXT
+        | GL_COMPRESSED_RGBA_S3TC_DXT1_EXT
+        | GL_COMPRESSED_RED_RGTC1
+        | GL_COMPRESSED_SIGNED_RED_RGTC1
+        | GL_COMPRESSED_RGB8_ETC2
+        | GL_COMPRESSED_SRGB8_ETC2 => 4,
+        GL_COMPRESSED_RGBA_S3TC_DXT3_EXT
+        | GL_COMPRESSED_RGBA_S3TC_DXT5_EXT
+        | GL_COMPRESSED_RG_RGTC2
+        | GL_COMPRESSED_SIGNED_RG_RGTC2
+        | GL_COMPRESSED_RGBA_BPTC_UNORM
+        | GL_COMPRESSED_SRGB_ALPHA_BPTC_UNORM
+        | GL_COMPRESSED_RGBA8_ETC2_EAC
+        | GL_COMPRESSED_SRGB8_ALPHA8_ETC2_EAC => 8,
+        _ => 32,
+    }
+}
+
+/// Estimates the bytes a 2D texture takes, mip_levels counts the base level so a texture without
+/// mipmaps has 1. Every level is half the size of the one before it, but at least 1x1
+pub fn texture_memory_bytes(
+    width: u32,
+    height: u32,
+    internal_format: u32,
+    mip_levels: u32,
+) -> usize {
+    let bits: usize = (0..mip_levels)
+        .map(|level| {
+            let width = (width >> level).max(1) as usize;
+            let height = (height >> level).max(1) as usize;
+            width * height * bits_per_pixel(internal_format)
+        })
+        .sum();
+    bits / 8
+}
+
+/// Records how many bytes the buffers of the mesh with this VAO take, it replaces the last value
+pub fn track_mesh(vao: u32, bytes: usize) {
+    MESH_MEMORY.lock().unwrap().insert(vao, bytes);
+}
+
+/// Stops counting the mesh with this VAO, e.g. when it is deleted
+pub fn forget_mesh(vao: u32) {
+    MESH_MEMORY.lock().unwrap().remove(&vao);
+}
+
+/// Records how many bytes the texture with this id takes, it replaces the last value
+pub fn track_texture(id: u32, bytes: usize) {
+    TEXTURE_MEMORY.lock().unwrap().insert(id, bytes);
+}
+
+/// Stops counting the texture with this id, e.g. when it is deleted
+pub fn forget_texture(id: u32) {
+    TEXTURE_MEMORY.lock().unwrap().remove(&id);
+}
+
+/// The bytes the vertex and index buffers of all the live meshes take
+pub fn mesh_memory() -> usize {
+    MESH_MEMORY.lock().unwrap().values().sum()
+}
+
+/// The estimated bytes all the live textures take, only textures uploaded through
+/// [Texture](super::texture::Texture) are counted
+pub fn texture_memory() -> usize {
+    TEXTURE_MEMORY.lock().unwrap().values().sum()
+}
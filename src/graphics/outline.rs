@@ -0,0 +1,80 @@
+use super::{shader::*, uniform::*, *};
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+
+/// Draws a solid color outline around an object using the stencil buffer, e.g. for selection
+///
+/// The object is drawn normally while writing 1 into the stencil buffer, then it is drawn again
+/// slightly scaled up in the outline color only where the stencil buffer isn't 1
+///
+/// NOTE: the window must have a stencil buffer, see SdlGlAttr::StencilSize
+///
+/// # Example
+/// ```
+/// let outline = Outline::new(vec4(1.0, 0.6, 0.0, 1.0), 1.05)?;
+///
+/// // in GameObjectTrait::draw
+/// outline.draw(&world.env.shader_program, &camera_matrix, pyramid.pos, || unsafe {
+///     glDrawElements(GL_TRIANGLES, 12, GL_UNSIGNED_INT, std::ptr::null());
+/// });
+/// ```
+pub struct Outline {
+    /// The color of the outline
+    pub color: Vec4,
+    /// How much bigger the outline is than the object, e.g. 1.05
+    pub scale: f32,
+    program: ShaderProgram,
+}
+
+impl Outline {
+    /// Creates a new outline and compiles the built in outline program
+    pub fn new(color: Vec4, scale: f32) -> Result<Self, String> {
+        Ok(Outline {
+            color,
+            scale,
+            program: ShaderProgram::from_vert_frag(
+                include_str!("../../shaders/outline_vert.glsl"),
+                include_str!("../../shaders/outline_frag.glsl"),
+            )?,
+        })
+    }
+
+    /// Draws the object with shader_program then draws its outline
+    ///
+    /// draw must issue the draw calls of the object, it is called once per pass. The outline is
+    /// scaled around center and the stencil buffer is cleared before drawing
+    pub fn draw<F: Fn()>(
+        &self,
+        shader_program: &ShaderProgram,
+        camera_matrix: &Mat4,
+        center: Vec3,
+        draw: F,
+    ) {
+        enable(GL_STENCIL_TEST);
+        stencil_mask(0xFF);
+        clear(GL_STENCIL_BUFFER_BIT);
+
+        // write 1 wherever the object is drawn
+        stencil_func(GL_ALWAYS, 1, 0xFF);
+        stencil_op(GL_KEEP, GL_KEEP, GL_REPLACE);
+        shader_program.use_program();
+        draw();
+
+        // draw the scaled object only outside of the object
+        stencil_func(GL_NOTEQUAL, 1, 0xFF);
+        stencil_mask(0x00);
+        disable(GL_DEPTH_TEST);
+        self.program.use_program();
+        Uniform::new(&self.program, "camera_matrix")
+            .set_uniform_matrix(false, (*camera_matrix).into());
+        Uniform::new(&self.program, "outline_center").set_uniform_f(center.as_slice());
+        Uniform::new(&self.program, "outline_scale").set_uniform_f(&[self.scale]);
+        Uniform::new(&self.program, "outline_color").set_uniform_f(self.color.as_slice());
+        draw();
+
+        stencil_mask(0xFF);
+        stencil_func(GL_ALWAYS, 0, 0xFF);
+        enable(GL_DEPTH_TEST);
+        disable(GL_STENCIL_TEST);
+        shader_program.use_program();
+    }
+}
@@ -0,0 +1,208 @@
+use super::{fullscreen::*, shader::*, texture::*, uniform::*, *};
+
+/// How many colors a [Palette] has
+pub const PALETTE_SIZE: usize = 256;
+
+/// A 256x1 RGBA texture used to look up the colors of an [IndexedTexture]
+///
+/// Swapping palettes at runtime is done with [Palette::set_colors] or by binding another palette
+///
+/// # Example
+/// ```
+/// let mut day = Palette::new(&[[0, 0, 0, 255], [90, 160, 255, 255]])?;
+/// let night = [[0, 0, 0, 255], [20, 20, 80, 255]];
+///
+/// // when the sun goes down
+/// day.set_colors(0, &night)?;
+/// ```
+pub struct Palette {
+    /// The 256x1 palette texture
+    pub texture: Texture,
+    colors: [[u8; 4]; PALETTE_SIZE],
+}
+
+impl Palette {
+    /// Makes a new palette, colors that aren't given are transparent black
+    pub fn new(colors: &[[u8; 4]]) -> Result<Self, TextureError> {
+        if colors.len() > PALETTE_SIZE {
+            return Err(TextureError::OutOfBounds(format!(
+                "A palette can only have {} colors but got {}",
+                PALETTE_SIZE,
+                colors.len()
+            )));
+        }
+
+        let mut palette = Palette {
+            texture: Texture::new(),
+            colors: [[0; 4]; PALETTE_SIZE],
+        };
+        palette.colors[..colors.len()].copy_from_slice(colors);
+
+        palette.texture.bind(GL_TEXTURE_2D);
+        unsafe {
+            glTexImage2D(
+                GL_TEXTURE_2D,
+                0,
+                GL_RGBA8 as i32,
+                PALETTE_SIZE as i32,
+                1,
+                0,
+                GL_RGBA,
+                GL_UNSIGNED_BYTE,
+                to_carray(palette.colors.as_flattened()).cast(),
+            );
+        }
+        nearest_clamped();
+        Texture::unbind(GL_TEXTURE_2D);
+
+        Ok(palette)
+    }
+
+    /// Gets the color at index
+    pub fn get_color(&self, index: u8) -> [u8; 4] {
+        self.colors[index as usize]
+    }
+
+    /// Changes the color at index
+    pub fn set_color(&mut self, index: u8, color: [u8; 4]) {
+        // a single color always fits so this can't fail
+        let _ = self.set_colors(index, &[color]);
+    }
+
+    /// Replaces the colors starting at start and uploads them, e.g. to swap to another palette
+    pub fn set_colors(&mut self, start: u8, colors: &[[u8; 4]]) -> Result<(), TextureError> {
+        let start = start as usize;
+        if start + colors.len() > PALETTE_SIZE {
+            return Err(TextureError::OutOfBounds(format!(
+                "{} colors starting at {} don't fit in the palette",
+                colors.len(),
+                start
+            )));
+        }
+        self.colors[start..start + colors.len()].copy_from_slice(colors);
+
+        self.texture.bind(GL_TEXTURE_2D);
+        let result = self.texture.update_region(
+            start as u32,
+            0,
+            colors.len() as u32,
+            1,
+            colors.as_flattened(),
+        );
+        Texture::unbind(GL_TEXTURE_2D);
+        result
+    }
+
+    /// Deletes the palette texture
+    pub fn delete(&self) {
+        self.texture.delete();
+    }
+}
+
+/// An R8 texture where every texel is an index into a [Palette], for retro and palette swap
+/// effects
+///
+/// # Example
+/// ```
+/// let program = ShaderProgram::palette_lookup()?;
+/// let mut sprite = IndexedTexture::from_indices(2, 2, &[0, 1, 1, 0])?;
+/// let mut palette = Palette::new(&[[255, 0, 0, 255], [0, 0, 255, 255]])?;
+///
+/// sprite.draw(&mut palette, &program);
+/// ```
+pub struct IndexedTexture {
+    /// The R8 index texture
+    pub texture: Texture,
+}
+
+impl IndexedTexture {
+    /// Makes an index texture from raw indices, the first row is the bottom of the texture
+    pub fn from_indices(width: u32, height: u32, indices: &[u8]) -> Result<Self, TextureError> {
+        if indices.len() != (width * height) as usize {
+            return Err(TextureError::OutOfBounds(format!(
+                "A {}x{} index texture needs {} indices but got {}",
+                width,
+                height,
+                width * height,
+                indices.len()
+            )));
+        }
+
+        let mut texture = Texture::new();
+        texture.bind(GL_TEXTURE_2D);
+        unsafe {
+            // rows of a single channel image aren't always 4 byte aligned
+            glPixelStorei(GL_UNPACK_ALIGNMENT, 1);
+            glTexImage2D(
+                GL_TEXTURE_2D,
+                0,
+                GL_R8 as i32,
+                width as i32,
+                height as i32,
+                0,
+                GL_RED,
+                GL_UNSIGNED_BYTE,
+                to_carray(indices).cast(),
+            );
+            glPixelStorei(GL_UNPACK_ALIGNMENT, 4);
+        }
+        nearest_clamped();
+        Texture::unbind(GL_TEXTURE_2D);
+
+        Ok(IndexedTexture { texture })
+    }
+
+    /// Makes an index texture from a grayscale image, the brightness of each pixel is its index
+    pub fn from_image(img: DynamicImage) -> Self {
+        let mut texture = Texture::new();
+        texture.bind(GL_TEXTURE_2D);
+        texture.tex_2d_r8(0, img);
+        nearest_clamped();
+        Texture::unbind(GL_TEXTURE_2D);
+        IndexedTexture { texture }
+    }
+
+    /// Binds the index texture to GL_TEXTURE0 and the palette to GL_TEXTURE1
+    pub fn bind(&mut self, palette: &mut Palette) {
+        Texture::set_tex_unit(GL_TEXTURE0);
+        self.texture.bind(GL_TEXTURE_2D);
+        Texture::set_tex_unit(GL_TEXTURE1);
+        palette.texture.bind(GL_TEXTURE_2D);
+        Texture::set_tex_unit(GL_TEXTURE0);
+    }
+
+    /// Draws the texture over the whole viewport with the colors of palette
+    ///
+    /// program should be made with [ShaderProgram::palette_lookup]
+    pub fn draw(&mut self, palette: &mut Palette, program: &ShaderProgram) {
+        self.bind(palette);
+        program.use_program();
+        Uniform::new(program, "index_texture").set_uniform_i(&[0]);
+        Uniform::new(program, "palette").set_uniform_i(&[1]);
+        fullscreen_pass(program);
+    }
+
+    /// Deletes the index texture
+    pub fn delete(&self) {
+        self.texture.delete();
+    }
+}
+
+impl ShaderProgram {
+    /// Makes the built in palette lookup program used by [IndexedTexture::draw]
+    ///
+    /// The index texture is the sampler index_texture and the palette is the sampler palette
+    pub fn palette_lookup() -> Result<Self, String> {
+        Self::fullscreen(include_str!("../../shaders/palette_frag.glsl"))
+    }
+}
+
+/// Sets nearest filtering and clamping on the bound 2D texture so indices are never blended
+fn nearest_clamped() {
+    unsafe {
+        glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MIN_FILTER, GL_NEAREST as i32);
+        glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MAG_FILTER, GL_NEAREST as i32);
+        glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_S, GL_CLAMP_TO_EDGE as i32);
+        glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_T, GL_CLAMP_TO_EDGE as i32);
+    }
+}
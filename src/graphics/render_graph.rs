@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+
+use super::{framebuffer::*, texture::*, *};
+
+/// Where a [RenderGraph] pass draws to
+pub enum RenderTarget {
+    /// The window
+    Screen,
+    /// An offscreen framebuffer, its color texture is the output of the pass
+    Framebuffer(Framebuffer),
+    /// An offscreen framebuffer whose depth texture is the output of the pass, e.g. a shadow map
+    Depth(Framebuffer),
+}
+
+/// A single pass of a [RenderGraph]
+pub struct RenderPass<T> {
+    /// The name of the pass, other passes depend on it by name
+    pub name: String,
+    /// Where the pass draws to
+    pub target: RenderTarget,
+    /// The passes that must run before this one, their outputs are bound as inputs
+    pub dependencies: Vec<String>,
+    /// What is cleared before the pass runs, e.g. GL_COLOR_BUFFER_BIT | GL_DEPTH_BUFFER_BIT
+    pub clear_mask: u32,
+    execute: Box<dyn FnMut(&T)>,
+}
+
+/// Runs render passes in dependency order and binds their framebuffers for them
+///
+/// Before a pass runs its target is bound and cleared, and the output of each dependency that
+/// draws into a framebuffer is bound to GL_TEXTURE0, GL_TEXTURE1 ... in the order the
+/// dependencies were declared. The output is the color texture, or the depth texture for
+/// [RenderTarget::Depth]
+///
+/// # Example
+/// ```
+/// let mut graph = RenderGraph::new(vec2(800.0, 600.0));
+/// graph.add_pass("shadow", RenderTarget::Depth(Framebuffer::new(1024, 1024)?), &[], |world: &World<GameObject>| draw_shadows(world));
+/// graph.add_pass("main", RenderTarget::Screen, &["shadow"], |world: &World<GameObject>| draw_scene(world)); // shadow map on GL_TEXTURE0
+///
+/// // every frame
+/// graph.execute(&world)?;
+/// ```
+pub struct RenderGraph<T> {
+    /// The passes in the order they were added
+    pub passes: Vec<RenderPass<T>>,
+    /// The size of the window, used for the viewport of passes that draw to the screen
+    pub screen_size: nalgebra_glm::Vec2,
+}
+
+impl<T> RenderGraph<T> {
+    /// Creates an empty render graph
+    pub fn new(screen_size: nalgebra_glm::Vec2) -> Self {
+        RenderGraph {
+            passes: Vec::new(),
+            screen_size,
+        }
+    }
+
+    /// Adds a pass that clears color and depth, see [RenderPass::clear_mask] to change that
+    pub fn add_pass<F: FnMut(&T) + 'static>(
+        &mut self,
+        name: &str,
+        target: RenderTarget,
+        dependencies: &[&str],
+        execute: F,
+    ) -> &mut RenderPass<T> {
+        self.passes.push(RenderPass {
+            name: name.to_string(),
+            target,
+            dependencies: dependencies.iter().map(|name| name.to_string()).collect(),
+            clear_mask: GL_COLOR_BUFFER_BIT | GL_DEPTH_BUFFER_BIT,
+            execute: Box::new(execute),
+        });
+        self.passes.last_mut().unwrap()
+    }
+
+    /// Gets a pass by name
+    pub fn get_pass(&self, name: &str) -> Option<&RenderPass<T>> {
+        self.passes.iter().find(|pass| pass.name == name)
+    }
+
+    /// The indicies of the passes in the order they run
+    ///
+    /// It is an error if a dependency dosen't exist or the dependencies have a cycle
+    pub fn order(&self) -> Result<Vec<usize>, String> {
+        let indicies: HashMap<&str, usize> = self
+            .passes
+            .iter()
+            .enumerate()
+            .map(|(i, pass)| (pass.name.as_str(), i))
+            .collect();
+
+        let mut remaining = vec![0; self.passes.len()];
+        let mut dependents = vec![Vec::new(); self.passes.len()];
+        for (i, pass) in self.passes.iter().enumerate() {
+            for dependency in &pass.dependencies {
+                let dependency = *indicies.get(dependency.as_str()).ok_or_else(|| {
+                    format!(
+                        "Pass {} depends on {} which dosen't exist",
+                        pass.name, dependency
+                    )
+                })?;
+                remaining[i] += 1;
+                dependents[dependency].push(i);
+            }
+        }
+
+        // Kahn's algorithm, ties are broken by the order the passes were added
+        let mut ready: Vec<usize> = (0..self.passes.len())
+            .filter(|i| remaining[*i] == 0)
+            .rev()
+            .collect();
+        let mut order = Vec::with_capacity(self.passes.len());
+        while let Some(i) = ready.pop() {
+            order.push(i);
+            for dependent in dependents[i].iter().rev() {
+                remaining[*dependent] -= 1;
+                if remaining[*dependent] == 0 {
+                    ready.push(*dependent);
+                }
+            }
+        }
+
+        if order.len() == self.passes.len() {
+            Ok(order)
+        } else {
+            Err("The render graph has a cycle".to_string())
+        }
+    }
+
+    /// Runs every pass in dependency order
+    pub fn execute(&mut self, ctx: &T) -> Result<(), String> {
+        for i in self.order()? {
+            let inputs: Vec<u32> = self.passes[i]
+                .dependencies
+                .iter()
+                .filter_map(|dependency| match &self.get_pass(dependency)?.target {
+                    RenderTarget::Framebuffer(framebuffer) => Some(framebuffer.color.id),
+                    RenderTarget::Depth(framebuffer) => Some(framebuffer.depth.id),
+                    RenderTarget::Screen => None,
+                })
+                .collect();
+
+            let pass = &mut self.passes[i];
+            match &pass.target {
+                RenderTarget::Screen => {
+                    Framebuffer::bind_default(self.screen_size.x as u32, self.screen_size.y as u32)
+                }
+                RenderTarget::Framebuffer(framebuffer) | RenderTarget::Depth(framebuffer) => {
+                    framebuffer.bind()
+                }
+            }
+            if pass.clear_mask != 0 {
+                clear(pass.clear_mask);
+            }
+
+            for (unit, texture) in inputs.iter().enumerate() {
+                Texture::set_tex_unit(GL_TEXTURE0 + unit as u32);
+                unsafe { glBindTexture(GL_TEXTURE_2D, *texture) };
+            }
+            Texture::set_tex_unit(GL_TEXTURE0);
+
+            (pass.execute)(ctx);
+        }
+
+        Framebuffer::bind_default(self.screen_size.x as u32, self.screen_size.y as u32);
+        Ok(())
+    }
+}
@@ -1,5 +1,17 @@
-use super::*;
+use super::{capabilities::get_integer, extension::*, *};
+use log::{error, info, warn};
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// The start of every file written by [ShaderProgram::save_binary]
+const PROGRAM_BINARY_MAGIC: &[u8] = b"LHPROG1\0";
+
+/// The program that was last used by [ShaderProgram::use_program] or [ShaderProgram::bind]
+static CURRENT_PROGRAM: AtomicU32 = AtomicU32::new(0);
+
 /// Specifies the type of [Shader]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ShaderType {
     /// Vertex shaders determine the position of geometry within the screen.
     Vertex = GL_VERTEX_SHADER as isize,
@@ -73,19 +85,28 @@ impl Shader {
 
     /// Creates a new shader program from a string
     pub fn from_source(ty: ShaderType, source: &str) -> Result<Self, String> {
-        let id = Self::new(ty).ok_or_else(|| "Unable to allocate new shader".to_string())?;
+        let id = Self::new(ty).ok_or_else(|| {
+            error!("Unable to allocate new {:?} shader", ty);
+            "Unable to allocate new shader".to_string()
+        })?;
         id.set_source(source);
         id.compile();
         if id.compile_success() {
             Ok(id)
         } else {
-            let out = id.info_log();
+            let out = compile_error(ty, id.info_log());
             id.delete();
             Err(out)
         }
     }
 }
 
+/// Logs the info log of a shader that failed to compile with its stage and returns it
+fn compile_error(ty: ShaderType, info_log: String) -> String {
+    error!("{:?} shader failed to compile: {}", ty, info_log);
+    info_log
+}
+
 /// A handle to a [Program
 /// Object](https://www.khronos.org/opengl/wiki/GLSL_Object#Program_objects)
 #[derive(Copy, Clone)]
@@ -145,6 +166,28 @@ impl ShaderProgram {
     /// Sets the program as the program to use when drawing.
     pub fn use_program(&self) {
         unsafe { glUseProgram(self.0) };
+        CURRENT_PROGRAM.store(self.0, Ordering::Relaxed);
+    }
+
+    /// Uses the program only if it isn't the program that is already in use, so drawing many
+    /// meshes with the same program dosen't cost a glUseProgram each.
+    ///
+    /// Returns true if the program was changed. It only knows about programs that were used
+    /// through [ShaderProgram::use_program] or this function, see
+    /// [ShaderProgram::invalidate_binding]
+    pub fn bind(&self) -> bool {
+        if CURRENT_PROGRAM.swap(self.0, Ordering::Relaxed) == self.0 {
+            false
+        } else {
+            unsafe { glUseProgram(self.0) };
+            true
+        }
+    }
+
+    /// Forgets which program is in use so the next [ShaderProgram::bind] always calls
+    /// glUseProgram, call it after calling glUseProgram directly
+    pub fn invalidate_binding() {
+        CURRENT_PROGRAM.store(0, Ordering::Relaxed);
     }
 
     /// Marks the program for deletion.
@@ -153,6 +196,9 @@ impl ShaderProgram {
     /// currently in use it won't be deleted until it's not the active program.
     /// When a program is finally deleted and attached shaders are unattached.
     pub fn delete(self) {
+        // a new program can get the same id
+        let _ = CURRENT_PROGRAM.compare_exchange(self.0, 0, Ordering::Relaxed, Ordering::Relaxed);
+        super::uniform::forget_uniform_types(self.0);
         unsafe { glDeleteProgram(self.0) };
     }
 
@@ -162,13 +208,25 @@ impl ShaderProgram {
     /// This is the preferred way to create a simple shader program in the common
     /// case. It's just less error prone than doing all the steps yourself.
     pub fn from_vert_frag(vert: &str, frag: &str) -> Result<Self, String> {
-        let p = Self::new().ok_or_else(|| "Couldn't allocate a program".to_string())?;
+        Self::link_vert_frag(vert, frag, false)
+    }
+
+    /// Makes a program like [ShaderProgram::from_vert_frag], when retrievable is true the
+    /// driver is told the binary will be read back before it is linked
+    fn link_vert_frag(vert: &str, frag: &str, retrievable: bool) -> Result<Self, String> {
+        let p = Self::new().ok_or_else(|| {
+            error!("Couldn't allocate a program");
+            "Couldn't allocate a program".to_string()
+        })?;
         let v = Shader::from_source(ShaderType::Vertex, vert)
             .map_err(|e| format!("Vertex Compile Error: {}", e))?;
         let f = Shader::from_source(ShaderType::Fragment, frag)
             .map_err(|e| format!("Fragment Compile Error: {}", e))?;
         p.attach_shader(&v);
         p.attach_shader(&f);
+        if retrievable {
+            unsafe { glProgramParameteri(p.0, GL_PROGRAM_BINARY_RETRIEVABLE_HINT, GL_TRUE as i32) };
+        }
         p.link_program();
         v.delete();
         f.delete();
@@ -176,8 +234,214 @@ impl ShaderProgram {
             Ok(p)
         } else {
             let out = format!("Program Link Error: {}", p.info_log());
+            error!("{}", out);
             p.delete();
             Err(out)
         }
     }
+
+    /// Writes the linked program to a file so [ShaderProgram::load_binary] can skip compiling it
+    /// on the next run
+    ///
+    /// The file also holds the vendor, renderer and version of the driver since a binary only
+    /// works on the driver that made it. It is an error if the driver dosen't support
+    /// GL_ARB_get_program_binary or can't give the binary back
+    pub fn save_binary<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        if !program_binary_supported() {
+            return Err("The driver dosen't support program binaries".to_string());
+        }
+
+        let mut length = 0;
+        unsafe { glGetProgramiv(self.0, GL_PROGRAM_BINARY_LENGTH, &mut length) };
+        if length <= 0 {
+            return Err(format!("Program {} has no binary to save", self.0));
+        }
+        let mut binary = vec![0_u8; length as usize];
+        let (mut written, mut format) = (0, 0);
+        unsafe {
+            glGetProgramBinary(
+                self.0,
+                length,
+                &mut written,
+                &mut format,
+                binary.as_mut_ptr().cast(),
+            )
+        };
+        binary.truncate(written.max(0) as usize);
+
+        let driver = driver_key();
+        let mut out =
+            Vec::with_capacity(PROGRAM_BINARY_MAGIC.len() + 8 + driver.len() + binary.len());
+        out.extend_from_slice(PROGRAM_BINARY_MAGIC);
+        out.extend_from_slice(&format.to_le_bytes());
+        out.extend_from_slice(&(driver.len() as u32).to_le_bytes());
+        out.extend_from_slice(driver.as_bytes());
+        out.extend_from_slice(&binary);
+        fs::write(path.as_ref(), out)
+            .map_err(|err| format!("Couldn't write {}: {}", path.as_ref().display(), err))
+    }
+
+    /// Loads a program saved by [ShaderProgram::save_binary], or compiles it from the vertex and
+    /// fragment sources if the file is missing, was made by another driver or is rejected
+    ///
+    /// After compiling from source the binary is saved to path again so the next run can use
+    /// it, failing to save only logs a warning
+    ///
+    /// # Example
+    /// ```
+    /// let program = ShaderProgram::load_binary(
+    ///     "cache/lit.bin",
+    ///     include_str!("lit_vert.glsl"),
+    ///     include_str!("lit_frag.glsl"),
+    /// )?;
+    /// ```
+    pub fn load_binary<P: AsRef<Path>>(path: P, vert: &str, frag: &str) -> Result<Self, String> {
+        let path = path.as_ref();
+        if program_binary_supported() {
+            match fs::read(path)
+                .map_err(|err| err.to_string())
+                .and_then(|bytes| Self::from_binary(&bytes))
+            {
+                Ok(program) => return Ok(program),
+                Err(err) => info!(
+                    "Compiling the shader instead of loading {}: {}",
+                    path.display(),
+                    err
+                ),
+            }
+        }
+
+        let program = Self::link_vert_frag(vert, frag, program_binary_supported())?;
+        if program_binary_supported() {
+            if let Err(err) = program.save_binary(path) {
+                warn!("Couldn't cache the shader binary: {}", err);
+            }
+        }
+        Ok(program)
+    }
+
+    /// Makes a program from the contents of a file written by [ShaderProgram::save_binary], it
+    /// fails if the binary is from another driver or the driver rejects it
+    fn from_binary(bytes: &[u8]) -> Result<Self, String> {
+        let header = PROGRAM_BINARY_MAGIC.len();
+        if bytes.len() < header + 8 || &bytes[..header] != PROGRAM_BINARY_MAGIC {
+            return Err("It isn't a program binary".to_string());
+        }
+        let format = u32::from_le_bytes(bytes[header..header + 4].try_into().unwrap());
+        let driver_len =
+            u32::from_le_bytes(bytes[header + 4..header + 8].try_into().unwrap()) as usize;
+        let binary_start = header + 8 + driver_len;
+        if bytes.len() < binary_start {
+            return Err("The program binary is truncated".to_string());
+        }
+        if bytes[header + 8..binary_start] != *driver_key().as_bytes() {
+            return Err("The program binary was made by another driver".to_string());
+        }
+
+        let binary = &bytes[binary_start..];
+        let program = Self::new().ok_or_else(|| "Couldn't allocate a program".to_string())?;
+        unsafe {
+            glProgramBinary(
+                program.0,
+                format,
+                binary.as_ptr().cast(),
+                binary.len() as i32,
+            )
+        };
+        if program.link_success() {
+            Ok(program)
+        } else {
+            program.delete();
+            Err("The driver rejected the program binary".to_string())
+        }
+    }
+
+    /// Makes the built in shadeless vertex color program
+    ///
+    /// The vertex has a vec3 position at location 0 and a vec4 color at location 1, see
+    /// [crate::ECS::mesh::ColoredVertex]. The camera matrix uniform is called camera_matrix
+    pub fn vertex_color() -> Result<Self, String> {
+        Self::from_vert_frag(
+            include_str!("../../shaders/color_vert.glsl"),
+            include_str!("../../shaders/color_frag.glsl"),
+        )
+    }
+
+    /// Makes the built in shadeless program for instanced meshes with a color and an offset per
+    /// instance, e.g. for crowds and debris
+    ///
+    /// The vertex has a vec3 position at location 0, the instance has a vec4 color at
+    /// [INSTANCE_COLOR_LOCATION] and a vec3 offset at [INSTANCE_OFFSET_LOCATION], see
+    /// [crate::ECS::mesh::InstanceBuffer]. The camera matrix uniform is called camera_matrix
+    pub fn instanced_color() -> Result<Self, String> {
+        Self::from_vert_frag(
+            include_str!("../../shaders/instanced_color_vert.glsl"),
+            include_str!("../../shaders/color_frag.glsl"),
+        )
+    }
+}
+
+/// The location of the per instance color of [ShaderProgram::instanced_color]
+pub const INSTANCE_COLOR_LOCATION: u32 = 8;
+/// The location of the per instance offset of [ShaderProgram::instanced_color]
+pub const INSTANCE_OFFSET_LOCATION: u32 = 9;
+
+/// Checks if programs can be saved and loaded as binaries, the driver needs
+/// GL_ARB_get_program_binary with at least one binary format and the functions must be loaded by
+/// [load_extensions_with]
+pub fn program_binary_supported() -> bool {
+    is_loaded("glGetProgramBinary")
+        && is_loaded("glProgramBinary")
+        && is_loaded("glProgramParameteri")
+        && get_integer(GL_NUM_PROGRAM_BINARY_FORMATS) > 0
+}
+
+/// The vendor, renderer and version of the driver, a program binary only works on the driver
+/// that made it
+fn driver_key() -> String {
+    format!(
+        "{}\n{}\n{}",
+        get_string(GL_VENDOR),
+        get_string(GL_RENDERER),
+        get_string(GL_VERSION)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::{Level, Log, Metadata, Record};
+    use std::sync::Mutex;
+
+    /// A logger that keeps every record so the tests can look at them
+    struct CapturingLogger(Mutex<Vec<(Level, String)>>);
+
+    impl Log for CapturingLogger {
+        fn enabled(&self, _metadata: &Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &Record) {
+            let message = record.args().to_string();
+            self.0.lock().unwrap().push((record.level(), message));
+        }
+
+        fn flush(&self) {}
+    }
+
+    static LOGGER: CapturingLogger = CapturingLogger(Mutex::new(Vec::new()));
+
+    #[test]
+    fn a_failed_compile_is_logged_as_an_error_with_the_stage() {
+        log::set_logger(&LOGGER).unwrap();
+        log::set_max_level(log::LevelFilter::Trace);
+
+        let out = compile_error(ShaderType::Fragment, "0:3: 'vec5' : undeclared".to_string());
+        assert_eq!(out, "0:3: 'vec5' : undeclared");
+
+        let records = LOGGER.0.lock().unwrap();
+        assert!(records.iter().any(|(level, message)| {
+            *level == Level::Error && message.contains("Fragment") && message.contains("vec5")
+        }));
+    }
 }
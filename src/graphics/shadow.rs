@@ -0,0 +1,374 @@
+use super::{
+    memory::{texture_memory_bytes, track_texture},
+    shader::*,
+    texture::Texture,
+    uniform::*,
+    *,
+};
+use crate::math::up_axis;
+use nalgebra_glm::{inverse, look_at, ortho, perspective, vec4, Mat4, Vec3};
+
+/// The most cascades a [CascadedShadowMap] can have, it is the size of the arrays in
+/// [CASCADE_SHADOW_GLSL]
+pub const MAX_CASCADES: usize = 4;
+
+/// GLSL uniforms and functions for the main shader to sample a [CascadedShadowMap] after
+/// [CascadedShadowMap::upload], view_depth is the distance of the fragment from the camera along
+/// its view direction. shadow returns 0.0 in shadow and 1.0 when lit
+///
+/// # Example
+/// ```
+/// let frag = format!("#version 330 core\n{}\n{}", CASCADE_SHADOW_GLSL, LIT_FRAG);
+/// // in LIT_FRAG
+/// // color.rgb *= mix(0.3, 1.0, shadow(world_pos, -view_pos.z, 0.002));
+/// ```
+pub const CASCADE_SHADOW_GLSL: &str = "uniform sampler2DArray u_shadow_map;
+uniform mat4 u_light_matrices[4];
+uniform float u_cascade_splits[4];
+uniform int u_cascade_count;
+
+int select_cascade(float view_depth) {
+    for (int i = 0; i < u_cascade_count - 1; i++) {
+        if (view_depth < u_cascade_splits[i]) {
+            return i;
+        }
+    }
+    return u_cascade_count - 1;
+}
+
+float shadow(vec3 world_pos, float view_depth, float bias) {
+    int cascade = select_cascade(view_depth);
+    vec4 light_pos = u_light_matrices[cascade] * vec4(world_pos, 1.0);
+    vec3 coords = light_pos.xyz / light_pos.w * 0.5 + 0.5;
+    if (coords.z > 1.0) {
+        return 1.0;
+    }
+    float closest = texture(u_shadow_map, vec3(coords.xy, float(cascade))).r;
+    return coords.z - bias > closest ? 0.0 : 1.0;
+}";
+
+/// Picks the cascade a fragment view_depth away from the camera is in, it is the same as
+/// select_cascade in [CASCADE_SHADOW_GLSL]
+///
+/// splits are the far distances of the cascades, a fragment is in the first cascade whose split
+/// is further than it and fragments past every split are in the last cascade
+pub fn select_cascade(splits: &[f32], view_depth: f32) -> usize {
+    splits
+        .iter()
+        .take(splits.len().saturating_sub(1))
+        .position(|split| view_depth < *split)
+        .unwrap_or(splits.len().saturating_sub(1))
+}
+
+/// Splits the view distance from near to far into count cascades and returns the far distance
+/// of each cascade, the last one is far
+///
+/// lambda blends between evenly spaced splits(0.0) and logarithmic splits(1.0), which give the
+/// cascades near the camera more of the resolution
+pub fn split_distances(near: f32, far: f32, count: usize, lambda: f32) -> Vec<f32> {
+    (1..=count)
+        .map(|i| {
+            let t = i as f32 / count as f32;
+            let uniform = near + (far - near) * t;
+            let log = near * (far / near).powf(t);
+            lambda * log + (1.0 - lambda) * uniform
+        })
+        .collect()
+}
+
+/// Shadows of a directional light(e.g. the sun) split into cascades by the distance from the
+/// camera, so the shadows near the camera stay sharp while still covering a large view distance
+///
+/// Each cascade is a layer of a depth texture array with its own light space matrix, the main
+/// shader picks the cascade by the depth of the fragment, see [CASCADE_SHADOW_GLSL]
+///
+/// # Example
+/// ```
+/// let mut shadows = CascadedShadowMap::new(2048, 2)?;
+///
+/// // every frame
+/// let settings = camera.get_camera_settings();
+/// shadows.update(
+///     &sun_direction,
+///     &camera.view_matrix(),
+///     settings.fov,
+///     settings.screen_size.x / settings.screen_size.y,
+///     settings.near_plane,
+///     settings.far_plane,
+/// );
+/// depth_program.use_program();
+/// shadows.render(|light_matrix| draw_depth(&depth_program, light_matrix));
+/// Framebuffer::bind_default(800, 600);
+///
+/// lit_program.use_program();
+/// shadows.upload(&lit_program, GL_TEXTURE5)?;
+/// draw_scene();
+/// ```
+pub struct CascadedShadowMap {
+    /// The width and height of every cascade
+    pub size: u32,
+    /// How logarithmic the splits are, see [split_distances]. It is 0.75 by default
+    pub lambda: f32,
+    /// How far behind each cascade the light space box is extended, so objects between the
+    /// light and the view still cast shadows into it. It is 50.0 by default
+    pub depth_margin: f32,
+    /// The far distance of every cascade from the camera, it is set by
+    /// [CascadedShadowMap::update]
+    pub splits: Vec<f32>,
+    /// The light space(projection * view) matrix of every cascade, it is set by
+    /// [CascadedShadowMap::update]
+    pub light_matrices: Vec<Mat4>,
+    /// The depth texture array, one layer per cascade
+    pub depth: Texture,
+    /// The framebuffer the cascades are rendered with
+    fbo: u32,
+}
+
+impl CascadedShadowMap {
+    /// Makes a shadow map with cascades layers of size x size, there must be between 1 and
+    /// [MAX_CASCADES] cascades
+    pub fn new(size: u32, cascades: usize) -> Result<Self, String> {
+        if !(1..=MAX_CASCADES).contains(&cascades) {
+            return Err(format!(
+                "A cascaded shadow map can have 1 to {} cascades but {} were requested",
+                MAX_CASCADES, cascades
+            ));
+        }
+
+        let mut depth = Texture::new();
+        depth.bind(GL_TEXTURE_2D_ARRAY);
+        unsafe {
+            glTexImage3D(
+                GL_TEXTURE_2D_ARRAY,
+                0,
+                GL_DEPTH_COMPONENT32F as i32,
+                size as i32,
+                size as i32,
+                cascades as i32,
+                0,
+                GL_DEPTH_COMPONENT,
+                GL_FLOAT,
+                std::ptr::null(),
+            );
+            glTexParameteri(
+                GL_TEXTURE_2D_ARRAY,
+                GL_TEXTURE_MIN_FILTER,
+                GL_NEAREST as i32,
+            );
+            glTexParameteri(
+                GL_TEXTURE_2D_ARRAY,
+                GL_TEXTURE_MAG_FILTER,
+                GL_NEAREST as i32,
+            );
+            // everything outside of the map is lit
+            glTexParameteri(
+                GL_TEXTURE_2D_ARRAY,
+                GL_TEXTURE_WRAP_S,
+                GL_CLAMP_TO_BORDER as i32,
+            );
+            glTexParameteri(
+                GL_TEXTURE_2D_ARRAY,
+                GL_TEXTURE_WRAP_T,
+                GL_CLAMP_TO_BORDER as i32,
+            );
+            glTexParameterfv(
+                GL_TEXTURE_2D_ARRAY,
+                GL_TEXTURE_BORDER_COLOR,
+                [1.0, 1.0, 1.0, 1.0].as_ptr(),
+            );
+        }
+        Texture::unbind(GL_TEXTURE_2D_ARRAY);
+        track_texture(
+            depth.id,
+            texture_memory_bytes(size, size, GL_DEPTH_COMPONENT32F, 1) * cascades,
+        );
+
+        let mut fbo = 0;
+        unsafe {
+            glGenFramebuffers(1, &mut fbo);
+            glBindFramebuffer(GL_FRAMEBUFFER, fbo);
+            glFramebufferTextureLayer(GL_FRAMEBUFFER, GL_DEPTH_ATTACHMENT, depth.id, 0, 0);
+            // there is no color attachment
+            glDrawBuffer(GL_NONE);
+            glReadBuffer(GL_NONE);
+        }
+        let complete =
+            unsafe { glCheckFramebufferStatus(GL_FRAMEBUFFER) == GL_FRAMEBUFFER_COMPLETE };
+        unsafe { glBindFramebuffer(GL_FRAMEBUFFER, 0) };
+
+        let shadow_map = CascadedShadowMap {
+            size,
+            lambda: 0.75,
+            depth_margin: 50.0,
+            splits: vec![0.0; cascades],
+            light_matrices: vec![Mat4::identity(); cascades],
+            depth,
+            fbo,
+        };
+        if complete {
+            Ok(shadow_map)
+        } else {
+            shadow_map.delete();
+            Err("The cascaded shadow map framebuffer is not complete".to_string())
+        }
+    }
+
+    /// How many cascades there are
+    pub fn cascade_count(&self) -> usize {
+        self.light_matrices.len()
+    }
+
+    /// Picks the cascade a fragment view_depth away from the camera is in, see [select_cascade]
+    pub fn select_cascade(&self, view_depth: f32) -> usize {
+        select_cascade(&self.splits, view_depth)
+    }
+
+    /// Splits the view of the camera into cascades and fits a light space matrix around each
+    /// of them, it should be called every frame the camera or light moves
+    ///
+    /// light_direction is the direction the light shines in, view is the view matrix of the
+    /// camera and fov(in degrees), aspect, near and far are the settings of its perspective
+    /// projection
+    pub fn update(
+        &mut self,
+        light_direction: &Vec3,
+        view: &Mat4,
+        fov: f32,
+        aspect: f32,
+        near: f32,
+        far: f32,
+    ) {
+        self.splits = split_distances(near, far, self.cascade_count(), self.lambda);
+        let direction = light_direction.normalize();
+        // look_at can't use an up that is parallel to the direction
+        let axis = up_axis();
+        let up = if direction.dot(&axis.up()).abs() > 0.99 {
+            axis.forward()
+        } else {
+            axis.up()
+        };
+
+        let mut cascade_near = near;
+        for i in 0..self.cascade_count() {
+            let cascade_far = self.splits[i];
+            let inverse_view_projection =
+                inverse(&(perspective(aspect, fov.to_radians(), cascade_near, cascade_far) * view));
+            let corners: Vec<Vec3> = [-1.0, 1.0]
+                .iter()
+                .flat_map(|x| [-1.0, 1.0].iter().map(move |y| (*x, *y)))
+                .flat_map(|(x, y)| [-1.0, 1.0].iter().map(move |z| vec4(x, y, *z, 1.0)))
+                .map(|corner| {
+                    let corner = inverse_view_projection * corner;
+                    corner.xyz() / corner.w
+                })
+                .collect();
+            let center = corners.iter().sum::<Vec3>() / corners.len() as f32;
+
+            let light_view = look_at(&(center - direction), &center, &up);
+            let (mut min, mut max) = (Vec3::repeat(f32::INFINITY), Vec3::repeat(f32::NEG_INFINITY));
+            for corner in &corners {
+                let corner = (light_view * corner.push(1.0)).xyz();
+                min = min.inf(&corner);
+                max = max.sup(&corner);
+            }
+
+            // the light looks down -z so the distances along it are -z
+            let projection = ortho(
+                min.x,
+                max.x,
+                min.y,
+                max.y,
+                -max.z - self.depth_margin,
+                -min.z,
+            );
+            self.light_matrices[i] = projection * light_view;
+            cascade_near = cascade_far;
+        }
+    }
+
+    /// Binds the framebuffer to render the depth of a cascade, sets the viewport to the size of
+    /// the cascade and clears its depth
+    pub fn bind_cascade(&self, cascade: usize) {
+        unsafe {
+            glBindFramebuffer(GL_FRAMEBUFFER, self.fbo);
+            glFramebufferTextureLayer(
+                GL_FRAMEBUFFER,
+                GL_DEPTH_ATTACHMENT,
+                self.depth.id,
+                0,
+                cascade as i32,
+            );
+            glViewport(0, 0, self.size as i32, self.size as i32);
+            glClear(GL_DEPTH_BUFFER_BIT);
+        }
+    }
+
+    /// Renders every cascade, draw is called once per cascade with its light space matrix and
+    /// should draw the depth of the scene with it
+    ///
+    /// The shadow map is left bound, bind the window again with
+    /// [Framebuffer::bind_default](super::framebuffer::Framebuffer::bind_default)
+    pub fn render<F: FnMut(&Mat4)>(&self, mut draw: F) {
+        for (i, light_matrix) in self.light_matrices.iter().enumerate() {
+            self.bind_cascade(i);
+            draw(light_matrix);
+        }
+    }
+
+    /// Binds the depth texture array to texture_unit e.g. GL_TEXTURE5 and sets the uniforms of
+    /// [CASCADE_SHADOW_GLSL], the program must be in use
+    ///
+    /// Uniforms that aren't in the program are skipped, the active texture unit is left as
+    /// texture_unit
+    pub fn upload(&self, program: &ShaderProgram, texture_unit: u32) -> Result<(), UniformError> {
+        Texture::set_tex_unit(texture_unit);
+        unsafe { glBindTexture(GL_TEXTURE_2D_ARRAY, self.depth.id) };
+
+        if let Some(uniform) = Uniform::find(program, "u_shadow_map") {
+            uniform.set_int((texture_unit - GL_TEXTURE0) as i32)?;
+        }
+        if let Some(uniform) = Uniform::find(program, "u_cascade_count") {
+            uniform.set_int(self.cascade_count() as i32)?;
+        }
+        for (i, (light_matrix, split)) in self.light_matrices.iter().zip(&self.splits).enumerate() {
+            if let Some(uniform) = Uniform::find(program, &format!("u_light_matrices[{}]", i)) {
+                uniform.set_mat4(light_matrix)?;
+            }
+            if let Some(uniform) = Uniform::find(program, &format!("u_cascade_splits[{}]", i)) {
+                uniform.set_float(*split)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Deletes the depth texture array and the framebuffer
+    pub fn delete(&self) {
+        self.depth.delete();
+        unsafe { glDeleteFramebuffers(1, &self.fbo) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_blend_between_even_and_logarithmic() {
+        assert_eq!(split_distances(1.0, 100.0, 2, 0.0), vec![50.5, 100.0]);
+
+        let log = split_distances(1.0, 100.0, 2, 1.0);
+        assert!((log[0] - 10.0).abs() < 1e-4);
+        assert!((log[1] - 100.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn the_cascade_is_picked_by_the_view_depth() {
+        let splits = [10.0, 40.0, 100.0];
+        assert_eq!(select_cascade(&splits, 5.0), 0);
+        assert_eq!(select_cascade(&splits, 10.0), 1);
+        assert_eq!(select_cascade(&splits, 50.0), 2);
+        // everything past far uses the last cascade
+        assert_eq!(select_cascade(&splits, 500.0), 2);
+        assert_eq!(select_cascade(&[], 5.0), 0);
+    }
+}
@@ -0,0 +1,57 @@
+use super::{shader::*, uniform::*};
+use nalgebra_glm::{Mat4, Vec4};
+
+/// Draws objects in a single flat color without lighting, textures or materials, e.g. for
+/// selection highlights, silhouettes, thumbnails or to write a mask into the stencil buffer
+///
+/// Only the position of the vertex at location 0 is used so any vertex can be drawn with it
+///
+/// # Example
+/// ```
+/// let silhouette = SolidColor::new(vec4(1.0, 0.0, 0.0, 1.0))?;
+///
+/// // in GameObjectTrait::draw
+/// world.draw_mesh_solid(&world.objects.pyramid.mesh, &silhouette);
+/// ```
+pub struct SolidColor {
+    /// The color every pixel of the object is drawn in
+    pub color: Vec4,
+    program: ShaderProgram,
+}
+
+impl SolidColor {
+    /// Creates a new solid color and compiles the built in solid color program
+    pub fn new(color: Vec4) -> Result<Self, String> {
+        Ok(SolidColor {
+            color,
+            program: ShaderProgram::from_vert_frag(
+                include_str!("../../shaders/solid_color_vert.glsl"),
+                include_str!("../../shaders/solid_color_frag.glsl"),
+            )?,
+        })
+    }
+
+    /// The built in program, the camera matrix uniform is called camera_matrix and the color is
+    /// called solid_color
+    pub fn program(&self) -> &ShaderProgram {
+        &self.program
+    }
+
+    /// Binds the program and sets its uniforms then calls draw, which must issue the draw calls
+    /// of the object
+    ///
+    /// NOTE: the color is blended like any other if blending is on, use an alpha of 1.0 for an
+    /// exact color
+    pub fn draw<F: FnOnce()>(&self, camera_matrix: &Mat4, draw: F) {
+        self.program.bind();
+        Uniform::new(&self.program, "camera_matrix")
+            .set_uniform_matrix(false, (*camera_matrix).into());
+        Uniform::new(&self.program, "solid_color").set_uniform_f(self.color.as_slice());
+        draw();
+    }
+
+    /// Deletes the program
+    pub fn delete(self) {
+        self.program.delete();
+    }
+}
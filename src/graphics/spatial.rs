@@ -0,0 +1,248 @@
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+use super::culling::Aabb;
+use nalgebra_glm::*;
+
+/// A cell of a [SpatialGrid]
+type Cell = (i32, i32, i32);
+
+/// A uniform grid that indexes objects by their bounding box for broadphase collision and
+/// culling
+///
+/// Every object is stored in all the cells its box touches, so queries only test the objects
+/// near the region instead of every object. The cell size should be around the size of a
+/// typical object
+///
+/// # Example
+/// ```
+/// let mut grid = SpatialGrid::new(2.0);
+/// grid.insert(entity, Aabb::new(vec3(0.0, 0.0, 0.0), vec3(1.0, 1.0, 1.0)));
+///
+/// for (a, b) in grid.potential_pairs() {
+///     // narrow phase
+/// }
+/// let nearby = grid.query(&Aabb::new(vec3(-5.0, -5.0, -5.0), vec3(5.0, 5.0, 5.0)));
+/// ```
+#[derive(Debug, Clone)]
+pub struct SpatialGrid<Id: Copy + Eq + Hash> {
+    cell_size: f32,
+    objects: HashMap<Id, Aabb>,
+    cells: HashMap<Cell, Vec<Id>>,
+}
+
+impl<Id: Copy + Eq + Hash> SpatialGrid<Id> {
+    /// Creates an empty grid, cell_size is the width, height and depth of a cell
+    ///
+    /// NOTE: will panic if cell_size isn't a finite number above 0
+    pub fn new(cell_size: f32) -> Self {
+        assert!(
+            cell_size.is_finite() && cell_size > 0.0,
+            "The cell size of a SpatialGrid must be above 0 not {}",
+            cell_size
+        );
+        SpatialGrid {
+            cell_size,
+            objects: HashMap::new(),
+            cells: HashMap::new(),
+        }
+    }
+
+    /// The width, height and depth of a cell
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    /// How many objects are in the grid
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    /// Checks if there are no objects in the grid
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+
+    /// Gets the box of an object
+    pub fn get(&self, id: Id) -> Option<&Aabb> {
+        self.objects.get(&id)
+    }
+
+    /// Adds an object, if it is already in the grid it is moved to the new box
+    pub fn insert(&mut self, id: Id, aabb: Aabb) {
+        self.remove(id);
+        for cell in self.cells_of(&aabb) {
+            self.cells.entry(cell).or_default().push(id);
+        }
+        self.objects.insert(id, aabb);
+    }
+
+    /// Removes an object, returns its box if it was in the grid
+    pub fn remove(&mut self, id: Id) -> Option<Aabb> {
+        let aabb = self.objects.remove(&id)?;
+        for cell in self.cells_of(&aabb) {
+            if let Some(ids) = self.cells.get_mut(&cell) {
+                ids.retain(|other| *other != id);
+                if ids.is_empty() {
+                    self.cells.remove(&cell);
+                }
+            }
+        }
+        Some(aabb)
+    }
+
+    /// Removes every object
+    pub fn clear(&mut self) {
+        self.objects.clear();
+        self.cells.clear();
+    }
+
+    /// Gets every object whose box overlaps region, each object is returned once
+    ///
+    /// When the region spans more cells than are occupied the occupied cells are checked
+    /// instead, so a huge region costs no more than testing every object
+    pub fn query(&self, region: &Aabb) -> Vec<Id> {
+        let (min, max) = (self.cell_of(&region.min), self.cell_of(&region.max));
+        let span = |min: i32, max: i32| (max as i64 - min as i64 + 1).max(0) as u64;
+        let region_cells = span(min.0, max.0)
+            .saturating_mul(span(min.1, max.1))
+            .saturating_mul(span(min.2, max.2));
+
+        let ids: Box<dyn Iterator<Item = &Id>> = if region_cells > self.cells.len() as u64 {
+            let inside = move |(x, y, z): &Cell| {
+                (min.0..=max.0).contains(x)
+                    && (min.1..=max.1).contains(y)
+                    && (min.2..=max.2).contains(z)
+            };
+            Box::new(
+                self.cells
+                    .iter()
+                    .filter(move |(cell, _)| inside(cell))
+                    .flat_map(|(_, ids)| ids),
+            )
+        } else {
+            Box::new(
+                self.cells_of(region)
+                    .flat_map(|cell| self.cells.get(&cell).into_iter().flatten()),
+            )
+        };
+
+        let mut seen = HashSet::new();
+        ids.filter(|id| seen.insert(**id) && self.objects[*id].intersects(region))
+            .copied()
+            .collect()
+    }
+
+    /// Gets every pair of objects whose boxes overlap, each pair is returned once
+    pub fn potential_pairs(&self) -> Vec<(Id, Id)> {
+        let mut seen = HashSet::new();
+        let mut out = Vec::new();
+        for ids in self.cells.values() {
+            for (i, a) in ids.iter().enumerate() {
+                for b in &ids[i + 1..] {
+                    if !seen.contains(&(*b, *a))
+                        && seen.insert((*a, *b))
+                        && self.objects[a].intersects(&self.objects[b])
+                    {
+                        out.push((*a, *b));
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    /// The cell a point is in
+    fn cell_of(&self, point: &Vec3) -> Cell {
+        let cell = (point / self.cell_size).map(f32::floor);
+        (cell.x as i32, cell.y as i32, cell.z as i32)
+    }
+
+    /// All the cells a box touches
+    fn cells_of(&self, aabb: &Aabb) -> impl Iterator<Item = Cell> {
+        let (min, max) = (self.cell_of(&aabb.min), self.cell_of(&aabb.max));
+        (min.0..=max.0).flat_map(move |x| {
+            (min.1..=max.1).flat_map(move |y| (min.2..=max.2).map(move |z| (x, y, z)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    fn random_box(rng: &mut Rng) -> Aabb {
+        let min = vec3(
+            rng.range(-50.0, 50.0),
+            rng.range(-50.0, 50.0),
+            rng.range(-50.0, 50.0),
+        );
+        let size = vec3(
+            rng.range(0.0, 4.0),
+            rng.range(0.0, 4.0),
+            rng.range(0.0, 4.0),
+        );
+        Aabb::new(min, min + size)
+    }
+
+    #[test]
+    fn queries_and_pairs_match_a_brute_force_search() {
+        let mut rng = Rng::seed(422);
+        let boxes: Vec<Aabb> = (0..1000).map(|_| random_box(&mut rng)).collect();
+        let mut grid = SpatialGrid::new(3.0);
+        for (id, aabb) in boxes.iter().enumerate() {
+            grid.insert(id, *aabb);
+        }
+
+        for _ in 0..50 {
+            let region = random_box(&mut rng);
+            let mut found = grid.query(&region);
+            found.sort_unstable();
+            let expected: Vec<usize> = (0..boxes.len())
+                .filter(|id| boxes[*id].intersects(&region))
+                .collect();
+            assert_eq!(found, expected);
+        }
+
+        let mut pairs: Vec<(usize, usize)> = grid
+            .potential_pairs()
+            .into_iter()
+            .map(|(a, b)| (a.min(b), a.max(b)))
+            .collect();
+        pairs.sort_unstable();
+        let mut expected = Vec::new();
+        for a in 0..boxes.len() {
+            for b in a + 1..boxes.len() {
+                if boxes[a].intersects(&boxes[b]) {
+                    expected.push((a, b));
+                }
+            }
+        }
+        assert_eq!(pairs, expected);
+    }
+
+    #[test]
+    fn a_region_bigger_than_the_grid_checks_the_occupied_cells() {
+        let mut grid = SpatialGrid::new(1.0);
+        grid.insert(1, Aabb::new(Vec3::zeros(), vec3(1.0, 1.0, 1.0)));
+        grid.insert(2, Aabb::new(vec3(1e6, 0.0, 0.0), vec3(1e6 + 1.0, 1.0, 1.0)));
+
+        let everything = Aabb::new(Vec3::repeat(-1e9), Vec3::repeat(1e9));
+        let mut found = grid.query(&everything);
+        found.sort_unstable();
+        assert_eq!(found, vec![1, 2]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn a_cell_size_of_zero_is_rejected() {
+        SpatialGrid::<u32>::new(0.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn a_nan_cell_size_is_rejected() {
+        SpatialGrid::<u32>::new(f32::NAN);
+    }
+}
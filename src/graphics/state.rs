@@ -0,0 +1,218 @@
+use super::{capabilities::get_integer, *};
+use log::warn;
+
+/// A snapshot of the GL state that draws commonly change and forget to change back
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GlState {
+    /// GL_BLEND is enabled
+    pub blend: bool,
+    /// GL_DEPTH_TEST is enabled
+    pub depth_test: bool,
+    /// GL_CULL_FACE is enabled
+    pub cull_face: bool,
+    /// GL_STENCIL_TEST is enabled
+    pub stencil_test: bool,
+    /// GL_SCISSOR_TEST is enabled
+    pub scissor_test: bool,
+    /// Writing into the depth buffer is enabled, see [depth_mask]
+    pub depth_mask: bool,
+    /// The source and destination blend factors of the color then of the alpha
+    pub blend_func: (u32, u32, u32, u32),
+    /// The depth comparison, e.g. GL_LESS
+    pub depth_func: u32,
+    /// See [polygon_mode]
+    pub polygon_mode: PolygonMode,
+    /// The bound VAO, 0 if there is none
+    pub vertex_array: u32,
+    /// The active texture unit, e.g. GL_TEXTURE0
+    pub active_texture: u32,
+    /// The bound draw framebuffer, 0 is the window
+    pub framebuffer: u32,
+    /// The viewport as (x, y, width, height)
+    pub viewport: (i32, i32, i32, i32),
+}
+
+impl GlState {
+    /// Reads the state of the current context
+    pub fn capture() -> Self {
+        GlState {
+            blend: is_enabled(GL_BLEND),
+            depth_test: is_enabled(GL_DEPTH_TEST),
+            cull_face: is_enabled(GL_CULL_FACE),
+            stencil_test: is_enabled(GL_STENCIL_TEST),
+            scissor_test: is_enabled(GL_SCISSOR_TEST),
+            depth_mask: get_integer(GL_DEPTH_WRITEMASK) != 0,
+            blend_func: (
+                get_integer(GL_BLEND_SRC_RGB) as u32,
+                get_integer(GL_BLEND_DST_RGB) as u32,
+                get_integer(GL_BLEND_SRC_ALPHA) as u32,
+                get_integer(GL_BLEND_DST_ALPHA) as u32,
+            ),
+            depth_func: get_integer(GL_DEPTH_FUNC) as u32,
+            polygon_mode: get_polygon_mode(),
+            vertex_array: get_integer(GL_VERTEX_ARRAY_BINDING) as u32,
+            active_texture: get_integer(GL_ACTIVE_TEXTURE) as u32,
+            framebuffer: get_integer(GL_DRAW_FRAMEBUFFER_BINDING) as u32,
+            viewport: get_viewport(),
+        }
+    }
+
+    /// Sets the state of the current context to the snapshot
+    pub fn restore(&self) {
+        for (cap, on) in [
+            (GL_BLEND, self.blend),
+            (GL_DEPTH_TEST, self.depth_test),
+            (GL_CULL_FACE, self.cull_face),
+            (GL_STENCIL_TEST, self.stencil_test),
+            (GL_SCISSOR_TEST, self.scissor_test),
+        ] {
+            if on {
+                enable(cap);
+            } else {
+                disable(cap);
+            }
+        }
+        depth_mask(self.depth_mask);
+        depth_func(self.depth_func);
+        polygon_mode(self.polygon_mode);
+        let (x, y, width, height) = self.viewport;
+        viewport(x, y, width, height);
+        unsafe {
+            let (src_rgb, dst_rgb, src_alpha, dst_alpha) = self.blend_func;
+            glBlendFuncSeparate(src_rgb, dst_rgb, src_alpha, dst_alpha);
+            glBindVertexArray(self.vertex_array);
+            glActiveTexture(self.active_texture);
+            glBindFramebuffer(GL_DRAW_FRAMEBUFFER, self.framebuffer);
+        }
+    }
+
+    /// Describes every way other differs from self, it is empty if they are the same
+    pub fn diff(&self, other: &GlState) -> Vec<String> {
+        let mut changes = Vec::new();
+        let mut compare = |name: &str, before: String, after: String| {
+            if before != after {
+                changes.push(format!("{} changed from {} to {}", name, before, after));
+            }
+        };
+
+        compare("GL_BLEND", self.blend.to_string(), other.blend.to_string());
+        compare(
+            "GL_DEPTH_TEST",
+            self.depth_test.to_string(),
+            other.depth_test.to_string(),
+        );
+        compare(
+            "GL_CULL_FACE",
+            self.cull_face.to_string(),
+            other.cull_face.to_string(),
+        );
+        compare(
+            "GL_STENCIL_TEST",
+            self.stencil_test.to_string(),
+            other.stencil_test.to_string(),
+        );
+        compare(
+            "GL_SCISSOR_TEST",
+            self.scissor_test.to_string(),
+            other.scissor_test.to_string(),
+        );
+        compare(
+            "the depth mask",
+            self.depth_mask.to_string(),
+            other.depth_mask.to_string(),
+        );
+        compare(
+            "the blend func",
+            format!("{:#x?}", self.blend_func),
+            format!("{:#x?}", other.blend_func),
+        );
+        compare(
+            "the depth func",
+            format!("{:#x}", self.depth_func),
+            format!("{:#x}", other.depth_func),
+        );
+        compare(
+            "the polygon mode",
+            format!("{:?}", self.polygon_mode),
+            format!("{:?}", other.polygon_mode),
+        );
+        compare(
+            "the bound VAO",
+            self.vertex_array.to_string(),
+            other.vertex_array.to_string(),
+        );
+        compare(
+            "the active texture",
+            format!("GL_TEXTURE{}", self.active_texture - GL_TEXTURE0),
+            format!("GL_TEXTURE{}", other.active_texture - GL_TEXTURE0),
+        );
+        compare(
+            "the bound framebuffer",
+            self.framebuffer.to_string(),
+            other.framebuffer.to_string(),
+        );
+        compare(
+            "the viewport",
+            format!("{:?}", self.viewport),
+            format!("{:?}", other.viewport),
+        );
+
+        changes
+    }
+}
+
+/// Catches GL state that a frame changed and didn't change back, which makes bugs that depend
+/// on the order things are drawn in
+///
+/// It is used by [World::run](crate::ECS::world::World::run) when
+/// [Enviroment::validate_state](crate::ECS::world::Enviroment::validate_state) is on
+///
+/// # Example
+/// ```
+/// let guard = StateGuard::new();
+/// draw_ui();
+/// // logs e.g. "GL_BLEND changed from false to true" and disables blending again
+/// guard.check_and_restore();
+/// ```
+pub struct StateGuard {
+    start: GlState,
+}
+
+impl StateGuard {
+    /// Snapshots the current state
+    pub fn new() -> Self {
+        StateGuard {
+            start: GlState::capture(),
+        }
+    }
+
+    /// The state when the guard was made
+    pub fn start(&self) -> &GlState {
+        &self.start
+    }
+
+    /// Logs every change to the state since the guard was made and returns them
+    pub fn check(&self) -> Vec<String> {
+        let leaks = self.start.diff(&GlState::capture());
+        for leak in &leaks {
+            warn!("Leaked GL state: {}", leak);
+        }
+        leaks
+    }
+
+    /// Like [StateGuard::check] but also restores the state, so the leak dosen't affect what is
+    /// drawn next
+    pub fn check_and_restore(&self) -> Vec<String> {
+        let leaks = self.check();
+        if !leaks.is_empty() {
+            self.start.restore();
+        }
+        leaks
+    }
+}
+
+impl Default for StateGuard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,54 @@
+use super::*;
+use std::time::Duration;
+
+/// A [sync object](https://www.khronos.org/opengl/wiki/Sync_Object) that is signaled when the
+/// gpu has finished every command issued before it
+///
+/// # Example
+/// ```
+/// draw_scene();
+/// let fence = Fence::new();
+/// // do some cpu work while the gpu draws
+/// fence.wait(Duration::from_secs(1));
+/// ```
+pub struct Fence(pub GLsync);
+
+impl Fence {
+    /// Inserts a fence after all the commands issued so far
+    pub fn new() -> Self {
+        Fence(unsafe { glFenceSync(GL_SYNC_GPU_COMMANDS_COMPLETE, 0) })
+    }
+
+    /// Checks if the gpu has reached the fence without waiting
+    pub fn is_signaled(&self) -> bool {
+        let mut status = 0;
+        unsafe { glGetSynciv(self.0, GL_SYNC_STATUS, 1, std::ptr::null_mut(), &mut status) };
+        status as u32 == GL_SIGNALED
+    }
+
+    /// Flushes the commands and blocks until the gpu has reached the fence or timeout has
+    /// passed, returns false if it timed out or failed
+    pub fn wait(&self, timeout: Duration) -> bool {
+        let result = unsafe {
+            glClientWaitSync(
+                self.0,
+                GL_SYNC_FLUSH_COMMANDS_BIT,
+                timeout.as_nanos().min(u64::MAX as u128) as u64,
+            )
+        };
+        result == GL_ALREADY_SIGNALED || result == GL_CONDITION_SATISFIED
+    }
+}
+
+impl Default for Fence {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Fence {
+    /// Deletes the sync object, the GL context must still exist
+    fn drop(&mut self) {
+        unsafe { glDeleteSync(self.0) }
+    }
+}
@@ -1,12 +1,50 @@
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+use std::thread;
 
-use super::{number::*, *};
+use super::{memory::*, number::*, *};
+use image::{Rgba, RgbaImage};
+use log::{error, warn};
 
 /// This is a texture error, it is used by [Texture]
 #[derive(Debug)]
 pub enum TextureError {
     /// This error happens when the name of the texture parameter dosen't exist
     UnknownTextureParameter(String),
+    /// This error happens when a region or its data dosen't fit in the texture
+    OutOfBounds(String),
+    /// This error happens when a texture file can't be read or isn't valid
+    InvalidFile(String),
+    /// This error happens when the format of a texture isn't supported by the lib or the driver
+    UnsupportedFormat(String),
+}
+
+/// How the channels of a texture are presented to the shader, see [Texture::set_swizzle]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Swizzle {
+    /// (r, g, b, a), the opengl default
+    Rgba,
+    /// (r, r, r, 1), a single channel texture shown as grayscale
+    Luminance,
+    /// (1, 1, 1, r), a single channel texture used as alpha e.g. masks and font atlases
+    Alpha,
+}
+
+impl Swizzle {
+    /// The value of GL_TEXTURE_SWIZZLE_RGBA
+    pub fn values(&self) -> &'static [i32] {
+        match self {
+            Swizzle::Rgba => &[
+                GL_RED as i32,
+                GL_GREEN as i32,
+                GL_BLUE as i32,
+                GL_ALPHA as i32,
+            ],
+            Swizzle::Luminance => &[GL_RED as i32, GL_RED as i32, GL_RED as i32, GL_ONE as i32],
+            Swizzle::Alpha => &[GL_ONE as i32, GL_ONE as i32, GL_ONE as i32, GL_RED as i32],
+        }
+    }
 }
 
 /// A type used by [Texture] to store the texture params and it's values
@@ -20,6 +58,12 @@ pub struct Texture {
     pub params: TextureParam,
     /// The texture type, it can exist and not exist
     pub texture_type: Option<u32>,
+    /// When true the color of images is multiplied by their alpha when they are uploaded, use it
+    /// with [BlendMode::PremultipliedAlpha](super::BlendMode::PremultipliedAlpha). It is off by default
+    pub premultiply_alpha: bool,
+    /// When true the rows of images are flipped when they are uploaded, so the top left of the
+    /// image is at uv (0, 1) like opengl expects instead of (0, 0). It is on by default
+    pub flip_v: bool,
 }
 impl Texture {
     /// Creates a new blank texture
@@ -56,6 +100,8 @@ impl Texture {
                     params
                 },
                 texture_type: None,
+                premultiply_alpha: false,
+                flip_v: true,
             }
         }
     }
@@ -155,13 +201,37 @@ impl Texture {
         }
     }
 
-    /// Sets the image to the texture
+    /// Checks if any of the wrap parameters is GL_CLAMP_TO_BORDER
+    fn is_clamped_to_border(&self) -> bool {
+        ["GL_TEXTURE_WRAP_S", "GL_TEXTURE_WRAP_T", "GL_TEXTURE_WRAP_R"]
+            .iter()
+            .any(|wrap| {
+                matches!(
+                    self.params[wrap],
+                    MultiSingularNumber::Number(Number::Integer(mode)) if mode == GL_CLAMP_TO_BORDER as i32
+                )
+            })
+    }
+
+    /// Sets the image to the texture, the rows are flipped if [Texture::flip_v] is true
     pub fn tex_2d(&self, lod: i32, img: DynamicImage) {
-        let img = match img.flipv() {
+        let img = if self.flip_v { img.flipv() } else { img };
+        let mut img = match img {
             ImageRgba8(img) => img,
             img => img.to_rgba8(),
         };
-        unsafe {
+        if self.premultiply_alpha {
+            premultiply_alpha(&mut img);
+        }
+        if !img.width().is_power_of_two() || !img.height().is_power_of_two() {
+            warn!(
+                "Texture {} is not a power of two ({}x{}), mipmaps and wrapping may be slower or unsupported",
+                self.id,
+                img.width(),
+                img.height()
+            );
+        }
+        crate::gl_check!(unsafe {
             glTexImage2D(
                 self.texture_type.unwrap(),
                 lod,
@@ -173,7 +243,190 @@ impl Texture {
                 GL_UNSIGNED_BYTE,
                 to_carray(&img as &[u8]).cast(),
             )
+        });
+        self.track_memory();
+    }
+
+    /// Sets a single channel image to the texture, it is stored as GL_R8
+    ///
+    /// Use [Texture::set_swizzle] so the shader dosen't have to read .r and broadcast it
+    pub fn tex_2d_r8(&self, lod: i32, img: DynamicImage) {
+        let img = if self.flip_v { img.flipv() } else { img }.to_luma8();
+        unsafe {
+            // rows of a single channel image aren't always 4 byte aligned
+            glPixelStorei(GL_UNPACK_ALIGNMENT, 1);
+            crate::gl_check!(glTexImage2D(
+                self.texture_type.unwrap(),
+                lod,
+                GL_R8 as i32,
+                img.width() as i32,
+                img.height() as i32,
+                0,
+                GL_RED,
+                GL_UNSIGNED_BYTE,
+                to_carray(&img as &[u8]).cast(),
+            ));
+            glPixelStorei(GL_UNPACK_ALIGNMENT, 4);
+        }
+        self.track_memory();
+    }
+
+    /// Sets how the channels of the texture are presented to the shader, the texture must be bound
+    ///
+    /// # Example
+    /// ```
+    /// texture.bind(GL_TEXTURE_2D);
+    /// texture.tex_2d_r8(0, mask);
+    /// texture.set_swizzle(Swizzle::Alpha); // the shader sees (1, 1, 1, mask)
+    /// ```
+    pub fn set_swizzle(&mut self, swizzle: Swizzle) {
+        let values = swizzle.values();
+        self.params.insert(
+            "GL_TEXTURE_SWIZZLE_RGBA",
+            MultiSingularNumber::Array(Array::Integer(values)),
+        );
+        unsafe {
+            glTexParameteriv(
+                self.texture_type.unwrap(),
+                GL_TEXTURE_SWIZZLE_RGBA,
+                to_carray(values),
+            )
+        }
+    }
+
+    /// Gets the swizzle of the texture from opengl as (r, g, b, a), the texture must be bound
+    pub fn get_swizzle(&self) -> [i32; 4] {
+        let mut out = [0; 4];
+        unsafe {
+            glGetTexParameteriv(
+                self.texture_type.unwrap(),
+                GL_TEXTURE_SWIZZLE_RGBA,
+                out.as_mut_ptr(),
+            )
+        }
+        out
+    }
+
+    /// Sets the color that is sampled outside of [0, 1] when the wrap mode is
+    /// GL_CLAMP_TO_BORDER, the texture must be bound
+    ///
+    /// It can also be set with the GL_TEXTURE_BORDER_COLOR parameter, e.g.
+    /// `MultiSingularNumber::Array(Array::Float(&[1.0, 1.0, 1.0, 1.0]))`
+    pub fn set_border_color(&self, color: [f32; 4]) {
+        unsafe {
+            glTexParameterfv(
+                self.texture_type.unwrap(),
+                GL_TEXTURE_BORDER_COLOR,
+                color.as_ptr(),
+            )
+        }
+    }
+
+    /// Gets the border color of the texture from opengl as (r, g, b, a), the texture must be bound
+    pub fn get_border_color(&self) -> [f32; 4] {
+        let mut out = [0.0; 4];
+        unsafe {
+            glGetTexParameterfv(
+                self.texture_type.unwrap(),
+                GL_TEXTURE_BORDER_COLOR,
+                out.as_mut_ptr(),
+            )
+        }
+        out
+    }
+
+    /// Sets the s, t and r wrap modes to GL_CLAMP_TO_BORDER with color as the border color, e.g.
+    /// for shadow maps where everything outside of the map is lit. The texture must be bound
+    ///
+    /// # Example
+    /// ```
+    /// shadow_map.depth.bind(GL_TEXTURE_2D);
+    /// shadow_map.depth.clamp_to_border([1.0, 1.0, 1.0, 1.0]);
+    /// ```
+    pub fn clamp_to_border(&self, color: [f32; 4]) {
+        for wrap in [GL_TEXTURE_WRAP_S, GL_TEXTURE_WRAP_T, GL_TEXTURE_WRAP_R] {
+            unsafe { glTexParameteri(self.texture_type.unwrap(), wrap, GL_CLAMP_TO_BORDER as i32) }
+        }
+        self.set_border_color(color);
+    }
+
+    /// Gets the (width, height) of a mipmap level of the texture from opengl, the texture must be bound
+    pub fn get_size(&self, lod: i32) -> (u32, u32) {
+        let (mut width, mut height) = (0, 0);
+        unsafe {
+            glGetTexLevelParameteriv(
+                self.texture_type.unwrap(),
+                lod,
+                GL_TEXTURE_WIDTH,
+                &mut width,
+            );
+            glGetTexLevelParameteriv(
+                self.texture_type.unwrap(),
+                lod,
+                GL_TEXTURE_HEIGHT,
+                &mut height,
+            );
+        }
+        (width as u32, height as u32)
+    }
+
+    /// Gets the internal format of a mipmap level of the texture from opengl e.g. GL_RGBA8, the
+    /// texture must be bound
+    pub fn get_internal_format(&self, lod: i32) -> u32 {
+        let mut internal_format = 0;
+        unsafe {
+            glGetTexLevelParameteriv(
+                self.texture_type.unwrap(),
+                lod,
+                GL_TEXTURE_INTERNAL_FORMAT,
+                &mut internal_format,
+            )
+        };
+        internal_format as u32
+    }
+
+    /// Replaces a rectangle of the texture, e.g. to pack glyphs into an atlas. The texture must
+    /// be bound
+    ///
+    /// data is RGBA, or a single byte per pixel for textures made with [Texture::tex_2d_r8].
+    /// Unlike [Texture::tex_2d] the rows are not flipped, the first row of data is at y
+    pub fn update_region(
+        &self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        data: &[u8],
+    ) -> Result<(), TextureError> {
+        let (format, channels) = if self.get_internal_format(0) == GL_R8 {
+            (GL_RED, 1)
+        } else {
+            (GL_RGBA, 4)
+        };
+        check_region(
+            (x, y, width, height),
+            self.get_size(0),
+            channels,
+            data.len(),
+        )?;
+
+        unsafe {
+            glPixelStorei(GL_UNPACK_ALIGNMENT, 1);
+            glTexSubImage2D(
+                self.texture_type.unwrap(),
+                0,
+                x as i32,
+                y as i32,
+                width as i32,
+                height as i32,
+                format,
+                GL_UNSIGNED_BYTE,
+                to_carray(data).cast(),
+            );
+            glPixelStorei(GL_UNPACK_ALIGNMENT, 4);
         }
+
+        Ok(())
     }
 
     /// Generate the mipmaps required by the texture
@@ -181,18 +434,41 @@ impl Texture {
         unsafe {
             glGenerateMipmap(self.texture_type.unwrap());
         }
+        self.track_memory();
+    }
+
+    /// Estimates how many bytes the texture takes on the gpu from the size and internal format of
+    /// every mipmap level, see [texture_memory_bytes]. The texture must be bound
+    pub fn gpu_memory_bytes(&self) -> usize {
+        (0..32)
+            .map(|lod| (self.get_size(lod), self.get_internal_format(lod)))
+            .take_while(|((width, height), _)| *width != 0 && *height != 0)
+            .map(|((width, height), format)| texture_memory_bytes(width, height, format, 1))
+            .sum()
+    }
+
+    /// Updates the size of the texture that is counted by [texture_memory], it is called after
+    /// every upload. The texture must be bound
+    pub fn track_memory(&self) {
+        track_texture(self.id, self.gpu_memory_bytes());
     }
 
     /// Creates a [Texture] object from an image
+    ///
+    /// Images from the image crate have their origin at the top left while opengl's is at the
+    /// bottom left, so flip_v should be true for them unless the uvs are already flipped, see
+    /// [Texture::flip_v]
     pub fn from_image(
         texture_unit: u32,
         texture_type: u32,
         params: TextureParam,
         lod: i32,
         img: DynamicImage,
+        flip_v: bool,
     ) -> Result<Texture, TextureError> {
         Texture::set_tex_unit(texture_unit);
         let mut texture = Texture::new();
+        texture.flip_v = flip_v;
         texture.bind(texture_type);
 
         for (param, value) in &params {
@@ -207,6 +483,14 @@ impl Texture {
         }
 
         texture.set_params();
+        if texture.is_clamped_to_border()
+            && matches!(
+                texture.params["GL_TEXTURE_BORDER_COLOR"],
+                MultiSingularNumber::None
+            )
+        {
+            warn!("The texture is clamped to the border but has no GL_TEXTURE_BORDER_COLOR, it will be transparent black");
+        }
 
         texture.tex_2d(lod, img);
         texture.generate_mipmaps();
@@ -214,14 +498,262 @@ impl Texture {
         Ok(texture)
     }
 
+    /// Starts loading a 2D texture in the background and returns a placeholder straight away
+    ///
+    /// The image is decoded on another thread and uploaded a few rows at a time by
+    /// [StreamedTexture::update], so large textures don't cause a hitch. Until then
+    /// [StreamedTexture::texture] is a 1x1 texture of the placeholder color, it stays the
+    /// placeholder if the image can't be loaded or has no pixels
+    ///
+    /// # Example
+    /// ```
+    /// let mut texture = Texture::stream(GL_TEXTURE0, params, "data/image.jpg", [128, 128, 128, 255])?;
+    ///
+    /// 'main_loop: loop {
+    ///     texture.update();
+    ///     texture.texture.bind(GL_TEXTURE_2D);
+    ///     // -snip-
+    /// }
+    /// ```
+    pub fn stream<P: AsRef<Path>>(
+        texture_unit: u32,
+        params: TextureParam,
+        path: P,
+        placeholder: [u8; 4],
+    ) -> Result<StreamedTexture, TextureError> {
+        let texture = Texture::from_image(
+            texture_unit,
+            GL_TEXTURE_2D,
+            params.clone(),
+            0,
+            ImageRgba8(RgbaImage::from_pixel(1, 1, Rgba(placeholder))),
+            true,
+        )?;
+
+        let path: PathBuf = path.as_ref().to_path_buf();
+        let (sender, receiver) = channel();
+        thread::spawn(move || {
+            let img = image::io::Reader::open(&path)
+                .map_err(|err| err.to_string())
+                .and_then(|reader| reader.decode().map_err(|err| err.to_string()))
+                .and_then(|img| {
+                    // an empty image has no rows to upload so it would never finish
+                    if img.width() == 0 || img.height() == 0 {
+                        Err("the image has no pixels".to_string())
+                    } else {
+                        Ok(img.flipv().to_rgba8())
+                    }
+                })
+                .map_err(|err| format!("Couldn't load {}: {}", path.display(), err));
+            // the stream might have been dropped already, in which case there is nothing to do
+            let _ = sender.send(img);
+        });
+
+        Ok(StreamedTexture {
+            texture,
+            texture_unit,
+            params,
+            receiver: Some(receiver),
+            pending: None,
+            rows_uploaded: 0,
+            rows_per_frame: 64,
+        })
+    }
+
     /// Deletes the texture
     pub fn delete(&self) {
+        forget_texture(self.id);
         unsafe { glDeleteTextures(1, &self.id) }
     }
 }
 
+/// Checks that the region (x, y, width, height) of [Texture::update_region] fits in a texture of
+/// size and that data_len is the size of the region with channels bytes per pixel
+fn check_region(
+    (x, y, width, height): (u32, u32, u32, u32),
+    (tex_width, tex_height): (u32, u32),
+    channels: usize,
+    data_len: usize,
+) -> Result<(), TextureError> {
+    let fits = match (x.checked_add(width), y.checked_add(height)) {
+        (Some(right), Some(bottom)) => right <= tex_width && bottom <= tex_height,
+        // a region that overflows can't fit either
+        _ => false,
+    };
+    if !fits {
+        return Err(TextureError::OutOfBounds(format!(
+            "The region {}x{} at ({}, {}) dosen't fit in the {}x{} texture",
+            width, height, x, y, tex_width, tex_height
+        )));
+    }
+
+    let expected = width as usize * height as usize * channels;
+    if data_len != expected {
+        return Err(TextureError::OutOfBounds(format!(
+            "The region {}x{} needs {} bytes but got {}",
+            width, height, expected, data_len
+        )));
+    }
+    Ok(())
+}
+
+/// Multiplies the color of every pixel by its alpha, so straight alpha images blend without dark
+/// halos when using [BlendMode::PremultipliedAlpha](super::BlendMode::PremultipliedAlpha)
+pub fn premultiply_alpha(img: &mut RgbaImage) {
+    for pixel in img.pixels_mut() {
+        let alpha = pixel[3] as u32;
+        for channel in &mut pixel.0[..3] {
+            *channel = ((*channel as u32 * alpha + 127) / 255) as u8;
+        }
+    }
+}
+
+/// A texture that is being loaded in the background, see [Texture::stream]
+pub struct StreamedTexture {
+    /// The texture to bind, it is the placeholder until the image is fully uploaded
+    pub texture: Texture,
+    /// How many rows of the image are uploaded per [StreamedTexture::update]
+    pub rows_per_frame: u32,
+    texture_unit: u32,
+    params: TextureParam,
+    receiver: Option<Receiver<Result<RgbaImage, String>>>,
+    pending: Option<(Texture, RgbaImage)>,
+    rows_uploaded: u32,
+}
+
+impl StreamedTexture {
+    /// Uploads the next chunk of the image, it should be called once per frame on the GL thread
+    ///
+    /// Returns true once the real image has been swapped in
+    pub fn update(&mut self) -> bool {
+        if self.pending.is_none() {
+            let img = match &self.receiver {
+                Some(receiver) => match receiver.try_recv() {
+                    Ok(Ok(img)) => img,
+                    Ok(Err(err)) => {
+                        error!("{}, keeping the placeholder", err);
+                        self.receiver = None;
+                        return false;
+                    }
+                    Err(TryRecvError::Empty) => return false,
+                    Err(TryRecvError::Disconnected) => {
+                        self.receiver = None;
+                        return false;
+                    }
+                },
+                None => return self.is_loaded(),
+            };
+            self.receiver = None;
+            self.pending = Some((self.allocate(&img), img));
+        }
+
+        let (texture, img) = self.pending.as_mut().unwrap();
+        let rows = self
+            .rows_per_frame
+            .max(1)
+            .min(img.height() - self.rows_uploaded);
+        let start = (self.rows_uploaded * img.width() * 4) as usize;
+
+        Texture::set_tex_unit(self.texture_unit);
+        texture.bind(GL_TEXTURE_2D);
+        unsafe {
+            glTexSubImage2D(
+                GL_TEXTURE_2D,
+                0,
+                0,
+                self.rows_uploaded as i32,
+                img.width() as i32,
+                rows as i32,
+                GL_RGBA,
+                GL_UNSIGNED_BYTE,
+                to_carray(&img.as_raw()[start..]).cast(),
+            )
+        }
+        self.rows_uploaded += rows;
+
+        if self.rows_uploaded < img.height() {
+            return false;
+        }
+
+        texture.generate_mipmaps();
+        let (texture, _) = self.pending.take().unwrap();
+        self.texture.delete();
+        self.texture = texture;
+        true
+    }
+
+    /// Checks if the real image has been swapped in
+    pub fn is_loaded(&self) -> bool {
+        self.receiver.is_none() && self.pending.is_none() && self.rows_uploaded > 0
+    }
+
+    /// Makes the texture the image is uploaded into
+    fn allocate(&self, img: &RgbaImage) -> Texture {
+        Texture::set_tex_unit(self.texture_unit);
+        let mut texture = Texture::new();
+        texture.bind(GL_TEXTURE_2D);
+        for (param, value) in &self.params {
+            if let Some(texture_param) = texture.params.get_mut(param) {
+                *texture_param = *value;
+            }
+        }
+        texture.set_params();
+
+        unsafe {
+            glTexImage2D(
+                GL_TEXTURE_2D,
+                0,
+                GL_RGBA as i32,
+                img.width() as i32,
+                img.height() as i32,
+                0,
+                GL_RGBA,
+                GL_UNSIGNED_BYTE,
+                std::ptr::null(),
+            )
+        }
+        texture
+    }
+}
+
 impl Default for Texture {
     fn default() -> Self {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    #[test]
+    fn a_region_must_fit_in_the_texture() {
+        assert!(check_region((0, 0, 4, 4), (4, 4), 4, 64).is_ok());
+        assert!(check_region((2, 2, 2, 2), (4, 4), 1, 4).is_ok());
+        assert!(check_region((3, 0, 2, 1), (4, 4), 4, 8).is_err());
+        assert!(check_region((0, 3, 1, 2), (4, 4), 4, 8).is_err());
+        // x + width overflows a u32
+        assert!(check_region((u32::MAX, 0, 2, 1), (4, 4), 4, 8).is_err());
+    }
+
+    #[test]
+    fn a_region_needs_a_pixel_for_every_texel() {
+        assert!(check_region((0, 0, 2, 2), (4, 4), 4, 15).is_err());
+        assert!(check_region((0, 0, 2, 2), (4, 4), 1, 16).is_err());
+    }
+
+    #[test]
+    fn premultiplying_at_half_alpha_halves_the_color() {
+        let mut img = RgbaImage::from_pixel(1, 1, Rgba([200, 100, 50, 128]));
+        premultiply_alpha(&mut img);
+        assert_eq!(img.get_pixel(0, 0).0, [100, 50, 25, 128]);
+    }
+
+    #[test]
+    fn premultiplying_keeps_opaque_pixels_and_clears_transparent_ones() {
+        let mut img = RgbaImage::from_raw(2, 1, vec![10, 20, 30, 255, 10, 20, 30, 0]).unwrap();
+        premultiply_alpha(&mut img);
+        assert_eq!(img.as_raw(), &vec![10, 20, 30, 255, 0, 0, 0, 0]);
+    }
+}
@@ -1,20 +1,351 @@
 use super::{shader::*, *};
+use log::warn;
+use nalgebra_glm::{Mat3, Mat4, Vec2, Vec3, Vec4};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
 
-/// A [Uniform object](https://www.khronos.org/opengl/wiki/Uniform_(GLSL))
+/// The GLSL type of every uniform location of the programs whose uniforms were checked, by the
+/// id of the program. A program is scanned the first time one of its uniforms is checked
+static UNIFORM_TYPES: Mutex<BTreeMap<u32, BTreeMap<i32, u32>>> = Mutex::new(BTreeMap::new());
+
+/// The locations of the struct fields set by [set_field] by the id of the program, so uploading
+/// a struct array every frame dosen't look up every field again
+static FIELD_LOCATIONS: Mutex<BTreeMap<u32, BTreeMap<String, i32>>> = Mutex::new(BTreeMap::new());
+
+/// This is a uniform error, it is used by [Uniform]
+#[derive(Debug)]
+pub enum UniformError {
+    /// This error happens when a uniform is set with a type that is different to its GLSL type
+    TypeMismatch(String),
+}
+
+/// The GLSL types that are set with glUniform1i
+const INT_TYPES: [u32; 9] = [
+    GL_INT,
+    GL_BOOL,
+    GL_SAMPLER_1D,
+    GL_SAMPLER_2D,
+    GL_SAMPLER_3D,
+    GL_SAMPLER_CUBE,
+    GL_SAMPLER_2D_SHADOW,
+    GL_SAMPLER_2D_ARRAY,
+    GL_SAMPLER_2D_MULTISAMPLE,
+];
+
+/// A Rust type that matches a GLSL struct so it can be uploaded field by field, see
+/// [Uniform::set_struct_array]
+///
+/// # Example
+/// ```
+/// // struct Fog { vec3 color; float density; };
+/// impl UniformStruct for Fog {
+///     fn upload(&self, program: &ShaderProgram, name: &str) {
+///         set_field(program, name, "color", self.color.as_slice());
+///         set_field(program, name, "density", &[self.density]);
+///     }
+/// }
+/// ```
+pub trait UniformStruct {
+    /// Sets every field of the struct called name in the program, the program must be in use
+    fn upload(&self, program: &ShaderProgram, name: &str);
+}
+
+/// Sets the float, vec2, vec3 or vec4 field of the struct uniform called name, fields that
+/// don't exist(or were optimized out) are skipped
+///
+/// The location of the field is looked up once per program and cached until it is deleted
+pub fn set_field(program: &ShaderProgram, name: &str, field: &str, values: &[f32]) {
+    if let Some(uniform) = find_cached(program, &format!("{}.{}", name, field)) {
+        uniform.set_uniform_f(values);
+    }
+}
+
+/// Finds a uniform like [Uniform::find] but caches its location in [FIELD_LOCATIONS]
+fn find_cached(program: &ShaderProgram, name: &str) -> Option<Uniform> {
+    let mut locations = FIELD_LOCATIONS.lock().unwrap();
+    let locations = locations.entry(program.0).or_default();
+    let location = match locations.get(name) {
+        Some(location) => *location,
+        None => {
+            let location = Uniform::find(program, name).map_or(-1, |uniform| uniform.0);
+            locations.insert(name.to_string(), location);
+            location
+        }
+    };
+    (location != -1).then_some(Uniform(location))
+}
+
+/// Forgets the uniform types and field locations of a program, a new program can get the same
+/// id once it is deleted
+pub(crate) fn forget_uniform_types(program: u32) {
+    UNIFORM_TYPES.lock().unwrap().remove(&program);
+    FIELD_LOCATIONS.lock().unwrap().remove(&program);
+}
+
+/// Gets the id of the program that is in use from opengl
+fn current_program() -> u32 {
+    let mut program = 0;
+    unsafe { glGetIntegerv(GL_CURRENT_PROGRAM, &mut program) };
+    program as u32
+}
+
+/// A [Uniform object](https://www.khronos.org/opengl/wiki/Uniform_(GLSL)), it is the location
+/// of the uniform. The location is -1 if the uniform dosen't exist
 pub struct Uniform(pub i32);
 impl Uniform {
     /// Creates a new uniform
+    ///
+    /// If the uniform dosen't exist in the program (or was optimized out) a warning is logged
+    /// and setting it will do nothing
     pub fn new(program: &ShaderProgram, name: &str) -> Self {
+        let location = unsafe { glGetUniformLocation(program.0, to_cstr(name).as_ptr().cast()) };
+        if location == -1 {
+            warn!(
+                "Uniform {} was not found in shader program {}",
+                name, program.0
+            );
+        }
+        Self(location)
+    }
+
+    /// Creates a new uniform if it exists in the program, unlike [Uniform::new] nothing is logged
+    /// when it dosen't
+    pub fn find(program: &ShaderProgram, name: &str) -> Option<Self> {
+        let location = unsafe { glGetUniformLocation(program.0, to_cstr(name).as_ptr().cast()) };
+        if location == -1 {
+            None
+        } else {
+            Some(Self(location))
+        }
+    }
+
+    /// The GLSL type of the uniform in the program that is in use e.g. GL_FLOAT_MAT4, it is None
+    /// if the uniform dosen't exist
+    ///
+    /// The types of all the uniforms of a program are read once, the first time one of them is
+    /// needed, and cached until the program is deleted
+    pub fn gl_type(&self) -> Option<u32> {
+        if self.0 == -1 {
+            return None;
+        }
+        let program = current_program();
+        let mut types = UNIFORM_TYPES.lock().unwrap();
+        types
+            .entry(program)
+            .or_insert_with(|| Uniform::active_types(program))
+            .get(&self.0)
+            .copied()
+    }
+
+    /// Reads the GLSL type of every uniform location of the program, the elements of an array all
+    /// have the type of the array
+    fn active_types(program: u32) -> BTreeMap<i32, u32> {
+        let mut count = 0;
+        let mut max_len = 0;
+        unsafe {
+            glGetProgramiv(program, GL_ACTIVE_UNIFORMS, &mut count);
+            glGetProgramiv(program, GL_ACTIVE_UNIFORM_MAX_LENGTH, &mut max_len);
+        }
+
+        let mut types = BTreeMap::new();
+        for i in 0..count.max(0) as u32 {
+            let mut v: Vec<u8> = Vec::with_capacity(max_len.max(1) as usize + 1);
+            let mut len_written = 0_i32;
+            let mut size = 0_i32;
+            let mut ty = 0_u32;
+            unsafe {
+                glGetActiveUniform(
+                    program,
+                    i,
+                    v.capacity().try_into().unwrap(),
+                    &mut len_written,
+                    &mut size,
+                    &mut ty,
+                    v.as_mut_ptr().cast(),
+                );
+                v.set_len(len_written.try_into().unwrap());
+                // glGetUniformLocation needs the terminating zero
+                v.push(0);
+            }
+
+            let location = unsafe { glGetUniformLocation(program, v.as_ptr().cast()) };
+            if location == -1 {
+                // uniforms in uniform blocks have no location
+                continue;
+            }
+            // arrays are reported once as name[0] and their elements have consecutive locations
+            for element in 0..size.max(1) {
+                types.insert(location + element, ty);
+            }
+        }
+        types
+    }
+
+    /// Finds the GLSL type of a uniform by looking through the active uniforms of the program
+    pub fn active_type(program: &ShaderProgram, name: &str) -> Option<u32> {
+        let mut count = 0;
+        let mut max_len = 0;
         unsafe {
-            Self(glGetUniformLocation(
-                program.0,
-                to_cstr(name).as_ptr().cast(),
-            ))
+            glGetProgramiv(program.0, GL_ACTIVE_UNIFORMS, &mut count);
+            glGetProgramiv(program.0, GL_ACTIVE_UNIFORM_MAX_LENGTH, &mut max_len);
+        }
+
+        (0..count.max(0) as u32).find_map(|i| {
+            let mut v: Vec<u8> = Vec::with_capacity(max_len.max(1).try_into().unwrap());
+            let mut len_written = 0_i32;
+            let mut size = 0_i32;
+            let mut ty = 0_u32;
+            unsafe {
+                glGetActiveUniform(
+                    program.0,
+                    i,
+                    v.capacity().try_into().unwrap(),
+                    &mut len_written,
+                    &mut size,
+                    &mut ty,
+                    v.as_mut_ptr().cast(),
+                );
+                v.set_len(len_written.try_into().unwrap());
+            }
+
+            // arrays are reported as name[0]
+            let active_name = String::from_utf8_lossy(&v);
+            if active_name == name || active_name.strip_suffix("[0]") == Some(name) {
+                Some(ty)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Checks that the GLSL type of the uniform is one of expected
+    ///
+    /// Uniforms that don't exist always pass as setting them does nothing
+    pub fn check_type(&self, expected: &[u32]) -> Result<(), UniformError> {
+        match self.gl_type() {
+            Some(ty) if !expected.contains(&ty) => Err(UniformError::TypeMismatch(format!(
+                "Uniform at location {} has type {:#x} but was set as {:#x?}",
+                self.0, ty, expected
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Sets the uniform as a float, the GLSL type must be float
+    pub fn set_float(&self, value: f32) -> Result<(), UniformError> {
+        self.check_type(&[GL_FLOAT])?;
+        unsafe { glUniform1f(self.0, value) };
+        Ok(())
+    }
+
+    /// Sets the uniform as a vec2, the GLSL type must be vec2
+    pub fn set_vec2(&self, value: &Vec2) -> Result<(), UniformError> {
+        self.check_type(&[GL_FLOAT_VEC2])?;
+        unsafe { glUniform2f(self.0, value.x, value.y) };
+        Ok(())
+    }
+
+    /// Sets the uniform as a vec3, the GLSL type must be vec3
+    pub fn set_vec3(&self, value: &Vec3) -> Result<(), UniformError> {
+        self.check_type(&[GL_FLOAT_VEC3])?;
+        unsafe { glUniform3f(self.0, value.x, value.y, value.z) };
+        Ok(())
+    }
+
+    /// Sets the uniform as a vec4, the GLSL type must be vec4
+    pub fn set_vec4(&self, value: &Vec4) -> Result<(), UniformError> {
+        self.check_type(&[GL_FLOAT_VEC4])?;
+        unsafe { glUniform4f(self.0, value.x, value.y, value.z, value.w) };
+        Ok(())
+    }
+
+    /// Sets the uniform as an int, the GLSL type must be int, bool or a sampler
+    pub fn set_int(&self, value: i32) -> Result<(), UniformError> {
+        self.check_type(&INT_TYPES)?;
+        unsafe { glUniform1i(self.0, value) };
+        Ok(())
+    }
+
+    /// Sets the uniform as an unsigned int, the GLSL type must be uint
+    pub fn set_uint(&self, value: u32) -> Result<(), UniformError> {
+        self.check_type(&[GL_UNSIGNED_INT])?;
+        unsafe { glUniform1ui(self.0, value) };
+        Ok(())
+    }
+
+    /// Sets the uniform as a mat3, the GLSL type must be mat3
+    pub fn set_mat3(&self, value: &Mat3) -> Result<(), UniformError> {
+        self.check_type(&[GL_FLOAT_MAT3])?;
+        unsafe { glUniformMatrix3fv(self.0, 1, GL_FALSE, value.as_ptr()) };
+        Ok(())
+    }
+
+    /// Sets the uniform as a mat4, the GLSL type must be mat4
+    pub fn set_mat4(&self, value: &Mat4) -> Result<(), UniformError> {
+        self.check_type(&[GL_FLOAT_MAT4])?;
+        unsafe { glUniformMatrix4fv(self.0, 1, GL_FALSE, value.as_ptr()) };
+        Ok(())
+    }
+
+    /// Reads the current value of a mat4 uniform back from the program that is in use, useful to
+    /// check what was actually uploaded when something renders wrong
+    pub fn get_matrix(&self) -> Mat4 {
+        let mut values = [0.0_f32; 16];
+        if self.0 != -1 {
+            unsafe { glGetUniformfv(current_program(), self.0, values.as_mut_ptr()) };
+        }
+        Mat4::from_column_slice(&values)
+    }
+
+    /// Reads the current value of a float, vecN or matN uniform back from the program that is in
+    /// use as N floats, e.g. `get_uniform_f::<3>()` for a vec3. It is all zeros if the uniform
+    /// dosen't exist
+    ///
+    /// N can't be more than 16, the floats of a mat4, it is checked when compiling
+    pub fn get_uniform_f<const N: usize>(&self) -> [f32; N] {
+        const { assert!(N <= 16, "a uniform has at most 16 floats") };
+        let mut values = [0.0_f32; 16];
+        if self.0 != -1 {
+            unsafe { glGetUniformfv(current_program(), self.0, values.as_mut_ptr()) };
+        }
+        let mut out = [0.0; N];
+        out.copy_from_slice(&values[..N]);
+        out
+    }
+
+    /// Sets every element of a uniform array of structs, e.g. `uniform Light lights[8];`, the
+    /// fields of element i are uploaded as `name[i].field` by [UniformStruct::upload]
+    ///
+    /// If the program has an int uniform called `name_count` it is set to the number of
+    /// elements. Elements past the size of the GLSL array are ignored
+    ///
+    /// # Example
+    /// ```
+    /// program.use_program();
+    /// Uniform::set_struct_array(&program, "lights", &[sun, lamp, torch]);
+    /// ```
+    pub fn set_struct_array<T: UniformStruct>(program: &ShaderProgram, name: &str, values: &[T]) {
+        for (i, value) in values.iter().enumerate() {
+            value.upload(program, &format!("{}[{}]", name, i));
+        }
+        if let Some(count) = find_cached(program, &format!("{}_count", name)) {
+            count.set_uniform_i(&[values.len() as i32]);
         }
     }
 
     /// Sets the uniform as float
     pub fn set_uniform_f(&self, values: &[f32]) {
+        if (1..=4).contains(&values.len()) {
+            debug_assert!(
+                self.check_type(
+                    &[GL_FLOAT, GL_FLOAT_VEC2, GL_FLOAT_VEC3, GL_FLOAT_VEC4]
+                        [values.len() - 1..values.len()]
+                )
+                .is_ok(),
+                "Uniform at location {} set with the wrong type",
+                self.0
+            );
+        }
         unsafe {
             if values.len() == 1 {
                 glUniform1f(self.0, values[0]);
@@ -33,6 +364,17 @@ impl Uniform {
 
     /// Sets the uniform as integer
     pub fn set_uniform_i(&self, values: &[i32]) {
+        if (1..=4).contains(&values.len()) {
+            debug_assert!(
+                match values.len() {
+                    1 => self.check_type(&INT_TYPES),
+                    len => self.check_type(&[[GL_INT_VEC2, GL_INT_VEC3, GL_INT_VEC4][len - 2]]),
+                }
+                .is_ok(),
+                "Uniform at location {} set with the wrong type",
+                self.0
+            );
+        }
         unsafe {
             if values.len() == 1 {
                 glUniform1i(self.0, values[0]);
@@ -51,6 +393,21 @@ impl Uniform {
 
     /// Sets the uniform as unsigned integer
     pub fn set_uniform_ui(&self, values: &[u32]) {
+        if (1..=4).contains(&values.len()) {
+            debug_assert!(
+                self.check_type(
+                    &[
+                        GL_UNSIGNED_INT,
+                        GL_UNSIGNED_INT_VEC2,
+                        GL_UNSIGNED_INT_VEC3,
+                        GL_UNSIGNED_INT_VEC4
+                    ][values.len() - 1..values.len()]
+                )
+                .is_ok(),
+                "Uniform at location {} set with the wrong type",
+                self.0
+            );
+        }
         unsafe {
             if values.len() == 1 {
                 glUniform1ui(self.0, values[0]);
@@ -1,4 +1,6 @@
-use super::*;
+use std::mem::size_of;
+
+use super::{buffer::*, *};
 
 /// Creates a [VAO](https://www.khronos.org/opengl/wiki/Client-Side_Vertex_Arrays) and is used to make [VBO](https://www.khronos.org/opengl/wiki/Vertex_Specification#Vertex_Buffer_Object)
 /// using the [Buffer] struct
@@ -24,4 +26,84 @@ impl VertexArray {
     pub fn clear_binding() {
         unsafe { glBindVertexArray(0) }
     }
+
+    /// Binds the VAO and buffer then points the attributes at the floats of buffer and sets
+    /// their divisors
+    ///
+    /// The attributes are interleaved in the order they are given, so the stride is the size of
+    /// all of them. Per vertex and per instance data are usually in different buffers, in which
+    /// case this is called once for each buffer
+    ///
+    /// # Example
+    /// ```
+    /// vao.set_layout(&vertices, &[Attribute::per_vertex(0, 3), Attribute::per_vertex(1, 2)]);
+    /// vao.set_layout(&offsets, &[Attribute::per_instance(2, 3)]);
+    /// unsafe { glDrawArraysInstanced(GL_TRIANGLES, 0, 36, 100) };
+    /// ```
+    pub fn set_layout(&self, buffer: &Buffer, attributes: &[Attribute]) {
+        self.bind();
+        buffer.bind(BufferType::Array);
+
+        let stride = attributes.iter().map(|attr| attr.size).sum::<i32>() * size_of::<f32>() as i32;
+        let mut offset = 0;
+        for attr in attributes {
+            unsafe {
+                glVertexAttribPointer(
+                    attr.location,
+                    attr.size,
+                    GL_FLOAT,
+                    GL_FALSE,
+                    stride,
+                    (offset * size_of::<f32>()) as *const _,
+                );
+                glEnableVertexAttribArray(attr.location);
+                glVertexAttribDivisor(attr.location, attr.divisor);
+            }
+            offset += attr.size as usize;
+        }
+    }
+
+    /// Gets the divisor of an attribute of the VAO, the VAO must be bound
+    pub fn get_divisor(&self, location: u32) -> u32 {
+        let mut out = 0;
+        unsafe { glGetVertexAttribiv(location, GL_VERTEX_ATTRIB_ARRAY_DIVISOR, &mut out) };
+        out as u32
+    }
+
+    /// Deletes the VAO
+    pub fn delete(&self) {
+        unsafe { glDeleteVertexArrays(1, &self.0) }
+    }
+}
+
+/// A float attribute of a vertex layout, see [VertexArray::set_layout]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Attribute {
+    /// The location of the attribute in the vertex shader
+    pub location: u32,
+    /// How many floats the attribute has, 1 to 4
+    pub size: i32,
+    /// How many instances use the same value, 0 means the attribute changes every vertex and 1
+    /// means it changes every instance
+    pub divisor: u32,
+}
+
+impl Attribute {
+    /// Creates an attribute that changes every vertex
+    pub fn per_vertex(location: u32, size: i32) -> Self {
+        Attribute {
+            location,
+            size,
+            divisor: 0,
+        }
+    }
+
+    /// Creates an attribute that changes every instance
+    pub fn per_instance(location: u32, size: i32) -> Self {
+        Attribute {
+            location,
+            size,
+            divisor: 1,
+        }
+    }
 }
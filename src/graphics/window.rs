@@ -0,0 +1,146 @@
+use std::ffi::CStr;
+
+use beryllium::{fermium, SdlGlAttr, WindowFlags, SDL};
+
+/// An extra window whose GL context shares textures, buffers and shaders with the main window
+///
+/// beryllium only allows a single [GlWindow](beryllium::GlWindow), so this is made with SDL
+/// directly. The context of the main window must be current when it is created
+///
+/// # Example
+/// ```
+/// let preview = SharedWindow::new("Preview", 320, 240)?; // created while the main window is current
+///
+/// // in the main loop
+/// preview.make_current();
+/// draw_preview();
+/// preview.swap_window();
+///
+/// world.env.make_current();
+/// world.render();
+/// world.env.win.swap_window();
+/// ```
+pub struct SharedWindow {
+    /// The SDL window
+    pub win: *mut fermium::SDL_Window,
+    /// The GL context of the window
+    pub ctx: fermium::SDL_GLContext,
+}
+
+impl SharedWindow {
+    /// Creates a window and a context shared with the current context, afterwards the current
+    /// context is made current again
+    pub fn new(title: &str, width: i32, height: i32) -> Result<Self, String> {
+        let title: Vec<u8> = title.bytes().chain(Some(0)).collect();
+        unsafe {
+            let (prev_win, prev_ctx) = current();
+            if prev_ctx.is_null() {
+                return Err("A context must be current to share it with a new window".to_string());
+            }
+
+            fermium::SDL_GL_SetAttribute(fermium::SDL_GL_SHARE_WITH_CURRENT_CONTEXT, 1);
+            let win = fermium::SDL_CreateWindow(
+                title.as_ptr().cast(),
+                fermium::SDL_WINDOWPOS_CENTERED_MASK as i32,
+                fermium::SDL_WINDOWPOS_CENTERED_MASK as i32,
+                width,
+                height,
+                fermium::SDL_WINDOW_OPENGL
+                    | fermium::SDL_WINDOW_SHOWN
+                    | fermium::SDL_WINDOW_ALLOW_HIGHDPI,
+            );
+            if win.is_null() {
+                fermium::SDL_GL_SetAttribute(fermium::SDL_GL_SHARE_WITH_CURRENT_CONTEXT, 0);
+                return Err(sdl_error());
+            }
+
+            let ctx = fermium::SDL_GL_CreateContext(win);
+            fermium::SDL_GL_SetAttribute(fermium::SDL_GL_SHARE_WITH_CURRENT_CONTEXT, 0);
+            if ctx.is_null() {
+                let err = sdl_error();
+                fermium::SDL_DestroyWindow(win);
+                return Err(err);
+            }
+
+            fermium::SDL_GL_MakeCurrent(prev_win, prev_ctx);
+            Ok(SharedWindow { win, ctx })
+        }
+    }
+
+    /// Makes the context of this window current so draw calls go to it
+    pub fn make_current(&self) {
+        unsafe { fermium::SDL_GL_MakeCurrent(self.win, self.ctx) };
+    }
+
+    /// Swaps the window buffers
+    pub fn swap_window(&self) {
+        unsafe { fermium::SDL_GL_SwapWindow(self.win) }
+    }
+
+    /// Gets the physical size of the draw area
+    pub fn get_drawable_size(&self) -> (i32, i32) {
+        let mut w = 0_i32;
+        let mut h = 0_i32;
+        unsafe { fermium::SDL_GL_GetDrawableSize(self.win, &mut w, &mut h) }
+        (w, h)
+    }
+}
+
+impl Drop for SharedWindow {
+    fn drop(&mut self) {
+        unsafe {
+            let (prev_win, prev_ctx) = current();
+            fermium::SDL_GL_DeleteContext(self.ctx);
+            fermium::SDL_DestroyWindow(self.win);
+            if prev_ctx != self.ctx {
+                fermium::SDL_GL_MakeCurrent(prev_win, prev_ctx);
+            }
+        }
+    }
+}
+
+/// Asks SDL for a multisampled default framebuffer with this many samples, 0 turns
+/// multisampling off. It must be called before the window is created
+///
+/// The driver may give fewer samples than requested (or none), so check
+/// [Enviroment::msaa_samples](crate::ECS::world::Enviroment::msaa_samples) afterwards
+///
+/// # Example
+/// ```
+/// request_msaa(&sdl, 4)?;
+/// let win = sdl.create_gl_window(/* -snip- */)?;
+/// ```
+pub fn request_msaa(sdl: &SDL, samples: u8) -> Result<(), String> {
+    sdl.gl_set_attribute(SdlGlAttr::MultisampleBuffers, (samples > 0) as i32)?;
+    sdl.gl_set_attribute(SdlGlAttr::MultisampleSamples, samples as i32)
+}
+
+/// The window flag that asks for a drawable at the full resolution of high DPI screens, beryllium
+/// has no constant for it. Without it the drawable is the size of the window in screen
+/// coordinates and is scaled up
+///
+/// # Example
+/// ```
+/// let win = sdl.create_gl_window(title, pos, w, h, WindowFlags::Shown | allow_high_dpi())?;
+/// ```
+pub fn allow_high_dpi() -> WindowFlags {
+    // WindowFlags is a transparent wrapper around the SDL flags
+    unsafe { std::mem::transmute::<u32, WindowFlags>(fermium::SDL_WINDOW_ALLOW_HIGHDPI) }
+}
+
+/// The current SDL window and GL context
+pub fn current() -> (*mut fermium::SDL_Window, fermium::SDL_GLContext) {
+    unsafe {
+        (
+            fermium::SDL_GL_GetCurrentWindow(),
+            fermium::SDL_GL_GetCurrentContext(),
+        )
+    }
+}
+
+/// The last SDL error message
+pub fn sdl_error() -> String {
+    unsafe { CStr::from_ptr(fermium::SDL_GetError()) }
+        .to_string_lossy()
+        .into_owned()
+}
@@ -14,6 +14,13 @@
 
 #![deny(missing_docs)]
 /// Module containing ECS stuff
+#[allow(non_snake_case)]
 pub mod ECS;
 /// Module containing all things related to [crate::graphics]
 pub mod graphics;
+/// Module containing game math that isn't in nalgebra_glm, e.g. [math::move_towards]
+pub mod math;
+/// Module containing the CPU profiler, see [profiler::ProfileTree] and [profile]
+pub mod profiler;
+/// Module containing the deterministic [rng::Rng]
+pub mod rng;
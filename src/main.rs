@@ -15,14 +15,15 @@ use image::DynamicImage;
 use lighthouse::{
     ECS::{
         camera::{CameraSettings, CameraSettingsBuilder, CameraTrait},
+        controller::{SixDofController, SmoothMovement},
+        event::EngineEvent,
         mouse::{MousePressed::*, StateOfMouse::*, *},
-        mesh::{
-            ControllableKey, ControllableMouse, Mesh, MeshTrait, Object, PosRot, VertexTrait,
-        },
-        world::{self, Enviroment, GameObjectTrait, World},
+        mesh::{Mesh, MeshBuilder, PosRot, VertexTrait},
+        object::Object,
+        world::{self, Enviroment, EnviromentBuilder, GameObjectTrait, World},
     },
     graphics::{buffer::*, shader::*, texture::*, uniform::*, vertex::*, *},
-    impl_posrot,
+    impl_posrot, impl_vertex,
 };
 use nalgebra_glm::*;
 use std::thread::sleep;
@@ -31,6 +32,7 @@ use std::{borrow::BorrowMut, fs};
 
 const WIDTH: u16 = 800;
 const HEIGHT: u16 = 600;
+const MSAA_SAMPLES: u8 = 4;
 
 #[derive(Copy, Clone)]
 struct Vertex {
@@ -44,30 +46,15 @@ impl Vertex {
     }
 }
 
-impl VertexTrait for Vertex {
-    const SIZE: u32 = 5;
-
-    fn as_list(&self) -> Vec<f32> {
-        let mut out = Vec::<f32>::new();
-        out.append(&mut Vec::from(<[f32; 3]>::from(self.vert)));
-        out.append(&mut Vec::from(<[f32; 2]>::from(self.tex_coord)));
-        out
-    }
-
-    fn get_vertex(&self, pos: Vec3, rot: Vec4) -> Self {
-        let mut out = Self::new(self.vert, self.tex_coord);
-
-        out.vert = rotate_vec3(&out.vert, rot.w, &rot.xyz()) + pos;
-
-        out
-    }
-}
+impl_vertex!(Vertex { vert: Vec3, tex_coord: Vec2 });
 
 struct Camera {
     pos: Vec3,
     rot: Vec4,
     settings: CameraSettings,
     uniform: String,
+    movement: SmoothMovement,
+    look: SixDofController,
 }
 
 impl Camera {
@@ -77,6 +64,8 @@ impl Camera {
             rot,
             settings,
             uniform,
+            movement: SmoothMovement::default(),
+            look: SixDofController::default(),
         }
     }
 }
@@ -87,6 +76,7 @@ impl Object<GameObject> for Camera {
     fn update(world: &mut World<GameObject>, _: u32) {
         Camera::matrix(&world.objects.camera);
         Camera::on_key(world);
+        Camera::on_mouse(world);
     }
 }
 
@@ -98,33 +88,41 @@ impl CameraTrait<GameObject> for Camera {
     fn get_camera_uniform(&self) -> String {
         self.uniform.clone()
     }
+
+    fn get_up(&self) -> Vec3 {
+        self.look.up()
+    }
 }
 
-impl ControllableKey<GameObject> for Camera {
+impl Camera {
     fn on_key(world: &mut World<GameObject>) {
-        for key in world.env.device.get_keys() {
+        let keys = world.env.device.get_keys();
+        let direction = SmoothMovement::key_direction(&keys);
+        let camera = &mut world.objects.camera;
+        camera
+            .movement
+            .update(direction, &mut camera.pos, world.env.delta_time());
+
+        // Q and E roll the camera, get_up passes the rolled up direction to the view matrix
+        let roll_speed = camera.look.roll_speed;
+        for key in keys {
             match key {
-                Keycode::W => world.objects.set_camera().set_pos().z += 0.01,
-                Keycode::A => world.objects.set_camera().set_pos().x += 0.01,
-                Keycode::S => world.objects.set_camera().set_pos().z -= 0.01,
-                Keycode::D => world.objects.set_camera().set_pos().x -= 0.01,
-                Keycode::LShift | Keycode::RShift => world.objects.set_camera().set_pos().y -= 0.01,
-                Keycode::Space => world.objects.set_camera().set_pos().y += 0.01,
+                Keycode::Q => camera.look.roll(-roll_speed),
+                Keycode::E => camera.look.roll(roll_speed),
                 _ => (),
             }
         }
     }
-}
 
-impl ControllableMouse<GameObject> for Camera {
     fn on_mouse(world: &mut World<GameObject>) {
+        let was_locked = matches!(world.env.mouse.state, Locked(_));
         if let Some(keys) = world
             .env
             .mouse
             .get_pressed_cooldown(Duration::from_millis(100))
         {
             keys.iter().for_each(|key| match key {
-                LeftMouse => world.env.mouse.state = Locked(world.env.win_size / 2.0),
+                LeftMouse => world.env.mouse.state = Locked(world.env.window_size() / 2.0),
                 RightMouse => world.env.mouse.state = Free,
                 _ => (),
             });
@@ -133,6 +131,14 @@ impl ControllableMouse<GameObject> for Camera {
         match world.env.mouse.state {
             Free => (),
             Locked(vec) => {
+                // the mouse is only at the center once it has been warped there
+                if was_locked {
+                    let delta = world.env.mouse.window_pos(&world.env) - vec;
+                    let camera = &mut world.objects.camera;
+                    camera.look.on_mouse(delta, &camera.settings);
+                    camera.rot = camera.look.forward().push(0.0);
+                }
+
                 let arr: [f32; 2] = vec.into();
                 let (x, y) = (arr[0], arr[1]);
 
@@ -150,12 +156,6 @@ struct Pyramid {
     mesh: Mesh<Vertex>,
 }
 
-impl MeshTrait<GameObject, Vertex> for Pyramid {
-    fn get_mesh(&self) -> &Mesh<Vertex> {
-        &self.mesh
-    }
-}
-
 impl Pyramid {
     fn new(pos: Vec3, rot: Vec4, mesh: Mesh<Vertex>) -> Self {
         Self { pos, rot, mesh }
@@ -192,6 +192,12 @@ impl GameObjectTrait for GameObject {
         }
     }
 
+    fn draw(&self) -> fn(world: &World<GameObject>) {
+        |_: &World<GameObject>| unsafe {
+            glDrawElements(GL_TRIANGLES, 48, GL_UNSIGNED_INT, 0 as *const _);
+        }
+    }
+
     fn get_camera(&self) -> &dyn CameraTrait<Self> {
         &self.camera
     }
@@ -218,15 +224,13 @@ fn main() {
 
     let index = vec![[0, 1, 4], [1, 2, 4], [2, 3, 4], [0, 3, 4]];
 
-    // Create a new device state
-    let device_state = DeviceState::new();
-    let mouse: Mouse = device_state.clone().into();
-
     let sdl = SDL::init(InitFlags::Everything).expect("couldn't start SDL");
     sdl.gl_set_attribute(SdlGlAttr::MajorVersion, 3).unwrap();
     sdl.gl_set_attribute(SdlGlAttr::MinorVersion, 3).unwrap();
     sdl.gl_set_attribute(SdlGlAttr::Profile, GlProfile::Core)
         .unwrap();
+    sdl.gl_set_attribute(SdlGlAttr::StencilSize, 8).unwrap();
+    window::request_msaa(&sdl, MSAA_SAMPLES).unwrap();
     #[cfg(target_os = "macos")]
     {
         sdl.gl_set_attribute(SdlGlAttr::Flags, ContextFlag::ForwardCompatible)
@@ -238,13 +242,14 @@ fn main() {
             WindowPosition::Centered,
             WIDTH.into(),
             HEIGHT.into(),
-            WindowFlags::Shown,
+            WindowFlags::Shown | window::allow_high_dpi(),
         )
         .expect("couldn't make a window and context");
     win.set_swap_interval(SwapInterval::Vsync);
 
     unsafe {
         load_gl_with(|f_name| win.get_proc_address(f_name));
+        extension::load_extensions_with(|f_name| win.get_proc_address(f_name));
     }
 
     clear_color(0.2, 0.3, 0.3, 1.0); // sets background color
@@ -253,18 +258,25 @@ fn main() {
     let pyramid = Pyramid::new(
         vec3(0.0, 0.0, 0.0),
         vec4(0.0, 1.0, 0.0, 0.0),
-        Mesh::new(vert, vec![3, 2], index).unwrap(),
+        MeshBuilder::new()
+            .vertices(vert)
+            .triangles(index)
+            .usage(BufferUsage::Dynamic)
+            .build()
+            .unwrap(),
     );
 
     let shader_program = ShaderProgram::from_vert_frag(vert_shader, frag_shader).unwrap();
     shader_program.use_program();
 
     // World
+    // the drawable size is in pixels, it is bigger than the window on high DPI screens
+    let (drawable_width, drawable_height) = win.get_drawable_size();
     let camera = Camera::new(
         vec3(0.0, 0.0, -2.0),
         vec4(0.0, 0.0, 1.0, 0.0),
         CameraSettingsBuilder::default()
-            .screen_size(vec2(WIDTH.into(), HEIGHT.into()))
+            .screen_size(vec2(drawable_width as f32, drawable_height as f32))
             .shader_program(shader_program)
             .build(),
         "camera_matrix".to_string(),
@@ -273,13 +285,11 @@ fn main() {
     let game_objects = GameObject { camera, pyramid };
 
     let mut world = World::<GameObject>::new(
-        Enviroment::new(
-            vec2(WIDTH.into(), HEIGHT.into()),
-            win,
-            shader_program,
-            device_state,
-            mouse,
-        ),
+        EnviromentBuilder::new()
+            .win(win)
+            .shader_program(shader_program)
+            .msaa_samples(MSAA_SAMPLES)
+            .build(),
         game_objects,
     );
 
@@ -298,7 +308,8 @@ fn main() {
       "GL_TEXTURE_WRAP_T" => number::MultiSingularNumber::Number(number::Number::Integer(GL_REPEAT as i32))
     },
     0,
-    img
+    img,
+    true
   ).unwrap();
 
     // uniforms
@@ -308,26 +319,8 @@ fn main() {
     enable(GL_DEPTH_TEST);
     world.update();
     // Location of the world
-    'main_loop: loop {
-        world.env.mouse.mouse = world.env.device.get_mouse();
+    world.run(|_| texture.bind(GL_TEXTURE_2D));
 
-        // handle events this frame
-        while let Some(event) = sdl.poll_events().and_then(Result::ok) {
-            match event {
-                Event::Quit(_) => break 'main_loop,
-                _ => (),
-            }
-        }
-
-        texture.bind(GL_TEXTURE_2D);
-        world.update();
-
-        // and then draw!
-        unsafe {
-            glClear(GL_COLOR_BUFFER_BIT);
-            glClear(GL_DEPTH_BUFFER_BIT);
-            glDrawElements(GL_TRIANGLES, 48, GL_UNSIGNED_INT, 0 as *const _);
-        }
-        world.env.win.swap_window();
-    }
+    texture.delete();
+    world.shutdown();
 }
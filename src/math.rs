@@ -0,0 +1,206 @@
+use nalgebra_glm::*;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// The up axis of the world, see [set_up_axis]
+static UP_AXIS: AtomicU8 = AtomicU8::new(UpAxis::Y as u8);
+
+/// Which world axis points up, cameras and controllers use it for their up vector, jumping and
+/// the ground plane. It is [UpAxis::Y] by default
+///
+/// Input from the keys is in the axes of [UpAxis::Y]: +x is left, +y is up and +z is forward,
+/// [UpAxis::from_input] turns it into world space
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UpAxis {
+    /// +y is up and +z is forward, like opengl
+    #[default]
+    Y,
+    /// +z is up and +y is forward, like Blender
+    Z,
+}
+
+impl UpAxis {
+    /// The up direction
+    pub fn up(self) -> Vec3 {
+        match self {
+            UpAxis::Y => vec3(0.0, 1.0, 0.0),
+            UpAxis::Z => vec3(0.0, 0.0, 1.0),
+        }
+    }
+
+    /// The forward direction of an unrotated camera
+    pub fn forward(self) -> Vec3 {
+        match self {
+            UpAxis::Y => vec3(0.0, 0.0, 1.0),
+            UpAxis::Z => vec3(0.0, 1.0, 0.0),
+        }
+    }
+
+    /// The left direction of an unrotated camera, up cross forward
+    pub fn left(self) -> Vec3 {
+        self.up().cross(&self.forward())
+    }
+
+    /// Turns input(+x left, +y up, +z forward) into world space
+    pub fn from_input(self, input: Vec3) -> Vec3 {
+        self.left() * input.x + self.up() * input.y + self.forward() * input.z
+    }
+}
+
+/// Gets the up axis of the world, see [set_up_axis]
+pub fn up_axis() -> UpAxis {
+    match UP_AXIS.load(Ordering::Relaxed) {
+        x if x == UpAxis::Z as u8 => UpAxis::Z,
+        _ => UpAxis::Y,
+    }
+}
+
+/// Sets the up axis of the world, it is global since cameras and controllers don't have the
+/// world. Prefer [World::set_up_axis](crate::ECS::world::World::set_up_axis)
+pub fn set_up_axis(axis: UpAxis) {
+    UP_AXIS.store(axis as u8, Ordering::Relaxed);
+}
+
+/// Moves current toward target by at most max_delta, it returns target once it is within
+/// max_delta
+///
+/// # Example
+/// ```
+/// // move at 2 units per second no matter how far away the target is
+/// pos = move_towards(&pos, &target, 2.0 * delta_time);
+/// ```
+pub fn move_towards(current: &Vec3, target: &Vec3, max_delta: f32) -> Vec3 {
+    let offset = target - current;
+    let distance = offset.norm();
+    if distance <= max_delta || distance == 0.0 {
+        *target
+    } else {
+        current + offset / distance * max_delta
+    }
+}
+
+/// Moves the number current toward target by at most max_delta
+pub fn move_towards_f32(current: f32, target: f32, max_delta: f32) -> f32 {
+    if (target - current).abs() <= max_delta {
+        target
+    } else {
+        current + (target - current).signum() * max_delta
+    }
+}
+
+/// The angle between two vectors in radians from 0 to PI, it is 0 if either is zero
+pub fn angle_between(a: &Vec3, b: &Vec3) -> f32 {
+    let lengths = a.norm() * b.norm();
+    if lengths == 0.0 {
+        0.0
+    } else {
+        // rounding can push the cosine slightly outside -1..1
+        (a.dot(b) / lengths).clamp(-1.0, 1.0).acos()
+    }
+}
+
+/// Removes the part of v along normal so it lies on the plane, e.g. to slide along a wall.
+/// normal dosen't have to be normalized
+pub fn project_on_plane(v: &Vec3, normal: &Vec3) -> Vec3 {
+    let length_squared = normal.norm_squared();
+    if length_squared == 0.0 {
+        *v
+    } else {
+        v - normal * (v.dot(normal) / length_squared)
+    }
+}
+
+/// Reflects v off a surface with normal like a ball bouncing off a wall, normal dosen't have to
+/// be normalized
+///
+/// # Example
+/// ```
+/// // a ray going down and forward bounces up off the floor
+/// assert_eq!(reflect(&vec3(1.0, -1.0, 0.0), &vec3(0.0, 1.0, 0.0)), vec3(1.0, 1.0, 0.0));
+/// ```
+pub fn reflect(v: &Vec3, normal: &Vec3) -> Vec3 {
+    if normal.norm_squared() == 0.0 {
+        return *v;
+    }
+    let normal = normal.normalize();
+    v - normal * (2.0 * v.dot(&normal))
+}
+
+/// A unit vector perpendicular to v, which one is arbitrary but it is always the same for the
+/// same v. It is +x if v is zero
+pub fn any_perpendicular(v: &Vec3) -> Vec3 {
+    if v.norm_squared() == 0.0 {
+        return vec3(1.0, 0.0, 0.0);
+    }
+    // cross with the axis v is least along so the result can't be zero
+    let axis = if v.x.abs() < v.y.abs() && v.x.abs() < v.z.abs() {
+        vec3(1.0, 0.0, 0.0)
+    } else if v.y.abs() < v.z.abs() {
+        vec3(0.0, 1.0, 0.0)
+    } else {
+        vec3(0.0, 0.0, 1.0)
+    };
+    v.cross(&axis).normalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn input_is_turned_into_world_space() {
+        let input = vec3(1.0, 2.0, 3.0);
+        assert_eq!(UpAxis::Y.from_input(input), vec3(1.0, 2.0, 3.0));
+        // +z up and +y forward leaves -x on the left
+        assert_eq!(UpAxis::Z.from_input(input), vec3(-1.0, 3.0, 2.0));
+    }
+
+    #[test]
+    fn any_perpendicular_is_a_perpendicular_unit_vector() {
+        for v in [
+            vec3(1.0, 0.0, 0.0),
+            vec3(0.0, -2.0, 0.0),
+            vec3(0.0, 0.0, 3.0),
+            vec3(1.0, 1.0, 1.0),
+            vec3(-0.2, 5.0, 0.1),
+        ] {
+            let perpendicular = any_perpendicular(&v);
+            assert!((perpendicular.norm() - 1.0).abs() < 1e-5);
+            assert!(perpendicular.dot(&v).abs() < 1e-5);
+            assert_eq!(any_perpendicular(&v), perpendicular);
+        }
+        assert_eq!(any_perpendicular(&Vec3::zeros()), vec3(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn reflect_bounces_off_a_plane() {
+        // a ball falling at 45 degrees onto the floor, the normal dosen't have to be unit length
+        let bounced = reflect(&vec3(1.0, -1.0, 0.0), &vec3(0.0, 2.0, 0.0));
+        assert_eq!(bounced, vec3(1.0, 1.0, 0.0));
+        // moving along the plane isn't changed
+        assert_eq!(
+            reflect(&vec3(3.0, 0.0, 1.0), &vec3(0.0, 1.0, 0.0)),
+            vec3(3.0, 0.0, 1.0)
+        );
+        assert_eq!(
+            reflect(&vec3(1.0, 2.0, 3.0), &Vec3::zeros()),
+            vec3(1.0, 2.0, 3.0)
+        );
+    }
+
+    #[test]
+    fn move_towards_stops_at_the_target() {
+        let target = vec3(3.0, 4.0, 0.0);
+        assert_eq!(
+            move_towards(&Vec3::zeros(), &target, 1.0),
+            vec3(0.6, 0.8, 0.0)
+        );
+        assert_eq!(move_towards(&Vec3::zeros(), &target, 5.0), target);
+        assert_eq!(move_towards(&Vec3::zeros(), &target, 100.0), target);
+        assert_eq!(move_towards(&target, &target, 1.0), target);
+
+        assert_eq!(move_towards_f32(0.0, 2.0, 0.5), 0.5);
+        assert_eq!(move_towards_f32(0.0, -2.0, 0.5), -0.5);
+        assert_eq!(move_towards_f32(1.9, 2.0, 0.5), 2.0);
+        assert_eq!(move_towards_f32(2.1, 2.0, 0.5), 2.0);
+    }
+}
@@ -0,0 +1,216 @@
+use std::cell::RefCell;
+use std::time::{Duration, Instant};
+
+thread_local! {
+    /// The scopes of the frame that is being profiled on this thread
+    static PROFILER: RefCell<Profiler> = RefCell::new(Profiler::default());
+}
+
+#[derive(Default)]
+struct Profiler {
+    /// The scopes that are open, the innermost one is last
+    open: Vec<ProfileNode>,
+    /// The finished scopes of the current frame
+    frame: ProfileTree,
+    /// The scopes of the last finished frame
+    last_frame: ProfileTree,
+}
+
+/// A named scope in a [ProfileTree], scopes with the same name and parent are added together
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileNode {
+    /// The name given to [profile](crate::profile)
+    pub name: &'static str,
+    /// The time spent in the scope in the frame, it includes the time of its children
+    pub time: Duration,
+    /// How many times the scope was entered in the frame
+    pub calls: u32,
+    /// The scopes that were entered inside of this one
+    pub children: Vec<ProfileNode>,
+}
+
+impl ProfileNode {
+    fn new(name: &'static str) -> Self {
+        ProfileNode {
+            name,
+            time: Duration::ZERO,
+            calls: 0,
+            children: Vec::new(),
+        }
+    }
+
+    /// The time spent in the scope that wasn't spent in any of its children
+    pub fn self_time(&self) -> Duration {
+        let children: Duration = self.children.iter().map(|child| child.time).sum();
+        self.time.saturating_sub(children)
+    }
+
+    /// Finds a direct child by its name
+    pub fn child(&self, name: &str) -> Option<&ProfileNode> {
+        self.children.iter().find(|child| child.name == name)
+    }
+}
+
+/// Adds node to the scopes in nodes, merging it with the one with the same name
+fn merge(nodes: &mut Vec<ProfileNode>, node: ProfileNode) {
+    match nodes.iter_mut().find(|other| other.name == node.name) {
+        Some(other) => {
+            other.time += node.time;
+            other.calls += node.calls;
+            for child in node.children {
+                merge(&mut other.children, child);
+            }
+        }
+        None => nodes.push(node),
+    }
+}
+
+/// The CPU time spent in every [profile](crate::profile) scope of a frame, nested like the
+/// scopes were
+///
+/// # Example
+/// ```
+/// let profile = world.cpu_profile();
+/// if let Some(update) = profile.find(&["update", "physics"]) {
+///     println!("physics took {:?} of which {:?} was its own", update.time, update.self_time());
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProfileTree {
+    /// The outermost scopes of the frame
+    pub roots: Vec<ProfileNode>,
+}
+
+impl ProfileTree {
+    /// Finds a scope by the names of the scopes it was nested in and its own name
+    pub fn find(&self, path: &[&str]) -> Option<&ProfileNode> {
+        let (first, rest) = path.split_first()?;
+        let mut node = self.roots.iter().find(|root| root.name == *first)?;
+        for name in rest {
+            node = node.child(name)?;
+        }
+        Some(node)
+    }
+
+    /// The time spent in all the outermost scopes
+    pub fn total(&self) -> Duration {
+        self.roots.iter().map(|root| root.time).sum()
+    }
+
+    /// Checks if no scopes were entered
+    pub fn is_empty(&self) -> bool {
+        self.roots.is_empty()
+    }
+}
+
+/// Times a scope until it is dropped, it is made by [profile](crate::profile)
+#[must_use = "the scope ends as soon as the guard is dropped"]
+pub struct ProfileScope {
+    start: Instant,
+}
+
+impl ProfileScope {
+    /// Opens a scope inside of the innermost open scope
+    pub fn new(name: &'static str) -> Self {
+        PROFILER.with(|profiler| profiler.borrow_mut().open.push(ProfileNode::new(name)));
+        ProfileScope {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Drop for ProfileScope {
+    fn drop(&mut self) {
+        let elapsed = self.start.elapsed();
+        PROFILER.with(|profiler| {
+            let mut profiler = profiler.borrow_mut();
+            let Some(mut node) = profiler.open.pop() else {
+                return;
+            };
+            node.time += elapsed;
+            node.calls += 1;
+            match profiler.open.last_mut() {
+                Some(parent) => merge(&mut parent.children, node),
+                None => merge(&mut profiler.frame.roots, node),
+            }
+        });
+    }
+}
+
+/// Ends the frame of this thread, the scopes of the frame become [last_frame] and the next frame
+/// starts empty. Scopes that are still open are counted in the next frame
+///
+/// [World::run](crate::ECS::world::World::run) calls it after every frame
+pub fn end_frame() {
+    PROFILER.with(|profiler| {
+        let mut profiler = profiler.borrow_mut();
+        profiler.last_frame = std::mem::take(&mut profiler.frame);
+    });
+}
+
+/// The scopes of the last frame of this thread that was ended with [end_frame]
+pub fn last_frame() -> ProfileTree {
+    PROFILER.with(|profiler| profiler.borrow().last_frame.clone())
+}
+
+#[macro_export]
+/// Times the rest of the block it is in as a scope of the CPU profile, scopes opened inside of
+/// it become its children, see [ProfileTree](crate::profiler::ProfileTree)
+///
+/// # Example
+/// ```
+/// fn update(world: &mut World<GameObject>) {
+///     profile!("physics");
+///     step_bodies();
+///     {
+///         profile!("collisions");
+///         resolve_collisions();
+///     }
+/// }
+/// ```
+macro_rules! profile {
+    ($name: expr) => {
+        let _profile_scope = $crate::profiler::ProfileScope::new($name);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread::sleep;
+
+    #[test]
+    fn child_scopes_are_contained_in_their_parent() {
+        {
+            crate::profile!("update");
+            sleep(Duration::from_millis(5));
+            for _ in 0..2 {
+                crate::profile!("physics");
+                sleep(Duration::from_millis(10));
+            }
+        }
+        end_frame();
+
+        let tree = last_frame();
+        let update = tree.find(&["update"]).unwrap();
+        let physics = tree.find(&["update", "physics"]).unwrap();
+        assert_eq!(update.calls, 1);
+        assert_eq!(physics.calls, 2);
+        assert!(physics.time >= Duration::from_millis(20));
+        assert!(update.time >= physics.time + Duration::from_millis(5));
+        assert_eq!(update.self_time(), update.time - physics.time);
+        assert_eq!(tree.total(), update.time);
+    }
+
+    #[test]
+    fn a_frame_starts_empty() {
+        {
+            crate::profile!("draw");
+        }
+        end_frame();
+        assert!(!last_frame().is_empty());
+
+        end_frame();
+        assert!(last_frame().is_empty());
+    }
+}
@@ -0,0 +1,102 @@
+use nalgebra_glm::{vec3, Vec3};
+
+/// A small deterministic random number generator ([PCG32](https://www.pcg-random.org)) for
+/// procedural content
+///
+/// The same seed always gives the same sequence on every platform, so a seeded run is
+/// reproducible
+///
+/// # Example
+/// ```
+/// let mut rng = Rng::seed(42);
+///
+/// let height = rng.range(0.0, 10.0);
+/// let direction = rng.unit_vec3();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    const MULTIPLIER: u64 = 6364136223846793005;
+    const INCREMENT: u64 = 1442695040888963407;
+
+    /// Creates a new rng from a seed
+    pub fn seed(seed: u64) -> Self {
+        let mut rng = Rng { state: 0 };
+        rng.next_u32();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.next_u32();
+        rng
+    }
+
+    /// Returns a random u32
+    pub fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old
+            .wrapping_mul(Self::MULTIPLIER)
+            .wrapping_add(Self::INCREMENT);
+
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        let rot = (old >> 59) as u32;
+        xorshifted.rotate_right(rot)
+    }
+
+    /// Returns a random f32 in 0.0..1.0
+    pub fn next_f32(&mut self) -> f32 {
+        // 24 bits is all the precision an f32 has
+        (self.next_u32() >> 8) as f32 / (1 << 24) as f32
+    }
+
+    /// Returns a random f32 in min..max
+    pub fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next_f32()
+    }
+
+    /// Returns a random direction with a length of 1
+    pub fn unit_vec3(&mut self) -> Vec3 {
+        let z = self.range(-1.0, 1.0);
+        let angle = self.range(0.0, std::f32::consts::TAU);
+        let radius = (1.0 - z * z).sqrt();
+        vec3(radius * angle.cos(), radius * angle.sin(), z)
+    }
+}
+
+impl Default for Rng {
+    fn default() -> Self {
+        Self::seed(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_same_seed_gives_the_same_sequence() {
+        let (mut a, mut b) = (Rng::seed(42), Rng::seed(42));
+        for _ in 0..100 {
+            assert_eq!(a.next_u32(), b.next_u32());
+        }
+        assert_eq!(a.unit_vec3(), b.unit_vec3());
+    }
+
+    #[test]
+    fn different_seeds_diverge() {
+        let (mut a, mut b) = (Rng::seed(1), Rng::seed(2));
+        let a: Vec<u32> = (0..8).map(|_| a.next_u32()).collect();
+        let b: Vec<u32> = (0..8).map(|_| b.next_u32()).collect();
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn ranges_stay_in_their_bounds() {
+        let mut rng = Rng::seed(7);
+        for _ in 0..1000 {
+            let value = rng.range(-2.0, 3.0);
+            assert!((-2.0..3.0).contains(&value));
+            assert!((rng.unit_vec3().norm() - 1.0).abs() < 1e-5);
+        }
+    }
+}